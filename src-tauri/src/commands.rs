@@ -1,112 +1,622 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
-use std::sync::LazyLock;
+use std::path::{Path, PathBuf};
+
+use crate::config::{chrono_now, merge_presets, validate_preset_name, load_review_prompts, save_custom_review_prompt, delete_custom_review_prompt, load_api_config, save_api_config, load_project_file_config, ConfigState, COMMIT_MESSAGE_PROMPT, PRESET_BUNDLE_VERSION, PR_DESCRIPTION_PROMPT};
+use crate::i18n::project_type_label;
+use crate::metadata::MetadataCacheState;
+use crate::monorepo::{detect_monorepo, tag_packages};
+use crate::scan_cache::{load_cached_scan, save_cached_scan};
+use crate::security::{SecretCacheState, SecretScanState};
+use crate::packer::{build_pack_content_with_options, build_pack_content_extended};
+use crate::plugins::{
+    apply_plugin_rules, check_plugin_rules, command_fingerprint, command_line, download_plugin, fetch_registry,
+    enabled_plugins, get_plugin_header_templates, get_plugins_dir, load_plugins, plugin_matches,
+    plugin_evidence_with, plugin_file_name, plugin_problems, plugin_secret_rules, run_plugin_commands, validate_plugin, write_plugin, CommandOutput, CommandStage, GlobMatches, PluginDef,
+    RegistryEntry,
+};
+use crate::scanner::{build_file_tree_reporting, check_changed, prune_unchanged, ChangeFilter, build_file_tree_with_options, collect_file_paths, count_files, detect_project_type_with_plugins, detect_project_types, list_directory, passes_scan_rules, preset_entry, resolve_preset_entries, set_tests_checked, validate_globs, ScanCacheState};
+use crate::stats::{compute_churn_stats, compute_project_stats};
+use crate::wasm::WasmMetadata;
+use crate::tokens::{annotate_tree, estimate_cost, price_table, rollup_by_directory, TokenCacheState};
+use crate::workspace::{build_workspace_tree, root_labels, validate_roots};
+use tauri::{Emitter, Manager};
+use crate::types::{ApiConfig, AppSettings, ChangeSummary, ChurnStats, ConfigRecovery, Contributor, CostEstimate, DetailedTokenEstimate, DirTokenStat, DotenvPolicy, ExportFormat, FileNode, FileTokenEstimate, GitHookOptions, Locale, PackOptions, PackResult, PluginCommandInfo, PluginTestReport, Preset, PresetBundle, ProjectConfig, ProjectFileConfig, ProjectMetadata, ProjectStats, ProjectType, ReportFormat, ReviewPrompt, ScanOptions, ScanProgress, ScanResult, SecretBaselineEntry, SecretPolicy, SecretScanProgress, SecurityReport, TokenAnnotateProgress, TokenEstimate, Tokenizer, TreeDiff, Workspace, WorkspaceRoot};
+
+/// Names of the plugins enabled for the project; None when every installed plugin applies
+fn project_enabled_plugins(config: &ConfigState, project_path: &str) -> Option<Vec<String>> {
+    config.read(|c| c.projects.get(project_path).and_then(|p| p.enabled_plugins.clone()))
+}
 
-use tiktoken_rs::CoreBPE;
+// CodePack: 项目启用的插件；未设置启用列表时全部插件生效
+fn project_plugins(config: &ConfigState, project_path: &str) -> Vec<PluginDef> {
+    enabled_plugins(load_plugins(), project_enabled_plugins(config, project_path).as_deref())
+}
 
-use crate::config::{chrono_now, load_app_config, save_app_config, load_review_prompts, save_custom_review_prompt, delete_custom_review_prompt, load_api_config, save_api_config};
-use crate::metadata::extract_metadata;
-use crate::packer::{build_pack_content_with_limit, build_pack_content_extended};
+/// Shared defaults committed at the project root; a missing or invalid file means none
+fn project_file_config(root: &Path) -> ProjectFileConfig {
+    load_project_file_config(root).ok().flatten().unwrap_or_default()
+}
 
-static BPE: LazyLock<CoreBPE> = LazyLock::new(|| {
-    tiktoken_rs::cl100k_base().expect("failed to load cl100k_base tokenizer")
-});
-use crate::plugins::{
-    get_plugin_excluded_dirs, get_plugin_source_extensions, get_plugins_dir, load_plugins,
-    PluginDef,
-};
-use crate::scanner::{build_file_tree, count_files, detect_project_type_with_plugins};
-use crate::stats::compute_project_stats;
-use tauri::Emitter;
-use crate::types::{ApiConfig, ExportFormat, PackResult, ProjectConfig, ProjectStats, ReviewPrompt, ScanProgress, ScanResult, TokenEstimate};
+/// Merges the user's default excludes and the project's codepack.toml into the scan options
+fn apply_config_rules(mut options: ScanOptions, root: &Path, config: &ConfigState) -> ScanOptions {
+    let project = project_file_config(root);
+    options.extra_excludes.extend(config.read(|c| c.settings.default_excludes.clone()));
+    options.extra_excludes.extend(project.excludes);
+    options.extra_extensions.extend(normalize_extensions(Some(project.extra_extensions)));
+    options
+}
+
+/// Defaults for options a pack request leaves unset: codepack.toml first, then the global settings
+struct PackDefaults {
+    format: Option<ExportFormat>,
+    max_file_bytes: Option<u64>,
+    mask_secrets: bool,
+    /// Plugins enabled for the project, for secret rules
+    secret_plugins: Option<Vec<String>>,
+    /// From the plugins that match the project
+    header_templates: Vec<String>,
+    /// Output of the approved pre-pack plugin commands
+    hooks: CommandOutput,
+}
+
+fn pack_defaults(project_path: &str, config: &ConfigState) -> PackDefaults {
+    let root = Path::new(project_path);
+    let project = project_file_config(root);
+    let settings = config.read(|c| c.settings.clone());
+    let plugins = project_plugins(config, project_path);
+    PackDefaults {
+        format: project.format.or(settings.default_format),
+        max_file_bytes: project.max_file_bytes.or(settings.default_max_file_bytes),
+        mask_secrets: settings.secret_policy == SecretPolicy::Mask,
+        secret_plugins: project_enabled_plugins(config, project_path),
+        header_templates: get_plugin_header_templates(&plugins, root),
+        hooks: run_plugin_commands(&plugins, root, CommandStage::PrePack, &approved_commands(config, project_path)),
+    }
+}
+
+/// Plugin commands the user approved for this project
+fn approved_commands(config: &ConfigState, project_path: &str) -> Vec<String> {
+    config.read(|c| {
+        c.projects
+            .get(project_path)
+            .map(|p| p.approved_plugin_commands.clone())
+            .unwrap_or_default()
+    })
+}
+
+fn with_hook_metadata(mut metadata: ProjectMetadata, hook: WasmMetadata) -> ProjectMetadata {
+    hook.apply(&mut metadata);
+    metadata
+}
+
+/// Accepts `.proto` as well as `proto`
+fn normalize_extensions(extensions: Option<Vec<String>>) -> Vec<String> {
+    extensions
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| e.trim().trim_start_matches('.').to_string())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+/// Scan options from the filter arguments the scan commands share; the shallow scans pass None
+/// for the limits that don't apply to a single level
+#[allow(clippy::too_many_arguments)]
+fn scan_options(
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+    max_depth: Option<usize>,
+    max_files: Option<u32>,
+    include_all: Option<bool>,
+) -> ScanOptions {
+    ScanOptions {
+        extra_excludes: custom_excludes.unwrap_or_default(),
+        extra_extensions: normalize_extensions(extra_extensions),
+        include_globs: include_globs.unwrap_or_default(),
+        exclude_globs: exclude_globs.unwrap_or_default(),
+        include_hidden,
+        follow_symlinks: follow_symlinks.unwrap_or(false),
+        count_lines: count_lines.unwrap_or(false),
+        max_depth,
+        max_files,
+        include_all: include_all.unwrap_or(false),
+        ..Default::default()
+    }
+}
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn scan_directory_async(
     app: tauri::AppHandle,
     path: String,
     custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+    max_depth: Option<usize>,
+    max_files: Option<u32>,
+    include_all: Option<bool>,
 ) -> Result<ScanResult, String> {
-    let path_clone = path.clone();
-    let result = tokio::task::spawn_blocking(move || {
-        let root = Path::new(&path_clone);
-        if !root.exists() || !root.is_dir() {
-            return Err("Path does not exist or is not a directory".to_string());
-        }
+    let options = ScanOptions {
+        dotenv_policy: project_dotenv_policy(&app.state::<ConfigState>(), &path),
+        ..scan_options(
+            custom_excludes,
+            extra_extensions,
+            include_globs,
+            exclude_globs,
+            include_hidden,
+            follow_symlinks,
+            count_lines,
+            max_depth,
+            max_files,
+            include_all,
+        )
+    };
+    tokio::task::spawn_blocking(move || {
+        let (result, options) = run_scan(
+            &path,
+            options,
+            &app.state::<MetadataCacheState>(),
+            &app.state::<ConfigState>(),
+            |progress| {
+                let _ = app.emit("scan-progress", progress);
+            },
+        )?;
+        app.state::<ScanCacheState>().store(&path, &options, &result.tree);
+        let _ = save_cached_scan(&path, &options, &result);
+        Ok(result)
+    })
+    .await
+    .map_err(|e| format!("Scan task failed: {}", e))?
+}
 
-        let _ = app.emit("scan-progress", ScanProgress {
-            phase: "detecting".to_string(),
-            files_found: 0,
-            message: "Detecting project type...".to_string(),
-        });
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn scan_directory(
+    cache: tauri::State<'_, ScanCacheState>,
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
+    path: String,
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+    max_depth: Option<usize>,
+    max_files: Option<u32>,
+    include_all: Option<bool>,
+) -> Result<ScanResult, String> {
+    let options = ScanOptions {
+        dotenv_policy: project_dotenv_policy(&config, &path),
+        ..scan_options(
+            custom_excludes,
+            extra_extensions,
+            include_globs,
+            exclude_globs,
+            include_hidden,
+            follow_symlinks,
+            count_lines,
+            max_depth,
+            max_files,
+            include_all,
+        )
+    };
+    let (result, options) = run_scan(&path, options, &metadata_cache, &config, |_| {})?;
+    cache.store(&path, &options, &result.tree);
+    let _ = save_cached_scan(&path, &options, &result);
+    Ok(result)
+}
 
-        let plugins = load_plugins();
-        let project_type = detect_project_type_with_plugins(root, &plugins);
-        let mut extra_excludes = get_plugin_excluded_dirs(&plugins);
-        if let Some(custom) = custom_excludes {
-            extra_excludes.extend(custom);
-        }
-        let extra_extensions = get_plugin_source_extensions(&plugins);
+/// Full synchronous scan reporting each phase to `progress`; also returns the options after
+/// plugin rules were merged in
+fn run_scan(
+    path: &str,
+    options: ScanOptions,
+    metadata_cache: &MetadataCacheState,
+    config: &ConfigState,
+    mut progress: impl FnMut(ScanProgress),
+) -> Result<(ScanResult, ScanOptions), String> {
+    let root = Path::new(path);
+    if !root.exists() || !root.is_dir() {
+        return Err("Path does not exist or is not a directory".to_string());
+    }
 
-        let _ = app.emit("scan-progress", ScanProgress {
+    progress(ScanProgress {
+        phase: "detecting".to_string(),
+        files_found: 0,
+        message: "Detecting project type...".to_string(),
+        dirs_found: 0,
+        current_dir: None,
+    });
+
+    let plugins = project_plugins(config, path);
+    let project_types = detect_project_types(root, &plugins);
+    let type_names: Vec<String> = project_types.iter().map(|t| t.name.to_string()).collect();
+    let type_info = project_types[0].clone();
+    let project_type = type_info.name.to_string();
+    let options = apply_config_rules(apply_plugin_rules(options, &plugins), root, config);
+    validate_globs(root, &options.include_globs)?;
+    validate_globs(root, &options.exclude_globs)?;
+
+    progress(ScanProgress {
+        phase: "scanning".to_string(),
+        files_found: 0,
+        message: "Scanning files...".to_string(),
+        dirs_found: 0,
+        current_dir: None,
+    });
+
+    let outcome = build_file_tree_reporting(root, &options, |walk| {
+        progress(ScanProgress {
             phase: "scanning".to_string(),
-            files_found: 0,
-            message: "Scanning files...".to_string(),
+            files_found: walk.files,
+            message: format!("Scanning files... {} found", walk.files),
+            dirs_found: walk.dirs,
+            current_dir: Some(walk.current_dir.to_string_lossy().to_string()),
         });
+    });
+    let mut tree = outcome.tree;
+    let mut monorepo = detect_monorepo(root);
+    if let Some(ref mut info) = monorepo {
+        tag_packages(&mut tree, info);
+    }
+    let total_files = count_files(&tree);
 
-        let tree = build_file_tree(root, &extra_excludes, &extra_extensions);
-        let total_files = count_files(&tree);
+    progress(ScanProgress {
+        phase: "metadata".to_string(),
+        files_found: total_files,
+        message: format!("Found {} files, extracting metadata...", total_files),
+        dirs_found: 0,
+        current_dir: None,
+    });
 
-        let _ = app.emit("scan-progress", ScanProgress {
-            phase: "metadata".to_string(),
-            files_found: total_files,
-            message: format!("Found {} files, extracting metadata...", total_files),
-        });
+    let mut metadata = metadata_cache.get(root, &type_names);
+    run_plugin_commands(&plugins, root, CommandStage::PostScan, &approved_commands(config, path))
+        .metadata
+        .apply(&mut metadata);
+
+    progress(ScanProgress {
+        phase: "done".to_string(),
+        files_found: total_files,
+        message: format!("Scan complete: {} files", total_files),
+        dirs_found: 0,
+        current_dir: None,
+    });
 
-        let metadata = extract_metadata(root, &project_type);
+    let result = ScanResult {
+        project_type,
+        project_type_info: Some(type_info),
+        project_types,
+        frameworks: metadata.frameworks.clone(),
+        tree,
+        total_files,
+        metadata,
+        truncated: outcome.truncated,
+        monorepo,
+        exclusions: Some(outcome.exclusions),
+    };
+    Ok((result, options))
+}
 
+// CodePack: 优先返回磁盘缓存的扫描结果，同时在后台重新扫描并通过 scan-refreshed 事件推送
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_directory_cached(
+    app: tauri::AppHandle,
+    path: String,
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+    max_depth: Option<usize>,
+    max_files: Option<u32>,
+    include_all: Option<bool>,
+) -> Result<ScanResult, String> {
+    let options = ScanOptions {
+        dotenv_policy: project_dotenv_policy(&app.state::<ConfigState>(), &path),
+        ..scan_options(
+            custom_excludes,
+            extra_extensions,
+            include_globs,
+            exclude_globs,
+            include_hidden,
+            follow_symlinks,
+            count_lines,
+            max_depth,
+            max_files,
+            include_all,
+        )
+    };
+    tokio::task::spawn_blocking(move || {
+        // Key the cache on the effective options so plugin changes invalidate it too
+        let config = app.state::<ConfigState>();
+        let effective = apply_config_rules(
+            apply_plugin_rules(options.clone(), &project_plugins(&config, &path)),
+            Path::new(&path),
+            &config,
+        );
+        if let Some(cached) = load_cached_scan(&path, &effective) {
+            app.state::<ScanCacheState>().store(&path, &effective, &cached.tree);
+            std::thread::spawn(move || {
+                if let Ok((fresh, effective)) = run_scan(&path, options, &app.state::<MetadataCacheState>(), &app.state::<ConfigState>(), |_| {}) {
+                    app.state::<ScanCacheState>().store(&path, &effective, &fresh.tree);
+                    let _ = save_cached_scan(&path, &effective, &fresh);
+                    let _ = app.emit("scan-refreshed", &fresh);
+                }
+            });
+            return Ok(cached);
+        }
+
+        let (result, effective) = run_scan(&path, options, &app.state::<MetadataCacheState>(), &app.state::<ConfigState>(), |_| {})?;
+        app.state::<ScanCacheState>().store(&path, &effective, &result.tree);
+        let _ = save_cached_scan(&path, &effective, &result);
+        Ok(result)
+    })
+    .await
+    .map_err(|e| format!("Scan task failed: {}", e))?
+}
+
+// CodePack: 浅克隆远程仓库到缓存目录后走常规扫描流程
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_remote_repo(
+    app: tauri::AppHandle,
+    url: String,
+    git_ref: Option<String>,
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+    max_depth: Option<usize>,
+    max_files: Option<u32>,
+    include_all: Option<bool>,
+) -> Result<ScanResult, String> {
+    let url = url.trim().to_string();
+    let is_remote = ["https://", "http://", "ssh://", "git://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+        || url.starts_with("git@");
+    if !is_remote {
+        return Err("Only remote git URLs are supported".to_string());
+    }
+    let options = scan_options(
+        custom_excludes,
+        extra_extensions,
+        include_globs,
+        exclude_globs,
+        include_hidden,
+        follow_symlinks,
+        count_lines,
+        max_depth,
+        max_files,
+        include_all,
+    );
+    tokio::task::spawn_blocking(move || {
+        let dest = crate::git::remote_checkout_dir(&url, git_ref.as_deref());
         let _ = app.emit("scan-progress", ScanProgress {
-            phase: "done".to_string(),
-            files_found: total_files,
-            message: format!("Scan complete: {} files", total_files),
+            phase: "cloning".to_string(),
+            files_found: 0,
+            message: format!("Cloning {}...", url),
+            dirs_found: 0,
+            current_dir: None,
         });
+        crate::git::shallow_clone(&url, git_ref.as_deref(), &dest)?;
+
+        let path = dest.to_string_lossy().to_string();
+        let (mut result, options) = run_scan(&path, options, &app.state::<MetadataCacheState>(), &app.state::<ConfigState>(), |_| {})?;
+        app.state::<ScanCacheState>().store(&path, &options, &result.tree);
+        let name = crate::git::repo_name_from_url(&url);
+        result.tree.name = name.clone();
+        if result.metadata.name == dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default() {
+            result.metadata.name = name;
+        }
+        Ok(result)
+    })
+    .await
+    .map_err(|e| format!("Scan task failed: {}", e))?
+}
 
-        Ok(ScanResult {
-            project_type,
-            tree,
-            total_files,
-            metadata,
-        })
+#[tauri::command]
+pub fn clear_remote_repos() -> Result<(), String> {
+    let root = crate::git::remote_cache_root();
+    if root.exists() {
+        fs::remove_dir_all(&root).map_err(|e| format!("Failed to clear remote checkouts: {}", e))?;
+    }
+    Ok(())
+}
+
+// CodePack: 增量重新扫描，沿用上次扫描的选项，只返回变化的节点以保留前端状态
+#[tauri::command]
+pub async fn rescan_directory(app: tauri::AppHandle, path: String) -> Result<TreeDiff, String> {
+    tokio::task::spawn_blocking(move || {
+        let root = Path::new(&path);
+        if !root.exists() || !root.is_dir() {
+            return Err("Path does not exist or is not a directory".to_string());
+        }
+        let cache = app.state::<ScanCacheState>();
+        let options = cache
+            .options_for(&path)
+            .ok_or_else(|| "No previous scan for this path".to_string())?;
+        let tree = build_file_tree_with_options(root, &options);
+        cache.update(&path, tree)
     })
     .await
-    .map_err(|e| format!("Scan task failed: {}", e))?;
-    result
+    .map_err(|e| format!("Rescan task failed: {}", e))?
 }
 
+// CodePack: 浅层扫描，只返回根目录下一层，子目录按需通过 expand_directory 展开
 #[tauri::command]
-pub fn scan_directory(path: String, custom_excludes: Option<Vec<String>>) -> Result<ScanResult, String> {
+#[allow(clippy::too_many_arguments)]
+pub fn scan_directory_shallow(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
+    path: String,
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+) -> Result<ScanResult, String> {
+    let options = ScanOptions {
+        dotenv_policy: project_dotenv_policy(&config, &path),
+        ..scan_options(
+            custom_excludes,
+            extra_extensions,
+            include_globs,
+            exclude_globs,
+            include_hidden,
+            follow_symlinks,
+            count_lines,
+            None,
+            None,
+            None,
+        )
+    };
     let root = Path::new(&path);
     if !root.exists() || !root.is_dir() {
         return Err("Path does not exist or is not a directory".to_string());
     }
 
-    let plugins = load_plugins();
-    let project_type = detect_project_type_with_plugins(root, &plugins);
-    let mut extra_excludes = get_plugin_excluded_dirs(&plugins);
-    if let Some(custom) = custom_excludes {
-        extra_excludes.extend(custom);
-    }
-    let extra_extensions = get_plugin_source_extensions(&plugins);
-    let tree = build_file_tree(root, &extra_excludes, &extra_extensions);
+    let plugins = project_plugins(&config, &path);
+    let project_types = detect_project_types(root, &plugins);
+    let type_names: Vec<String> = project_types.iter().map(|t| t.name.to_string()).collect();
+    let type_info = project_types[0].clone();
+    let project_type = type_info.name.to_string();
+    let options = apply_config_rules(apply_plugin_rules(options, &plugins), root, &config);
+    validate_globs(root, &options.include_globs)?;
+    validate_globs(root, &options.exclude_globs)?;
+    let tree = list_directory(root, root, &options);
+    // Only the files of the first level are known at this point
     let total_files = count_files(&tree);
-    let metadata = extract_metadata(root, &project_type);
+    let metadata = metadata_cache.get(root, &type_names);
 
     Ok(ScanResult {
         project_type,
+        project_type_info: Some(type_info),
+        project_types,
+        frameworks: metadata.frameworks.clone(),
         tree,
         total_files,
         metadata,
+        truncated: false,
+        monorepo: detect_monorepo(root),
+        exclusions: None,
+    })
+}
+
+// CodePack: 强制重新解析项目清单并更新元数据缓存
+#[tauri::command]
+pub async fn refresh_metadata(app: tauri::AppHandle, path: String) -> Result<ProjectMetadata, String> {
+    tokio::task::spawn_blocking(move || {
+        let root = Path::new(&path);
+        if !root.is_dir() {
+            return Err("Path does not exist or is not a directory".to_string());
+        }
+        let plugins = project_plugins(&app.state::<ConfigState>(), &path);
+        let type_names: Vec<String> = detect_project_types(root, &plugins)
+            .iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        Ok(app.state::<MetadataCacheState>().refresh(root, &type_names))
     })
+    .await
+    .map_err(|e| format!("Metadata task failed: {}", e))?
+}
+
+// CodePack: 展开浅层扫描中的某个子目录，过滤规则需与 scan_directory_shallow 保持一致
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn expand_directory(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    path: String,
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+) -> Result<FileNode, String> {
+    let options = ScanOptions {
+        dotenv_policy: project_dotenv_policy(&config, &project_path),
+        ..scan_options(
+            custom_excludes,
+            extra_extensions,
+            include_globs,
+            exclude_globs,
+            include_hidden,
+            follow_symlinks,
+            count_lines,
+            None,
+            None,
+            None,
+        )
+    };
+    let root = Path::new(&project_path);
+    let dir = Path::new(&path);
+    if !dir.is_dir() {
+        return Err("Path does not exist or is not a directory".to_string());
+    }
+    if !crate::paths::is_within(root, dir) {
+        return Err("Directory is outside the project".to_string());
+    }
+
+    let plugins = project_plugins(&config, &project_path);
+    let options = apply_config_rules(apply_plugin_rules(options, &plugins), root, &config);
+    validate_globs(root, &options.include_globs)?;
+    validate_globs(root, &options.exclude_globs)?;
+    Ok(list_directory(root, dir, &options))
+}
+
+// CodePack: 一键勾选 / 取消全部测试文件
+#[tauri::command]
+pub fn select_tests(mut tree: FileNode, include: bool) -> Result<FileNode, String> {
+    set_tests_checked(&mut tree, include);
+    Ok(tree)
+}
+
+// CodePack: 只保留 / 只勾选最近修改的文件；since 为 Unix 时间戳或 git ref
+#[tauri::command]
+pub fn apply_modified_since(
+    mut tree: FileNode,
+    project_path: String,
+    since: String,
+    prune: Option<bool>,
+) -> Result<FileNode, String> {
+    let filter = match since.trim().parse::<u64>() {
+        Ok(ts) => ChangeFilter::Since(ts),
+        Err(_) => ChangeFilter::Paths(
+            crate::git::changed_files_since(&project_path, since.trim())?
+                .into_iter()
+                .collect(),
+        ),
+    };
+    if prune.unwrap_or(false) {
+        prune_unchanged(&mut tree, &filter);
+    } else {
+        check_changed(&mut tree, &filter);
+    }
+    Ok(tree)
 }
 
 #[tauri::command]
@@ -115,65 +625,397 @@ pub fn read_file_content(path: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn save_project_config(project_path: String, checked_paths: Vec<String>) -> Result<(), String> {
-    let mut config = load_app_config();
+pub fn save_project_config(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    checked_paths: Vec<String>,
+) -> Result<(), String> {
     let now = chrono_now();
-    let (presets, pinned) = config
-        .projects
-        .get(&project_path)
-        .map(|p| (p.presets.clone(), p.pinned))
-        .unwrap_or_default();
-    config.projects.insert(
-        project_path.clone(),
-        ProjectConfig {
-            project_path,
-            checked_paths,
-            excluded_paths: Vec::new(),
-            last_opened: now,
-            presets,
-            pinned,
-        },
-    );
-    save_app_config(&config)
+    config.update(|config| {
+        let project = config.entry_or_default(&project_path);
+        project.checked_paths = checked_paths;
+        project.excluded_paths = Vec::new();
+        project.last_opened = now;
+    })
+}
+
+#[tauri::command]
+pub fn load_project_config(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+) -> Result<Option<ProjectConfig>, String> {
+    Ok(config.read(|c| c.projects.get(&project_path).cloned()))
 }
 
+// CodePack: 最近项目列表：置顶项目在前，其余按最近打开时间倒序
 #[tauri::command]
-pub fn load_project_config(project_path: String) -> Result<Option<ProjectConfig>, String> {
-    let config = load_app_config();
-    Ok(config.projects.get(&project_path).cloned())
+pub fn list_projects(config: tauri::State<'_, ConfigState>) -> Result<Vec<ProjectConfig>, String> {
+    let mut projects: Vec<ProjectConfig> = config.read(|c| c.projects.values().cloned().collect());
+    projects.sort_by(|a, b| b.pinned.cmp(&a.pinned).then_with(|| b.last_opened.cmp(&a.last_opened)));
+    Ok(projects)
 }
 
 #[tauri::command]
-pub fn estimate_tokens(paths: Vec<String>) -> Result<TokenEstimate, String> {
-    let mut total_bytes: u64 = 0;
-    let mut total_tokens: usize = 0;
-    let bpe = &*BPE;
-    for path in &paths {
-        if let Ok(content) = fs::read_to_string(path) {
-            total_bytes += content.len() as u64;
-            total_tokens += bpe.encode_ordinary(&content).len();
+pub fn set_project_pinned(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    pinned: bool,
+) -> Result<(), String> {
+    config.try_update(|config| match config.projects.get_mut(&project_path) {
+        Some(project) => {
+            project.pinned = pinned;
+            Ok(())
         }
-    }
+        None => Err(format!("Project not found: {}", project_path)),
+    })
+}
+
+/// Falls back to the globally configured tokenizer when the caller doesn't pick one
+fn resolve_tokenizer(tokenizer: Option<Tokenizer>, config: &ConfigState) -> Tokenizer {
+    tokenizer.unwrap_or_else(|| config.read(|c| c.settings.default_tokenizer))
+}
+
+#[tauri::command]
+pub async fn estimate_tokens(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ConfigState>,
+    paths: Vec<String>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+) -> Result<TokenEstimate, String> {
+    let tokenizer = resolve_tokenizer(tokenizer, &config);
+    let (counted, all_exact) = tokio::task::spawn_blocking(move || {
+        let cache = app.state::<TokenCacheState>();
+        cache.count_files(&paths, tokenizer)
+    })
+    .await
+    .map_err(|e| format!("Token estimate task failed: {}", e))?;
+
+    let total_bytes: u64 = counted.iter().map(|(_, bytes, _)| bytes).sum();
+    let tokens = counted.iter().map(|(_, _, tokens)| tokens).sum::<usize>() as f64;
     Ok(TokenEstimate {
-        tokens: total_tokens as f64,
+        tokens,
         total_bytes,
+        tokenizer,
+        approximate: !tokenizer.is_exact() || !all_exact,
+        estimated_cost: cost_for(target_model.as_deref(), tokens, &config),
+    })
+}
+
+#[tauri::command]
+pub async fn estimate_tokens_detailed(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    tokenizer: Option<Tokenizer>,
+) -> Result<DetailedTokenEstimate, String> {
+    let tokenizer = resolve_tokenizer(tokenizer, &app.state::<ConfigState>());
+    let (counted, all_exact) = tokio::task::spawn_blocking(move || {
+        let cache = app.state::<TokenCacheState>();
+        cache.count_files(&paths, tokenizer)
+    })
+    .await
+    .map_err(|e| format!("Token estimate task failed: {}", e))?;
+
+    let files: Vec<FileTokenEstimate> = counted
+        .into_iter()
+        .map(|(path, bytes, tokens)| FileTokenEstimate {
+            path,
+            bytes,
+            tokens: tokens as f64,
+        })
+        .collect();
+    Ok(DetailedTokenEstimate {
+        tokens: files.iter().map(|f| f.tokens).sum(),
+        total_bytes: files.iter().map(|f| f.bytes).sum(),
+        files,
+        tokenizer,
+        approximate: !tokenizer.is_exact() || !all_exact,
+    })
+}
+
+const ANNOTATE_CHUNK_SIZE: usize = 256;
+
+#[tauri::command]
+pub async fn annotate_tree_tokens(
+    app: tauri::AppHandle,
+    tree: FileNode,
+    tokenizer: Option<Tokenizer>,
+) -> Result<FileNode, String> {
+    let tokenizer = resolve_tokenizer(tokenizer, &app.state::<ConfigState>());
+    tokio::task::spawn_blocking(move || {
+        let mut tree = tree;
+        let cache = app.state::<TokenCacheState>();
+        let paths = collect_file_paths(&tree);
+        let files_total = paths.len() as u32;
+        let mut counts: HashMap<String, usize> = HashMap::new();
+
+        for chunk in paths.chunks(ANNOTATE_CHUNK_SIZE) {
+            let (counted, _) = cache.count_files(chunk, tokenizer);
+            counts.extend(counted.into_iter().map(|(path, _, tokens)| (path, tokens)));
+            let _ = app.emit("token-annotate-progress", TokenAnnotateProgress {
+                files_done: (counts.len() as u32).min(files_total),
+                files_total,
+            });
+        }
+
+        annotate_tree(&mut tree, &counts);
+        tree
     })
+    .await
+    .map_err(|e| format!("Token annotation task failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn estimate_tokens_tree(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    project_path: String,
+    tokenizer: Option<Tokenizer>,
+) -> Result<Vec<DirTokenStat>, String> {
+    let tokenizer = resolve_tokenizer(tokenizer, &app.state::<ConfigState>());
+    let (counted, _) = tokio::task::spawn_blocking(move || {
+        let cache = app.state::<TokenCacheState>();
+        cache.count_files(&paths, tokenizer)
+    })
+    .await
+    .map_err(|e| format!("Token estimate task failed: {}", e))?;
+    Ok(rollup_by_directory(Path::new(&project_path), &counted))
+}
+
+#[tauri::command]
+pub fn is_tokenizer_ready(
+    config: tauri::State<'_, ConfigState>,
+    tokenizer: Option<Tokenizer>,
+) -> Result<bool, String> {
+    Ok(crate::tokens::is_ready(resolve_tokenizer(tokenizer, &config)))
 }
 
 #[tauri::command]
+pub fn get_default_tokenizer(config: tauri::State<'_, ConfigState>) -> Result<Tokenizer, String> {
+    Ok(config.read(|c| c.settings.default_tokenizer))
+}
+
+#[tauri::command]
+pub fn set_default_tokenizer(
+    config: tauri::State<'_, ConfigState>,
+    tokenizer: Tokenizer,
+) -> Result<(), String> {
+    config.update(|c| c.settings.default_tokenizer = tokenizer)
+}
+
+#[tauri::command]
+pub fn get_app_settings(config: tauri::State<'_, ConfigState>) -> Result<AppSettings, String> {
+    Ok(config.read(|c| c.settings.clone()))
+}
+
+#[tauri::command]
+pub fn set_app_settings(
+    config: tauri::State<'_, ConfigState>,
+    settings: AppSettings,
+) -> Result<(), String> {
+    let settings = AppSettings {
+        default_excludes: settings
+            .default_excludes
+            .into_iter()
+            .map(|e| e.trim().to_string())
+            .filter(|e| !e.is_empty())
+            .collect(),
+        ..settings
+    };
+    config.update(|c| c.settings = settings)
+}
+
+/// Reports a corrupted config found at load time once, so the UI can tell the user what was recovered
+#[tauri::command]
+pub fn take_config_recovery() -> Result<Option<ConfigRecovery>, String> {
+    Ok(crate::config::take_config_recovery())
+}
+
+// CodePack: 将应用配置、插件与自定义 Review 预设导出为一个 JSON 文件，用于迁移或共享团队基线
+#[tauri::command]
+pub fn export_settings(
+    config: tauri::State<'_, ConfigState>,
+    path: String,
+) -> Result<String, String> {
+    let bundle = crate::config::export_settings_bundle(&config);
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to export settings: {}", e))?;
+    Ok(path)
+}
+
+#[tauri::command]
+pub fn import_settings(
+    config: tauri::State<'_, ConfigState>,
+    secret_cache: tauri::State<'_, SecretCacheState>,
+    path: String,
+) -> Result<(), String> {
+    let data = fs::read_to_string(&path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    let bundle = serde_json::from_str(&data).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    crate::config::import_settings_bundle(&config, bundle)?;
+    // Bundled plugins may bring secret rules
+    reload_plugin_secret_rules(&secret_cache);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_locale(config: tauri::State<'_, ConfigState>) -> Result<Locale, String> {
+    Ok(config.read(|c| c.locale))
+}
+
+#[tauri::command]
+pub fn set_locale(config: tauri::State<'_, ConfigState>, locale: Locale) -> Result<(), String> {
+    config.update(|c| c.locale = locale)
+}
+
+/// Display name of a project type in the given locale (or the configured one)
+#[tauri::command]
+pub fn get_project_type_label(
+    config: tauri::State<'_, ConfigState>,
+    project_type: String,
+    locale: Option<Locale>,
+) -> Result<String, String> {
+    let locale = locale.unwrap_or_else(|| config.read(|c| c.locale));
+    Ok(project_type_label(&ProjectType::from(project_type), locale))
+}
+
+fn cost_for(target_model: Option<&str>, tokens: f64, config: &ConfigState) -> Option<CostEstimate> {
+    let model = target_model?;
+    config.read(|c| estimate_cost(model, tokens, &c.model_prices))
+}
+
+#[tauri::command]
+pub fn get_model_prices(config: tauri::State<'_, ConfigState>) -> Result<HashMap<String, f64>, String> {
+    Ok(config.read(|c| price_table(&c.model_prices)))
+}
+
+#[tauri::command]
+pub fn set_model_price(
+    config: tauri::State<'_, ConfigState>,
+    model: String,
+    usd_per_million: Option<f64>,
+) -> Result<(), String> {
+    config.update(|config| {
+        match usd_per_million {
+            Some(price) => config.model_prices.insert(model.to_lowercase(), price),
+            None => config.model_prices.remove(&model.to_lowercase()),
+        };
+    })
+}
+
+/// Attaches the cost estimate and, with `force=false`, turns a pack that overflows
+/// the target model's window into an error
+fn finalize_pack(
+    mut result: PackResult,
+    target_model: Option<&str>,
+    force: Option<bool>,
+    config: &ConfigState,
+) -> Result<PackResult, String> {
+    result.estimated_cost = cost_for(target_model, result.estimated_tokens, config);
+    if let Some(ref warning) = result.budget_warning {
+        if !force.unwrap_or(true) {
+            return Err(warning.message.clone());
+        }
+    }
+    Ok(result)
+}
+
+/// Pack options from the arguments the pack and export commands share, with the project's
+/// defaults and pre-pack hook output filled in
+#[allow(clippy::too_many_arguments)]
+fn pack_options(
+    metadata_cache: &MetadataCacheState,
+    config: &ConfigState,
+    project_path: &str,
+    project_type: &str,
+    format: Option<ExportFormat>,
+    max_file_bytes: Option<u64>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    resolve_lockfiles: Option<bool>,
+    include_history: Option<u32>,
+    history_files: Option<bool>,
+    include_blame: Option<bool>,
+    include_contributors: Option<bool>,
+    include_markers: Option<bool>,
+    order_by_churn: Option<u32>,
+    mask_secrets: Option<bool>,
+) -> PackOptions {
+    let defaults = pack_defaults(project_path, config);
+    PackOptions {
+        format: format.or(defaults.format).unwrap_or_default(),
+        max_file_bytes: max_file_bytes.or(defaults.max_file_bytes),
+        tokenizer: resolve_tokenizer(tokenizer, config),
+        target_model,
+        resolve_lockfiles: resolve_lockfiles.unwrap_or(false),
+        include_history,
+        history_files: history_files.unwrap_or(false),
+        include_blame: include_blame.unwrap_or(false),
+        include_contributors: include_contributors.unwrap_or(false),
+        include_markers: include_markers.unwrap_or(false),
+        order_by_churn,
+        mask_secrets: mask_secrets.unwrap_or(defaults.mask_secrets),
+        secret_plugins: defaults.secret_plugins,
+        dotenv_policy: project_dotenv_policy(config, project_path),
+        header_templates: defaults.header_templates,
+        annotations: defaults.hooks.annotations,
+        metadata: Some(with_hook_metadata(
+            metadata_cache.get_for_pack(Path::new(project_path), project_type),
+            defaults.hooks.metadata,
+        )),
+        ..Default::default()
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn pack_files(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
     paths: Vec<String>,
     project_path: String,
     project_type: String,
     format: Option<ExportFormat>,
     max_file_bytes: Option<u64>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    force: Option<bool>,
+    resolve_lockfiles: Option<bool>,
+    include_history: Option<u32>,
+    history_files: Option<bool>,
+    include_blame: Option<bool>,
+    include_contributors: Option<bool>,
+    include_markers: Option<bool>,
+    order_by_churn: Option<u32>,
+    mask_secrets: Option<bool>,
 ) -> Result<PackResult, String> {
-    let fmt = format.unwrap_or_default();
-    Ok(build_pack_content_with_limit(&paths, &project_path, &project_type, &fmt, max_file_bytes))
+    let options = pack_options(
+        &metadata_cache,
+        &config,
+        &project_path,
+        &project_type,
+        format,
+        max_file_bytes,
+        tokenizer,
+        target_model,
+        resolve_lockfiles,
+        include_history,
+        history_files,
+        include_blame,
+        include_contributors,
+        include_markers,
+        order_by_churn,
+        mask_secrets,
+    );
+    let result = build_pack_content_with_options(&paths, &project_path, &project_type, &options);
+    finalize_pack(result, options.target_model.as_deref(), force, &config)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn pack_files_extended(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
     paths: Vec<String>,
     project_path: String,
     project_type: String,
@@ -181,18 +1023,161 @@ pub fn pack_files_extended(
     max_file_bytes: Option<u64>,
     include_diff: Option<bool>,
     instruction: Option<String>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    force: Option<bool>,
+    resolve_lockfiles: Option<bool>,
+    include_history: Option<u32>,
+    history_files: Option<bool>,
+    include_blame: Option<bool>,
+    include_contributors: Option<bool>,
+    include_markers: Option<bool>,
+    order_by_churn: Option<u32>,
+    mask_secrets: Option<bool>,
 ) -> Result<PackResult, String> {
-    let fmt = format.unwrap_or_default();
+    let options = pack_options(
+        &metadata_cache,
+        &config,
+        &project_path,
+        &project_type,
+        format,
+        max_file_bytes,
+        tokenizer,
+        target_model,
+        resolve_lockfiles,
+        include_history,
+        history_files,
+        include_blame,
+        include_contributors,
+        include_markers,
+        order_by_churn,
+        mask_secrets,
+    );
     let diffs = if include_diff.unwrap_or(false) {
-        let diff_map = crate::git::get_diffs_for_files(&project_path, &paths);
-        if diff_map.is_empty() { None } else { Some(diff_map) }
+        let diffs = crate::git::get_diffs_for_files(&project_path, &paths);
+        if diffs.is_empty() { None } else { Some(diffs) }
     } else {
         None
     };
-    Ok(build_pack_content_extended(
-        &paths, &project_path, &project_type, &fmt, max_file_bytes,
+    let result = build_pack_content_extended(
+        &paths, &project_path, &project_type, &options,
         diffs.as_ref(), instruction.as_deref(),
-    ))
+    );
+    finalize_pack(result, options.target_model.as_deref(), force, &config)
+}
+
+// CodePack: 打包 from_ref..to_ref 之间改动过的文件及其 diff（文件内容取自当前工作区）
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn pack_commit_range(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    from_ref: String,
+    to_ref: String,
+    format: Option<ExportFormat>,
+    max_file_bytes: Option<u64>,
+    instruction: Option<String>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    force: Option<bool>,
+) -> Result<PackResult, String> {
+    let (paths, range) = crate::git::diff_between_refs(&project_path, from_ref.trim(), to_ref.trim())?;
+    if range.diffs.is_empty() {
+        return Err(format!("No changes between {} and {}", from_ref.trim(), to_ref.trim()));
+    }
+    let plugins = project_plugins(&config, &project_path);
+    let project_type = detect_project_type_with_plugins(Path::new(&project_path), &plugins);
+    let defaults = pack_defaults(&project_path, &config);
+    let options = PackOptions {
+        format: format.or(defaults.format).unwrap_or_default(),
+        max_file_bytes: max_file_bytes.or(defaults.max_file_bytes),
+        tokenizer: resolve_tokenizer(tokenizer, &config),
+        target_model,
+        mask_secrets: defaults.mask_secrets,
+        secret_plugins: defaults.secret_plugins,
+        dotenv_policy: project_dotenv_policy(&config, &project_path),
+        header_templates: defaults.header_templates,
+        annotations: defaults.hooks.annotations,
+        metadata: Some(with_hook_metadata(
+            metadata_cache.get_for_pack(Path::new(&project_path), &project_type),
+            defaults.hooks.metadata,
+        )),
+        ..Default::default()
+    };
+    let diffs = crate::git::FileDiffs {
+        range: Some(range),
+        ..Default::default()
+    };
+    let result = build_pack_content_extended(
+        &paths, &project_path, &project_type, &options,
+        Some(&diffs), instruction.as_deref(),
+    );
+    finalize_pack(result, options.target_model.as_deref(), force, &config)
+}
+
+// CodePack: 打包 git_ref 对应提交中的文件内容；未传 paths 时打包该提交中项目目录下的全部源码文件
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn pack_at_ref(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    git_ref: String,
+    paths: Option<Vec<String>>,
+    format: Option<ExportFormat>,
+    max_file_bytes: Option<u64>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    force: Option<bool>,
+) -> Result<PackResult, String> {
+    let git_ref = git_ref.trim();
+    let root = Path::new(&project_path);
+    let reader = crate::git::RefReader::open(&project_path, git_ref)?;
+    let plugins = project_plugins(&config, &project_path);
+    let paths = match paths {
+        Some(paths) if !paths.is_empty() => paths,
+        _ => {
+            // The same rules a scan of the working tree would apply
+            let scan_options = apply_config_rules(
+                apply_plugin_rules(
+                    ScanOptions {
+                        dotenv_policy: project_dotenv_policy(&config, &project_path),
+                        ..Default::default()
+                    },
+                    &plugins,
+                ),
+                root,
+                &config,
+            );
+            reader
+                .files_under(root)
+                .into_iter()
+                .filter(|p| passes_scan_rules(root, Path::new(p), &scan_options))
+                .collect()
+        }
+    };
+    let project_type = detect_project_type_with_plugins(root, &plugins);
+    // Manifests are read from the working tree; the revision line reflects the ref
+    let mut metadata = metadata_cache.get_for_pack(root, &project_type);
+    metadata.repository = crate::git::repository_info_at(root, git_ref);
+    let defaults = pack_defaults(&project_path, &config);
+    let options = PackOptions {
+        format: format.or(defaults.format).unwrap_or_default(),
+        max_file_bytes: max_file_bytes.or(defaults.max_file_bytes),
+        tokenizer: resolve_tokenizer(tokenizer, &config),
+        target_model,
+        mask_secrets: defaults.mask_secrets,
+        secret_plugins: defaults.secret_plugins,
+        at_ref: Some(git_ref.to_string()),
+        dotenv_policy: project_dotenv_policy(&config, &project_path),
+        header_templates: defaults.header_templates,
+        annotations: defaults.hooks.annotations,
+        metadata: Some(with_hook_metadata(metadata, defaults.hooks.metadata)),
+        ..Default::default()
+    };
+    let result = build_pack_content_with_options(&paths, &project_path, &project_type, &options);
+    finalize_pack(result, options.target_model.as_deref(), force, &config)
 }
 
 #[tauri::command]
@@ -204,107 +1189,486 @@ pub fn copy_to_clipboard(content: String, app: tauri::AppHandle) -> Result<(), S
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn export_to_file(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
     paths: Vec<String>,
     project_path: String,
     project_type: String,
     save_path: String,
     format: Option<ExportFormat>,
     max_file_bytes: Option<u64>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    force: Option<bool>,
+    resolve_lockfiles: Option<bool>,
+    include_history: Option<u32>,
+    history_files: Option<bool>,
+    include_blame: Option<bool>,
+    include_contributors: Option<bool>,
+    include_markers: Option<bool>,
+    order_by_churn: Option<u32>,
+    mask_secrets: Option<bool>,
+    block_on_conflicts: Option<bool>,
 ) -> Result<String, String> {
-    let fmt = format.unwrap_or_default();
-    let result = build_pack_content_with_limit(&paths, &project_path, &project_type, &fmt, max_file_bytes);
+    let options = pack_options(
+        &metadata_cache,
+        &config,
+        &project_path,
+        &project_type,
+        format,
+        max_file_bytes,
+        tokenizer,
+        target_model,
+        resolve_lockfiles,
+        include_history,
+        history_files,
+        include_blame,
+        include_contributors,
+        include_markers,
+        order_by_churn,
+        mask_secrets,
+    );
+    let result = finalize_pack(
+        build_pack_content_with_options(&paths, &project_path, &project_type, &options),
+        options.target_model.as_deref(),
+        force,
+        &config,
+    )?;
+    if block_on_conflicts.unwrap_or(false) && !result.conflicts.is_empty() {
+        let files: Vec<&str> = result.conflicts.iter().map(|c| c.path.as_str()).collect();
+        return Err(format!("Unresolved merge conflicts in {}", files.join(", ")));
+    }
     fs::write(&save_path, &result.content)
         .map_err(|e| format!("Failed to export: {}", e))?;
     Ok(save_path)
 }
 
 #[tauri::command]
-pub fn open_directory(path: String) -> Result<(), String> {
-    let p = Path::new(&path);
-    let dir = if p.is_file() {
-        p.parent().unwrap_or(p)
-    } else {
-        p
-    };
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("explorer")
-            .arg(dir.to_string_lossy().to_string())
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(dir.to_string_lossy().to_string())
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(dir.to_string_lossy().to_string())
-            .spawn()
-            .map_err(|e| e.to_string())?;
+pub fn open_directory(path: String) -> Result<(), String> {
+    let p = Path::new(&path);
+    let dir = if p.is_file() {
+        p.parent().unwrap_or(p)
+    } else {
+        p
+    };
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("explorer")
+            .arg(dir.to_string_lossy().to_string())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(dir.to_string_lossy().to_string())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(dir.to_string_lossy().to_string())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_file_size(path: String) -> Result<u64, String> {
+    fs::metadata(&path)
+        .map(|m| m.len())
+        .map_err(|e| format!("Failed to get file size: {}", e))
+}
+
+// ─── Preset Commands ───────────────────────────────────────────
+
+// CodePack: 保存预设；除勾选路径外可附带导出格式、排除规则、排序方式和审查提示词
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn save_preset(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    preset_name: String,
+    checked_paths: Vec<String>,
+    format: Option<ExportFormat>,
+    excludes: Option<Vec<String>>,
+    order_by_churn: Option<u32>,
+    review_prompt: Option<String>,
+) -> Result<(), String> {
+    let root = Path::new(&project_path);
+    let preset = Preset {
+        paths: checked_paths.iter().filter_map(|path| preset_entry(root, path)).collect(),
+        format,
+        excludes: excludes.unwrap_or_default(),
+        order_by_churn,
+        review_prompt: review_prompt.filter(|name| !name.trim().is_empty()),
+    };
+    config.update(|config| {
+        if let Some(project) = config.projects.get_mut(&project_path) {
+            project.presets.insert(preset_name, preset);
+        } else {
+            let mut presets = HashMap::new();
+            presets.insert(preset_name, preset);
+            config.projects.insert(
+                project_path.clone(),
+                ProjectConfig { checked_paths, presets, ..ProjectConfig::new(project_path) },
+            );
+        }
+    })
+}
+
+#[tauri::command]
+pub fn delete_preset(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    preset_name: String,
+) -> Result<(), String> {
+    config.update(|config| {
+        if let Some(project) = config.projects.get_mut(&project_path) {
+            project.presets.remove(&preset_name);
+        }
+    })
+}
+
+// CodePack: 项目 codepack.toml 中的预设与用户预设合并，同名时用户预设优先；返回当前树中的绝对路径，前端直接用来勾选
+#[tauri::command]
+pub fn list_presets(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+) -> Result<HashMap<String, Vec<String>>, String> {
+    let root = Path::new(&project_path);
+    let mut presets = project_file_config(root).presets;
+    if let Some(saved) = config.read(|c| c.projects.get(&project_path).map(|p| p.presets.clone())) {
+        presets.extend(saved);
+    }
+    Ok(presets
+        .into_iter()
+        .map(|(name, preset)| {
+            let options = preset_scan_options(&config, &project_path, &preset);
+            let paths = resolve_preset_entries(root, &preset.paths, &options);
+            (name, paths)
+        })
+        .collect())
+}
+
+/// Scan rules a preset's glob entries are expanded with
+fn preset_scan_options(config: &ConfigState, project_path: &str, preset: &Preset) -> ScanOptions {
+    let mut options = apply_config_rules(ScanOptions::default(), Path::new(project_path), config);
+    options.dotenv_policy = project_dotenv_policy(config, project_path);
+    options.extra_excludes.extend(preset.excludes.iter().cloned());
+    options
+}
+
+// CodePack: 应用预设：将相对路径与 glob 条目解析为当前树中的绝对路径
+#[tauri::command]
+pub fn resolve_preset(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    preset_name: String,
+) -> Result<Vec<String>, String> {
+    let root = Path::new(&project_path);
+    let preset = config
+        .read(|c| c.projects.get(&project_path).and_then(|p| p.presets.get(&preset_name).cloned()))
+        .or_else(|| project_file_config(root).presets.remove(&preset_name))
+        .ok_or_else(|| format!("Preset '{}' not found", preset_name))?;
+    let options = preset_scan_options(&config, &project_path, &preset);
+    Ok(resolve_preset_entries(root, &preset.paths, &options))
+}
+
+#[tauri::command]
+pub fn rename_preset(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let new_name = validate_preset_name(&new_name)?;
+    config.try_update(|config| {
+        let project = config
+            .projects
+            .get_mut(&project_path)
+            .ok_or_else(|| format!("Project not found: {}", project_path))?;
+        if old_name == new_name {
+            return Ok(());
+        }
+        if project.presets.contains_key(&new_name) {
+            return Err(format!("Preset '{}' already exists", new_name));
+        }
+        let paths = project
+            .presets
+            .remove(&old_name)
+            .ok_or_else(|| format!("Preset '{}' not found", old_name))?;
+        project.presets.insert(new_name, paths);
+        Ok(())
+    })
+}
+
+// CodePack: 复制预设；源预设也可以来自 codepack.toml，复制后成为可编辑的用户预设
+#[tauri::command]
+pub fn duplicate_preset(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    preset_name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let new_name = validate_preset_name(&new_name)?;
+    let file_presets = project_file_config(Path::new(&project_path)).presets;
+    config.try_update(|config| {
+        let saved = config.projects.get(&project_path).map(|p| &p.presets);
+        if saved.is_some_and(|presets| presets.contains_key(&new_name))
+            || file_presets.contains_key(&new_name)
+        {
+            return Err(format!("Preset '{}' already exists", new_name));
+        }
+        let paths = saved
+            .and_then(|presets| presets.get(&preset_name))
+            .or_else(|| file_presets.get(&preset_name))
+            .cloned()
+            .ok_or_else(|| format!("Preset '{}' not found", preset_name))?;
+        config.entry_or_default(&project_path).presets.insert(new_name, paths);
+        Ok(())
+    })
+}
+
+// CodePack: 导出预设（指定名称或全部，含 codepack.toml 中的预设）到独立 JSON 文件，路径统一转为相对形式
+#[tauri::command]
+pub fn export_presets(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    preset_names: Option<Vec<String>>,
+    path: String,
+) -> Result<String, String> {
+    let root = Path::new(&project_path);
+    let mut presets = project_file_config(root).presets;
+    if let Some(saved) = config.read(|c| c.projects.get(&project_path).map(|p| p.presets.clone())) {
+        presets.extend(saved);
+    }
+    if let Some(names) = preset_names {
+        if let Some(missing) = names.iter().find(|name| !presets.contains_key(*name)) {
+            return Err(format!("Preset '{}' not found", missing));
+        }
+        presets.retain(|name, _| names.contains(name));
+    }
+    for preset in presets.values_mut() {
+        preset.paths = preset.paths.iter().filter_map(|p| preset_entry(root, p)).collect();
+    }
+    let bundle = PresetBundle {
+        version: PRESET_BUNDLE_VERSION,
+        exported_at: chrono_now(),
+        presets,
+    };
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize presets: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to export presets: {}", e))?;
+    Ok(path)
+}
+
+// CodePack: 从预设文件导入；同名预设默认保留原有的，overwrite 时以文件为准。返回实际导入的预设名
+#[tauri::command]
+pub fn import_presets(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let data = fs::read_to_string(&path).map_err(|e| format!("Failed to read presets: {}", e))?;
+    let bundle: PresetBundle =
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse presets: {}", e))?;
+    if bundle.version > PRESET_BUNDLE_VERSION {
+        return Err(format!(
+            "Preset file version {} is newer than supported ({})",
+            bundle.version, PRESET_BUNDLE_VERSION
+        ));
     }
-    Ok(())
+    let overwrite = overwrite.unwrap_or(false);
+    let root = PathBuf::from(&project_path);
+    config.try_update(|config| {
+        let project = config.entry_or_default(&project_path);
+        merge_presets(&mut project.presets, &root, bundle.presets, overwrite)
+    })
 }
 
+/// The project's codepack.toml / .codepack.json, or None; unlike scans and packs, parse errors are reported
 #[tauri::command]
-pub fn get_file_size(path: String) -> Result<u64, String> {
-    fs::metadata(&path)
-        .map(|m| m.len())
-        .map_err(|e| format!("Failed to get file size: {}", e))
+pub fn get_project_file_config(project_path: String) -> Result<Option<ProjectFileConfig>, String> {
+    load_project_file_config(Path::new(&project_path))
 }
 
-// ─── Preset Commands ───────────────────────────────────────────
+// ─── Workspace Commands ────────────────────────────────────────
+
+fn workspace_roots(config: &ConfigState, name: &str) -> Result<Vec<WorkspaceRoot>, String> {
+    config.read(|c| {
+        let workspace = c
+            .workspaces
+            .get(name)
+            .ok_or_else(|| format!("Workspace not found: {}", name))?;
+        Ok(root_labels(&workspace.roots))
+    })
+}
 
 #[tauri::command]
-pub fn save_preset(
-    project_path: String,
-    preset_name: String,
-    checked_paths: Vec<String>,
+pub fn save_workspace(
+    config: tauri::State<'_, ConfigState>,
+    name: String,
+    roots: Vec<String>,
 ) -> Result<(), String> {
-    let mut config = load_app_config();
-    if let Some(project) = config.projects.get_mut(&project_path) {
-        project.presets.insert(preset_name, checked_paths);
-    } else {
-        let now = chrono_now();
-        let mut presets = HashMap::new();
-        presets.insert(preset_name, checked_paths.clone());
-        config.projects.insert(
-            project_path.clone(),
-            ProjectConfig {
-                project_path,
-                checked_paths,
-                excluded_paths: Vec::new(),
-                last_opened: now,
-                presets,
-                pinned: false,
+    validate_roots(&roots)?;
+    config.update(|config| {
+        config.workspaces.insert(
+            name.clone(),
+            Workspace {
+                name,
+                roots,
+                last_opened: chrono_now(),
             },
         );
-    }
-    save_app_config(&config)
+    })
 }
 
 #[tauri::command]
-pub fn delete_preset(project_path: String, preset_name: String) -> Result<(), String> {
-    let mut config = load_app_config();
-    if let Some(project) = config.projects.get_mut(&project_path) {
-        project.presets.remove(&preset_name);
-    }
-    save_app_config(&config)
+pub fn delete_workspace(config: tauri::State<'_, ConfigState>, name: String) -> Result<(), String> {
+    config.update(|config| {
+        config.workspaces.remove(&name);
+    })
+}
+
+#[tauri::command]
+pub fn list_workspaces(config: tauri::State<'_, ConfigState>) -> Result<Vec<Workspace>, String> {
+    let mut workspaces: Vec<Workspace> = config.read(|c| c.workspaces.values().cloned().collect());
+    workspaces.sort_by(|a, b| b.last_opened.cmp(&a.last_opened));
+    Ok(workspaces)
+}
+
+// CodePack: 扫描工作区，各根目录作为虚拟根节点的子节点
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_workspace(
+    app: tauri::AppHandle,
+    name: String,
+    custom_excludes: Option<Vec<String>>,
+    extra_extensions: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_lines: Option<bool>,
+    max_depth: Option<usize>,
+    max_files: Option<u32>,
+    include_all: Option<bool>,
+) -> Result<ScanResult, String> {
+    let options = scan_options(
+        custom_excludes,
+        extra_extensions,
+        include_globs,
+        exclude_globs,
+        include_hidden,
+        follow_symlinks,
+        count_lines,
+        max_depth,
+        max_files,
+        include_all,
+    );
+    tokio::task::spawn_blocking(move || {
+        let config = app.state::<ConfigState>();
+        let roots = workspace_roots(&config, &name)?;
+        let root_paths: Vec<String> = roots.iter().map(|r| r.path.clone()).collect();
+        validate_roots(&root_paths)?;
+
+        // Plugins enabled for the first root apply to the whole workspace
+        let plugins = project_plugins(&config, &roots[0].path);
+        let options = apply_plugin_rules(options, &plugins);
+        for root in &roots {
+            validate_globs(Path::new(&root.path), &options.include_globs)?;
+            validate_globs(Path::new(&root.path), &options.exclude_globs)?;
+        }
+        // Each member keeps its own codepack.toml rules and .env policy
+        let (tree, truncated) = build_workspace_tree(&name, &roots, |root| {
+            let member = ScanOptions {
+                dotenv_policy: project_dotenv_policy(&config, &root.to_string_lossy()),
+                ..options.clone()
+            };
+            apply_config_rules(member, root, &config)
+        });
+        let total_files = count_files(&tree);
+
+        if config.read(|c| c.workspaces.contains_key(&name)) {
+            let _ = config.update(|c| {
+                if let Some(workspace) = c.workspaces.get_mut(&name) {
+                    workspace.last_opened = chrono_now();
+                }
+            });
+        }
+
+        // Metadata describes the first root; the tree carries the rest
+        let first = Path::new(&roots[0].path);
+        let project_types = detect_project_types(first, &plugins);
+        let type_names: Vec<String> = project_types.iter().map(|t| t.name.to_string()).collect();
+        let type_info = project_types[0].clone();
+        let project_type = type_info.name.to_string();
+        let metadata = app.state::<MetadataCacheState>().get(first, &type_names);
+
+        Ok(ScanResult {
+            project_type,
+            project_type_info: Some(type_info),
+            project_types,
+            frameworks: metadata.frameworks.clone(),
+            tree,
+            total_files,
+            metadata,
+            truncated,
+            monorepo: None,
+            exclusions: None,
+        })
+    })
+    .await
+    .map_err(|e| format!("Scan task failed: {}", e))?
 }
 
+// CodePack: 打包工作区，文件路径以根目录显示名为前缀
 #[tauri::command]
-pub fn list_presets(project_path: String) -> Result<HashMap<String, Vec<String>>, String> {
-    let config = load_app_config();
-    Ok(config
-        .projects
-        .get(&project_path)
-        .map(|p| p.presets.clone())
-        .unwrap_or_default())
+#[allow(clippy::too_many_arguments)]
+pub fn pack_workspace(
+    metadata_cache: tauri::State<'_, MetadataCacheState>,
+    config: tauri::State<'_, ConfigState>,
+    name: String,
+    paths: Vec<String>,
+    format: Option<ExportFormat>,
+    max_file_bytes: Option<u64>,
+    tokenizer: Option<Tokenizer>,
+    target_model: Option<String>,
+    force: Option<bool>,
+    resolve_lockfiles: Option<bool>,
+) -> Result<PackResult, String> {
+    let roots = workspace_roots(&config, &name)?;
+    let first = roots
+        .first()
+        .map(|r| r.path.clone())
+        .ok_or_else(|| "Workspace has no roots".to_string())?;
+    let project_type = detect_project_type_with_plugins(Path::new(&first), &project_plugins(&config, &first));
+    // Like the metadata, defaults and hooks come from the first root
+    let defaults = pack_defaults(&first, &config);
+    let metadata = metadata_cache.get_for_pack(Path::new(&first), &project_type);
+    let options = PackOptions {
+        format: format.or(defaults.format).unwrap_or_default(),
+        max_file_bytes: max_file_bytes.or(defaults.max_file_bytes),
+        tokenizer: resolve_tokenizer(tokenizer, &config),
+        target_model,
+        mask_secrets: defaults.mask_secrets,
+        secret_plugins: defaults.secret_plugins,
+        roots,
+        resolve_lockfiles: resolve_lockfiles.unwrap_or(false),
+        dotenv_policy: project_dotenv_policy(&config, &first),
+        header_templates: defaults.header_templates,
+        annotations: defaults.hooks.annotations,
+        metadata: Some(with_hook_metadata(metadata, defaults.hooks.metadata)),
+        ..Default::default()
+    };
+    let result = build_pack_content_with_options(&paths, &first, &project_type, &options);
+    finalize_pack(result, options.target_model.as_deref(), force, &config)
 }
 
 // ─── Plugin Commands ───────────────────────────────────────────
@@ -315,68 +1679,179 @@ pub fn list_plugins() -> Result<Vec<PluginDef>, String> {
 }
 
 #[tauri::command]
-pub fn save_plugin(plugin: PluginDef) -> Result<(), String> {
-    let dir = get_plugins_dir();
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    let filename = plugin.name.to_lowercase().replace(' ', "-") + ".json";
-    let path = dir.join(filename);
-    let json = serde_json::to_string_pretty(&plugin).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
+pub fn save_plugin(secret_cache: tauri::State<'_, SecretCacheState>, plugin: PluginDef) -> Result<(), String> {
+    validate_plugin(&plugin)?;
+    write_plugin(&plugin)?;
+    reload_plugin_secret_rules(&secret_cache);
     Ok(())
 }
 
+/// Recompiles the plugins' secret rules; cached scan results may predate them.
+/// Hook and regex errors are left out here: the plugin test report lists them
+pub fn reload_plugin_secret_rules(secret_cache: &SecretCacheState) {
+    let (rules, _) = plugin_secret_rules(&load_plugins());
+    crate::security::set_plugin_rules(rules);
+    secret_cache.clear();
+}
+
+/// Listed in a plugin test report before the list is cut off
+const PLUGIN_TEST_FILE_LIMIT: usize = 200;
+
+// CodePack: 插件试运行：检查各识别规则，并对比启用插件前后的扫描结果，不保存插件
+#[tauri::command]
+pub async fn test_plugin(plugin: PluginDef, path: String) -> Result<PluginTestReport, String> {
+    tokio::task::spawn_blocking(move || {
+        let root = Path::new(&path);
+        if !root.is_dir() {
+            return Err("Path does not exist or is not a directory".to_string());
+        }
+        let relative_files = |plugins: &[PluginDef]| -> HashSet<String> {
+            let options = apply_plugin_rules(ScanOptions::default(), plugins);
+            collect_file_paths(&build_file_tree_with_options(root, &options))
+                .into_iter()
+                .filter_map(|p| preset_entry(root, &p))
+                .collect()
+        };
+        let before = relative_files(&[]);
+        let after = relative_files(std::slice::from_ref(&plugin));
+        let mut added: Vec<String> = after.difference(&before).cloned().collect();
+        let mut removed: Vec<String> = before.difference(&after).cloned().collect();
+        added.sort();
+        removed.sort();
+        added.truncate(PLUGIN_TEST_FILE_LIMIT);
+        removed.truncate(PLUGIN_TEST_FILE_LIMIT);
+        let mut problems = plugin_problems(&plugin);
+        problems.extend(plugin_secret_rules(std::slice::from_ref(&plugin)).1);
+        Ok(PluginTestReport {
+            matches: plugin_matches(&plugin, root),
+            checks: check_plugin_rules(&plugin, root),
+            problems,
+            files_before: before.len() as u32,
+            files_after: after.len() as u32,
+            added_files: added,
+            removed_files: removed,
+        })
+    })
+    .await
+    .map_err(|e| format!("Plugin test failed: {}", e))?
+}
+
+fn registry_url(app: &tauri::AppHandle, url: Option<String>) -> Result<String, String> {
+    url.filter(|u| !u.trim().is_empty())
+        .or_else(|| app.state::<ConfigState>().read(|c| c.settings.plugin_registry_url.clone()))
+        .map(|u| u.trim().to_string())
+        .ok_or_else(|| "No plugin registry URL configured".to_string())
+}
+
+// CodePack: 获取社区插件索引；传入的 url 会保存为默认索引地址
 #[tauri::command]
-pub fn delete_plugin(name: String) -> Result<(), String> {
-    let dir = get_plugins_dir();
-    let filename = name.to_lowercase().replace(' ', "-") + ".json";
-    let path = dir.join(&filename);
+pub async fn fetch_plugin_registry(
+    app: tauri::AppHandle,
+    url: Option<String>,
+) -> Result<Vec<RegistryEntry>, String> {
+    let url = registry_url(&app, url)?;
+    let registry = fetch_registry(&url).await?;
+    app.state::<ConfigState>()
+        .update(|c| c.settings.plugin_registry_url = Some(url))?;
+    Ok(registry.plugins)
+}
+
+// CodePack: 从已配置的索引安装插件：下载 JSON、校验 sha256，通过后写入插件目录
+#[tauri::command]
+pub async fn install_plugin_from_registry(app: tauri::AppHandle, name: String) -> Result<PluginDef, String> {
+    let url = registry_url(&app, None)?;
+    let registry = fetch_registry(&url).await?;
+    let entry = registry
+        .plugins
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("Plugin '{}' not found in registry", name.trim()))?;
+    let plugin = download_plugin(&url, entry).await?;
+    save_plugin(app.state::<SecretCacheState>(), plugin.clone())?;
+    Ok(plugin)
+}
+
+#[tauri::command]
+pub fn delete_plugin(secret_cache: tauri::State<'_, SecretCacheState>, name: String) -> Result<(), String> {
+    let path = get_plugins_dir().join(plugin_file_name(&name)?);
     if path.exists() {
         fs::remove_file(&path).map_err(|e| e.to_string())?;
     }
+    reload_plugin_secret_rules(&secret_cache);
     Ok(())
 }
 
 // ─── Exclude Rules Commands ────────────────────────────────────
 
 #[tauri::command]
-pub fn save_exclude_rules(project_path: String, rules: Vec<String>) -> Result<(), String> {
-    let mut config = load_app_config();
-    if let Some(project) = config.projects.get_mut(&project_path) {
-        project.excluded_paths = rules;
-    } else {
-        let now = chrono_now();
-        config.projects.insert(
-            project_path.clone(),
-            ProjectConfig {
-                project_path,
-                checked_paths: Vec::new(),
-                excluded_paths: rules,
-                last_opened: now,
-                presets: HashMap::new(),
-                pinned: false,
-            },
-        );
-    }
-    save_app_config(&config)
+pub fn save_exclude_rules(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    rules: Vec<String>,
+) -> Result<(), String> {
+    config.update(|config| {
+        config.entry_or_default(&project_path).excluded_paths = rules;
+    })
 }
 
 #[tauri::command]
-pub fn load_exclude_rules(project_path: String) -> Result<Vec<String>, String> {
-    let config = load_app_config();
-    Ok(config
-        .projects
-        .get(&project_path)
-        .map(|p| p.excluded_paths.clone())
-        .unwrap_or_default())
+pub fn load_exclude_rules(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+) -> Result<Vec<String>, String> {
+    Ok(config.read(|c| {
+        c.projects
+            .get(&project_path)
+            .map(|p| p.excluded_paths.clone())
+            .unwrap_or_default()
+    }))
 }
 
 // ─── Git Command ───────────────────────────────────────────────
 
+const DEFAULT_FILE_HISTORY_LIMIT: usize = 20;
+
 #[tauri::command]
 pub fn get_git_status_cmd(project_path: String) -> Result<Option<crate::git::GitStatus>, String> {
     Ok(crate::git::get_git_status(&project_path))
 }
 
+#[tauri::command]
+pub fn get_file_history(
+    project_path: String,
+    file_path: String,
+    limit: Option<u32>,
+) -> Result<Vec<crate::git::CommitSummary>, String> {
+    let limit = limit.map(|l| l as usize).unwrap_or(DEFAULT_FILE_HISTORY_LIMIT);
+    crate::git::file_history(&project_path, &file_path, limit)
+}
+
+#[tauri::command]
+pub fn get_changed_paths_for_selection(
+    cache: tauri::State<'_, ScanCacheState>,
+    project_path: String,
+    include_renames: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let tree_paths = cache
+        .file_paths(&project_path)
+        .ok_or_else(|| "No previous scan for this path".to_string())?;
+    Ok(crate::git::changed_paths_for_selection(
+        &project_path,
+        &tree_paths,
+        include_renames.unwrap_or(false),
+    ))
+}
+
+#[tauri::command]
+pub fn install_git_hook(project_path: String, hook: String, options: GitHookOptions) -> Result<String, String> {
+    crate::git::install_git_hook(&project_path, hook.trim(), &options)
+}
+
+#[tauri::command]
+pub fn get_blame_summary(project_path: String, file_path: String) -> Result<crate::git::BlameSummary, String> {
+    crate::git::blame_summary(&project_path, &file_path)
+}
+
 // ─── Watcher Commands ──────────────────────────────────────────
 
 #[tauri::command]
@@ -397,29 +1872,215 @@ pub fn scan_secrets_cmd(path: String) -> Result<Vec<crate::types::SecretMatch>,
     Ok(crate::security::scan_content(&content))
 }
 
+/// Dotenv policy saved for the project; unsaved projects get the default (exclude)
+fn project_dotenv_policy(config: &ConfigState, project_path: &str) -> DotenvPolicy {
+    config.read(|c| {
+        c.projects
+            .get(project_path)
+            .map(|p| p.dotenv_policy)
+            .unwrap_or_default()
+    })
+}
+
+fn secret_baseline(config: &ConfigState, project_path: &str) -> Vec<SecretBaselineEntry> {
+    config.read(|c| {
+        c.projects
+            .get(project_path)
+            .map(|p| p.secret_baseline.clone())
+            .unwrap_or_default()
+    })
+}
+
+/// Runs the project-wide secret scan on the blocking pool, emitting `secret-scan-progress`.
+/// Fails with "Secret scan cancelled" after cancel_secret_scan for the same project
+async fn scan_project_secrets(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    project_path: String,
+) -> Result<(u32, crate::security::ProjectFindings), String> {
+    tokio::task::spawn_blocking(move || {
+        let state = app.state::<SecretScanState>();
+        let token = state.begin(&project_path);
+        let baseline = secret_baseline(&app.state::<ConfigState>(), &project_path);
+        let plugins = project_enabled_plugins(&app.state::<ConfigState>(), &project_path);
+        let files_total = paths.len() as u32;
+        crate::security::scan_project(
+            &paths,
+            Path::new(&project_path),
+            &baseline,
+            plugins.as_deref(),
+            &app.state::<SecretCacheState>(),
+            |files_done| {
+                let _ = app.emit("secret-scan-progress", SecretScanProgress { files_done, files_total });
+            },
+            || token.is_cancelled(),
+        )
+        .ok_or_else(|| "Secret scan cancelled".to_string())
+    })
+    .await
+    .map_err(|e| format!("Secret scan task failed: {}", e))?
+}
+
 #[tauri::command]
-pub fn scan_all_secrets_cmd(
+pub async fn scan_all_secrets_cmd(
+    app: tauri::AppHandle,
     paths: Vec<String>,
     project_path: String,
 ) -> Result<HashMap<String, Vec<crate::types::SecretMatch>>, String> {
+    let (_, findings) = scan_project_secrets(app, paths, project_path).await?;
+    Ok(findings.into_iter().collect())
+}
+
+#[tauri::command]
+pub fn cancel_secret_scan(state: tauri::State<'_, SecretScanState>, project_path: String) -> Result<(), String> {
+    state.cancel(&project_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn generate_security_report(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    project_path: String,
+) -> Result<SecurityReport, String> {
+    let (scanned, findings) = scan_project_secrets(app, paths, project_path.clone()).await?;
+    Ok(crate::security::build_report(&project_path, chrono_now(), scanned, findings))
+}
+
+// CodePack: 生成安全报告并写入 save_path，默认 JSON
+#[tauri::command]
+pub async fn export_security_report(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    project_path: String,
+    save_path: String,
+    format: Option<ReportFormat>,
+) -> Result<String, String> {
+    let report = generate_security_report(app, paths, project_path).await?;
+    let content = match format.unwrap_or_default() {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?,
+        ReportFormat::Markdown => crate::security::render_report_markdown(&report),
+    };
+    fs::write(&save_path, content).map_err(|e| format!("Failed to export: {}", e))?;
+    Ok(save_path)
+}
+
+#[tauri::command]
+pub fn get_secret_baseline(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+) -> Result<Vec<SecretBaselineEntry>, String> {
+    Ok(secret_baseline(&config, &project_path))
+}
+
+// CodePack: 将扫描结果加入基线；项目尚未保存过配置时只记录基线
+#[tauri::command]
+pub fn accept_secret_findings(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    entries: Vec<SecretBaselineEntry>,
+) -> Result<(), String> {
+    config.update(|config| {
+        let project = config.entry_or_default(&project_path);
+        for entry in entries {
+            if !project.secret_baseline.contains(&entry) {
+                project.secret_baseline.push(entry);
+            }
+        }
+    })
+}
+
+#[tauri::command]
+pub fn remove_secret_baseline_entry(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    path: String,
+    fingerprint: String,
+) -> Result<(), String> {
+    config.update(|config| {
+        if let Some(project) = config.projects.get_mut(&project_path) {
+            project
+                .secret_baseline
+                .retain(|entry| !(entry.path == path && entry.fingerprint == fingerprint));
+        }
+    })
+}
+
+// CodePack: 设置项目的 .env 文件策略；项目尚未保存过配置时只记录策略
+#[tauri::command]
+pub fn set_dotenv_policy(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    policy: DotenvPolicy,
+) -> Result<(), String> {
+    config.update(|config| {
+        config.entry_or_default(&project_path).dotenv_policy = policy;
+    })
+}
+
+// CodePack: 设置项目启用的插件；None 恢复为全部插件生效
+#[tauri::command]
+pub fn set_project_plugins(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    enabled: Option<Vec<String>>,
+) -> Result<(), String> {
+    config.update(|config| {
+        config.entry_or_default(&project_path).enabled_plugins = enabled;
+    })
+}
+
+// CodePack: 列出项目适用插件声明的外部命令及批准状态，界面据此请求用户确认
+#[tauri::command]
+pub fn list_plugin_commands(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+) -> Vec<PluginCommandInfo> {
+    let approved = approved_commands(&config, &project_path);
     let root = Path::new(&project_path);
-    let mut result = HashMap::new();
-    for path in &paths {
-        let content = match fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-        let matches = crate::security::scan_content(&content);
-        if !matches.is_empty() {
-            let relative = Path::new(path)
-                .strip_prefix(root)
-                .unwrap_or(Path::new(path))
-                .to_string_lossy()
-                .replace('\\', "/");
-            result.insert(relative, matches);
+    let plugins: Vec<PluginDef> = project_plugins(&config, &project_path)
+        .into_iter()
+        .filter(|p| !p.commands.is_empty())
+        .collect();
+    let globs = GlobMatches::find(root, &plugins);
+    plugins
+        .into_iter()
+        .filter(|p| plugin_evidence_with(p, root, &globs).is_some())
+        .flat_map(|plugin| {
+            plugin
+                .commands
+                .iter()
+                .map(|command| {
+                    let fingerprint = command_fingerprint(&plugin.name, command, root);
+                    PluginCommandInfo {
+                        plugin: plugin.name.clone(),
+                        stage: command.stage,
+                        command: command_line(command),
+                        approved: approved.contains(&fingerprint),
+                        fingerprint,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// CodePack: 批准或撤销某个插件命令在项目中运行
+#[tauri::command]
+pub fn set_plugin_command_approved(
+    config: tauri::State<'_, ConfigState>,
+    project_path: String,
+    fingerprint: String,
+    approved: bool,
+) -> Result<(), String> {
+    config.update(|config| {
+        let project = config.entry_or_default(&project_path);
+        project.approved_plugin_commands.retain(|f| f != &fingerprint);
+        if approved {
+            project.approved_plugin_commands.push(fingerprint);
         }
-    }
-    Ok(result)
+    })
 }
 
 #[tauri::command]
@@ -479,6 +2140,24 @@ pub async fn start_ai_review(
         return Err("请先在设置中配置 API Key".to_string());
     }
 
+    let system_msg = instruction.unwrap_or_else(|| {
+        "You are an expert code reviewer. Analyze the provided code and give a detailed review covering: security issues, performance problems, code quality, and suggestions for improvement. Format your response in Markdown.".to_string()
+    });
+    let full_content = stream_completion(&app, &config, &system_msg, &content, "review-chunk").await?;
+
+    let _ = app.emit("review-done", &full_content);
+    Ok(full_content)
+}
+
+/// Sends one system + user exchange to the configured provider, emitting each streamed
+/// delta as `chunk_event`, and returns the full reply
+async fn stream_completion(
+    app: &tauri::AppHandle,
+    config: &ApiConfig,
+    system_msg: &str,
+    content: &str,
+    chunk_event: &str,
+) -> Result<String, String> {
     let base_url = if config.base_url.is_empty() {
         match config.provider.as_str() {
             "openai" => "https://api.openai.com/v1".to_string(),
@@ -490,10 +2169,6 @@ pub async fn start_ai_review(
         config.base_url.clone()
     };
 
-    let system_msg = instruction.unwrap_or_else(|| {
-        "You are an expert code reviewer. Analyze the provided code and give a detailed review covering: security issues, performance problems, code quality, and suggestions for improvement. Format your response in Markdown.".to_string()
-    });
-
     // Build request based on provider
     let client = reqwest::Client::new();
 
@@ -563,25 +2238,152 @@ pub async fn start_ai_review(
                     // OpenAI/DeepSeek format
                     if let Some(delta) = json["choices"][0]["delta"]["content"].as_str() {
                         full_content.push_str(delta);
-                        let _ = app.emit("review-chunk", delta);
+                        let _ = app.emit(chunk_event, delta);
                     }
                     // Anthropic format
                     if let Some(text) = json["delta"]["text"].as_str() {
                         full_content.push_str(text);
-                        let _ = app.emit("review-chunk", text);
+                        let _ = app.emit(chunk_event, text);
                     }
                 }
             }
         }
     }
 
-    let _ = app.emit("review-done", &full_content);
     Ok(full_content)
 }
 
+// ─── Change Summary ────────────────────────────────────────────
+
+/// Diff-only pack of the working changes (or `base_ref..HEAD`) with `instruction` appended
+fn build_change_pack(
+    config: &ConfigState,
+    metadata_cache: &MetadataCacheState,
+    project_path: &str,
+    base_ref: Option<&str>,
+    prefer_staged: bool,
+    mut options: PackOptions,
+    instruction: &str,
+) -> Result<PackResult, String> {
+    let diffs = match base_ref {
+        Some(base) => {
+            let (_, range) = crate::git::diff_between_refs(project_path, base, "HEAD")?;
+            crate::git::FileDiffs {
+                range: Some(range),
+                ..Default::default()
+            }
+        }
+        None => {
+            let changed = crate::git::get_changed_file_paths(project_path);
+            let mut diffs = crate::git::get_diffs_for_files(project_path, &changed);
+            // A commit message describes what is staged; everything counts when nothing is
+            if prefer_staged && !diffs.staged.is_empty() {
+                diffs.unstaged.clear();
+            }
+            diffs
+        }
+    };
+    if diffs.is_empty() {
+        return Err("No changes to summarize".to_string());
+    }
+    let root = Path::new(project_path);
+    let project_type = detect_project_type_with_plugins(root, &project_plugins(config, project_path));
+    options.metadata = Some(metadata_cache.get_for_pack(root, &project_type));
+    Ok(build_pack_content_extended(&[], project_path, &project_type, &options, Some(&diffs), Some(instruction)))
+}
+
+// CodePack: 生成提交信息（kind = "commit"，默认）或 PR 描述（kind = "pr"）；draft = true 且配置了 API Key 时同时返回模型草稿
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_change_summary(
+    app: tauri::AppHandle,
+    project_path: String,
+    kind: Option<String>,
+    base_ref: Option<String>,
+    format: Option<ExportFormat>,
+    tokenizer: Option<Tokenizer>,
+    draft: Option<bool>,
+) -> Result<ChangeSummary, String> {
+    let is_pr = kind.as_deref() == Some("pr");
+    let prompt_name = if is_pr { PR_DESCRIPTION_PROMPT } else { COMMIT_MESSAGE_PROMPT };
+    // Custom prompts are loaded after the built-ins, so the last match wins
+    let instruction = load_review_prompts()
+        .into_iter()
+        .rev()
+        .find(|p| p.name == prompt_name)
+        .map(|p| p.instruction)
+        .ok_or_else(|| format!("Review prompt '{}' not found", prompt_name))?;
+
+    let pack = {
+        let app = app.clone();
+        let instruction = instruction.clone();
+        let base_ref = base_ref.filter(|r| !r.trim().is_empty());
+        tokio::task::spawn_blocking(move || {
+            let config = app.state::<ConfigState>();
+            let defaults = pack_defaults(&project_path, &config);
+            // Masking covers the diffs too, so a draft never sends what the policy hides
+            let options = PackOptions {
+                format: format.or(defaults.format).unwrap_or_default(),
+                tokenizer: resolve_tokenizer(tokenizer, &config),
+                mask_secrets: defaults.mask_secrets,
+                secret_plugins: defaults.secret_plugins,
+                ..Default::default()
+            };
+            build_change_pack(
+                &config,
+                &app.state::<MetadataCacheState>(),
+                &project_path,
+                base_ref.as_deref().map(str::trim),
+                !is_pr,
+                options,
+                &instruction,
+            )
+        })
+        .await
+        .map_err(|e| format!("Change summary task failed: {}", e))??
+    };
+
+    let config = load_api_config();
+    let draft = if draft.unwrap_or(false) && !config.api_key.is_empty() {
+        Some(stream_completion(&app, &config, &instruction, &pack.content, "change-summary-chunk").await?)
+    } else {
+        None
+    };
+    Ok(ChangeSummary {
+        prompt: pack.content,
+        estimated_tokens: pack.estimated_tokens,
+        draft,
+    })
+}
+
 // ─── Stats Command ─────────────────────────────────────────────
 
+/// Files listed per ranking in ProjectStats.top_files when the caller doesn't say
+const DEFAULT_TOP_FILES: usize = 10;
+
+#[tauri::command]
+pub fn get_project_stats(
+    config: tauri::State<'_, ConfigState>,
+    paths: Vec<String>,
+    tokenizer: Option<Tokenizer>,
+    top: Option<usize>,
+) -> Result<ProjectStats, String> {
+    Ok(compute_project_stats(
+        &paths,
+        resolve_tokenizer(tokenizer, &config),
+        top.unwrap_or(DEFAULT_TOP_FILES),
+    ))
+}
+
+/// Months of history covered by get_churn_stats when the caller doesn't say
+const DEFAULT_CHURN_MONTHS: u32 = 6;
+
+#[tauri::command]
+pub fn get_churn_stats(project_path: String, months: Option<u32>) -> Result<ChurnStats, String> {
+    compute_churn_stats(&project_path, months.unwrap_or(DEFAULT_CHURN_MONTHS))
+}
+
 #[tauri::command]
-pub fn get_project_stats(paths: Vec<String>) -> Result<ProjectStats, String> {
-    Ok(compute_project_stats(&paths))
+pub fn get_contributors(project_path: String) -> Result<Vec<Contributor>, String> {
+    crate::git::contributors(&project_path)
 }
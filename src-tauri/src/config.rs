@@ -1,34 +1,223 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::types::{ApiConfig, AppConfig, ReviewPrompt};
+use crate::plugins::{command_fingerprint, load_plugins, validate_plugin, write_plugin, PluginDef};
+use crate::scanner::preset_entry;
+use crate::types::{ApiConfig, AppConfig, ConfigRecovery, Preset, ProjectFileConfig, ReviewPrompt, SettingsBundle};
 
 pub fn get_config_path() -> PathBuf {
     let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
     base.join("codepack_config.json")
 }
 
-pub fn load_app_config() -> AppConfig {
-    let path = get_config_path();
-    if path.exists() {
-        if let Ok(data) = fs::read_to_string(&path) {
-            if let Ok(config) = serde_json::from_str::<AppConfig>(&data) {
-                return config;
+fn load_app_config(path: &Path) -> AppConfig {
+    let (config, recovery) = load_app_config_from(path);
+    if let Some(recovery) = recovery {
+        if let Ok(mut pending) = CONFIG_RECOVERY.lock() {
+            *pending = Some(recovery);
+        }
+    }
+    config
+}
+
+fn save_app_config(path: &Path, config: &AppConfig) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    write_atomic(path, &json, true)
+}
+
+// ─── Cross-Instance Locking ──────────────────────────────────
+
+/// Modification time and size of the config file, used to notice writes by other instances
+type FileStamp = (SystemTime, u64);
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+// CodePack: 多个 CodePack 实例共用同一配置文件；读写都先拿 `<config>.lock` 上的排他咨询锁，drop 时释放
+struct ConfigFileLock(fs::File);
+
+impl ConfigFileLock {
+    fn acquire(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sibling(path, "lock"))
+            .map_err(|e| format!("Failed to open config lock: {}", e))?;
+        // Exclusive even for loads: recovering a corrupted file writes it back
+        file.lock().map_err(|e| format!("Failed to lock config file: {}", e))?;
+        Ok(Self(file))
+    }
+}
+
+impl Drop for ConfigFileLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+// ─── Managed State ─────────────────────────────────────────────
+
+struct LoadedConfig {
+    config: AppConfig,
+    stamp: Option<FileStamp>,
+}
+
+impl LoadedConfig {
+    fn load(path: &Path) -> Self {
+        let _lock = ConfigFileLock::acquire(path);
+        Self {
+            config: load_app_config(path),
+            stamp: file_stamp(path),
+        }
+    }
+}
+
+// CodePack: 内存中的配置副本；写操作在写锁内修改并落盘，并发命令不会再互相覆盖。
+// 文件被其他实例改过（mtime/大小变化）时先重新加载，再读或改
+pub struct ConfigState {
+    path: PathBuf,
+    loaded: RwLock<LoadedConfig>,
+}
+
+impl Default for ConfigState {
+    fn default() -> Self {
+        Self::at(get_config_path())
+    }
+}
+
+impl ConfigState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn at(path: PathBuf) -> Self {
+        let loaded = LoadedConfig::load(&path);
+        Self {
+            path,
+            loaded: RwLock::new(loaded),
+        }
+    }
+
+    /// Copy of the current config, for commands that hand it to blocking work
+    pub fn snapshot(&self) -> AppConfig {
+        self.read(AppConfig::clone)
+    }
+
+    pub fn read<T>(&self, f: impl FnOnce(&AppConfig) -> T) -> T {
+        {
+            let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+            if loaded.stamp == file_stamp(&self.path) {
+                return f(&loaded.config);
             }
         }
+        let mut loaded = self.loaded.write().unwrap_or_else(|e| e.into_inner());
+        if loaded.stamp != file_stamp(&self.path) {
+            *loaded = LoadedConfig::load(&self.path);
+        }
+        f(&loaded.config)
+    }
+
+    /// Applies `f` and persists the result while holding both the write lock and the file lock,
+    /// so an update always starts from the latest config of this or any other instance
+    pub fn update<T>(&self, f: impl FnOnce(&mut AppConfig) -> T) -> Result<T, String> {
+        self.try_update(|config| Ok(f(config)))
+    }
+
+    /// Like `update`, but an `Err` from `f` leaves the config untouched. `f` works on a copy
+    /// that only replaces the held config once it has been saved
+    pub fn try_update<T>(&self, f: impl FnOnce(&mut AppConfig) -> Result<T, String>) -> Result<T, String> {
+        let mut loaded = self.loaded.write().unwrap_or_else(|e| e.into_inner());
+        let _lock = ConfigFileLock::acquire(&self.path)?;
+        if loaded.stamp != file_stamp(&self.path) {
+            loaded.config = load_app_config(&self.path);
+        }
+        let mut config = loaded.config.clone();
+        let result = f(&mut config)?;
+        save_app_config(&self.path, &config)?;
+        loaded.config = config;
+        loaded.stamp = file_stamp(&self.path);
+        Ok(result)
     }
-    AppConfig::default()
 }
 
-pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
-    let path = get_config_path();
+// ─── Atomic Writes & Recovery ────────────────────────────────
+
+/// Set when the last load found a corrupted config; handed to the UI once by take_config_recovery
+static CONFIG_RECOVERY: Mutex<Option<ConfigRecovery>> = Mutex::new(None);
+
+/// `codepack_config.json` → `codepack_config.json.<suffix>`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// CodePack: 先写临时文件再 rename，写到一半崩溃也不会留下半个配置；backup 时旧文件先转为 .bak
+fn write_atomic(path: &Path, contents: &str, backup: bool) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
-    Ok(())
+    let tmp = sibling(path, "tmp");
+    {
+        let mut file = fs::File::create(&tmp).map_err(|e| e.to_string())?;
+        file.write_all(contents.as_bytes()).map_err(|e| e.to_string())?;
+        file.sync_all().map_err(|e| e.to_string())?;
+    }
+    if backup && path.is_file() {
+        // The previous file only becomes the backup if it still parses
+        let current_ok = fs::read_to_string(path)
+            .ok()
+            .is_some_and(|data| serde_json::from_str::<serde_json::Value>(&data).is_ok());
+        if current_ok {
+            fs::copy(path, sibling(path, "bak")).map_err(|e| e.to_string())?;
+        }
+    }
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+/// Loads the config at `path`. A file that fails to parse is kept as `.corrupt` and replaced by
+/// the `.bak` when that one parses; the second value describes what happened
+fn load_app_config_from(path: &Path) -> (AppConfig, Option<ConfigRecovery>) {
+    let Ok(data) = fs::read_to_string(path) else {
+        return (AppConfig::default(), None);
+    };
+    let error = match serde_json::from_str::<AppConfig>(&data) {
+        Ok(config) => return (config, None),
+        Err(e) => e.to_string(),
+    };
+
+    let corrupt = sibling(path, "corrupt");
+    let corrupt_copy = fs::copy(path, &corrupt).ok().map(|_| crate::paths::display(&corrupt));
+    let backup = sibling(path, "bak");
+    let restored = fs::read_to_string(&backup)
+        .ok()
+        .and_then(|data| serde_json::from_str::<AppConfig>(&data).ok().map(|config| (config, data)));
+    if let Some((_, ref data)) = restored {
+        let _ = write_atomic(path, data, false);
+    }
+    let recovery = ConfigRecovery {
+        path: crate::paths::display(path),
+        error,
+        restored_from_backup: restored.is_some(),
+        corrupt_copy,
+    };
+    (restored.map(|(config, _)| config).unwrap_or_default(), Some(recovery))
+}
+
+/// The corruption found by the last load, if any; cleared once taken
+pub fn take_config_recovery() -> Option<ConfigRecovery> {
+    CONFIG_RECOVERY.lock().ok()?.take()
 }
 
 pub fn chrono_now() -> String {
@@ -38,6 +227,26 @@ pub fn chrono_now() -> String {
     format!("{}", duration.as_secs())
 }
 
+// ─── Project File Config ─────────────────────────────────────
+
+/// Checked in this order at the project root; the first one present wins
+pub const PROJECT_CONFIG_FILES: &[&str] = &["codepack.toml", ".codepack.json"];
+
+// CodePack: 读取项目根目录的共享配置；不存在时为 None，格式错误时报错
+pub fn load_project_file_config(root: &Path) -> Result<Option<ProjectFileConfig>, String> {
+    let Some(name) = PROJECT_CONFIG_FILES.iter().find(|name| root.join(name).is_file()) else {
+        return Ok(None);
+    };
+    let data = fs::read_to_string(root.join(name))
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let config = if name.ends_with(".toml") {
+        toml::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", name, e))?
+    } else {
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", name, e))?
+    };
+    Ok(Some(config))
+}
+
 // ─── API Config ─────────────────────────────────────────────
 
 fn get_api_config_path() -> PathBuf {
@@ -58,13 +267,8 @@ pub fn load_api_config() -> ApiConfig {
 }
 
 pub fn save_api_config(config: &ApiConfig) -> Result<(), String> {
-    let path = get_api_config_path();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
     let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
-    Ok(())
+    write_atomic(&get_api_config_path(), &json, false)
 }
 
 // ─── Review Prompts ──────────────────────────────────────────
@@ -74,6 +278,10 @@ fn get_review_prompts_path() -> PathBuf {
     base.join("codepack_review_prompts.json")
 }
 
+/// Built-in prompts used by generate_change_summary; a custom prompt with the same name replaces them
+pub const COMMIT_MESSAGE_PROMPT: &str = "Commit Message";
+pub const PR_DESCRIPTION_PROMPT: &str = "PR Description";
+
 fn builtin_prompts() -> Vec<ReviewPrompt> {
     vec![
         ReviewPrompt {
@@ -94,32 +302,37 @@ fn builtin_prompts() -> Vec<ReviewPrompt> {
             instruction: "You are a clean code advocate. Focus your review on:\n- SOLID principles violations\n- Code smells and anti-patterns\n- Naming conventions and readability\n- DRY principle (Don't Repeat Yourself)\n- Function/method length and complexity\n- Error handling patterns\n- Test coverage gaps\nSuggest refactoring with concrete before/after examples.".to_string(),
             builtin: true,
         },
+        ReviewPrompt {
+            name: COMMIT_MESSAGE_PROMPT.to_string(),
+            icon: "\u{1f4dd}".to_string(), // 📝
+            instruction: "You write git commit messages. From the diff below, write one commit message:\n- A subject line in the imperative mood, at most 72 characters, no trailing period\n- A blank line, then a short body explaining what changed and why, wrapped at 72 characters\n- Mention breaking changes explicitly\nReply with the commit message only.".to_string(),
+            builtin: true,
+        },
+        ReviewPrompt {
+            name: PR_DESCRIPTION_PROMPT.to_string(),
+            icon: "\u{1f4cb}".to_string(), // 📋
+            instruction: "You write pull request descriptions. From the diff below, write a Markdown description with these sections:\n## Summary\nOne or two sentences on what the change does and why.\n## Changes\nA bullet list of notable changes, grouped by area.\n## Testing\nHow the change can be verified.\n## Risks\nBreaking changes, migrations or anything reviewers should look at closely.\nReply with the description only.".to_string(),
+            builtin: true,
+        },
     ]
 }
 
+fn load_custom_review_prompts() -> Vec<ReviewPrompt> {
+    fs::read_to_string(get_review_prompts_path())
+        .ok()
+        .and_then(|d| serde_json::from_str(&d).ok())
+        .unwrap_or_default()
+}
+
 pub fn load_review_prompts() -> Vec<ReviewPrompt> {
     let mut prompts = builtin_prompts();
-    let path = get_review_prompts_path();
-    if path.exists() {
-        if let Ok(data) = fs::read_to_string(&path) {
-            if let Ok(custom) = serde_json::from_str::<Vec<ReviewPrompt>>(&data) {
-                prompts.extend(custom);
-            }
-        }
-    }
+    prompts.extend(load_custom_review_prompts());
     prompts
 }
 
 pub fn save_custom_review_prompt(prompt: &ReviewPrompt) -> Result<(), String> {
     let path = get_review_prompts_path();
-    let mut custom: Vec<ReviewPrompt> = if path.exists() {
-        fs::read_to_string(&path)
-            .ok()
-            .and_then(|d| serde_json::from_str(&d).ok())
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    let mut custom = load_custom_review_prompts();
     // Update existing or add new
     if let Some(existing) = custom.iter_mut().find(|p| p.name == prompt.name) {
         existing.icon = prompt.icon.clone();
@@ -128,11 +341,7 @@ pub fn save_custom_review_prompt(prompt: &ReviewPrompt) -> Result<(), String> {
         custom.push(prompt.clone());
     }
     let json = serde_json::to_string_pretty(&custom).map_err(|e| e.to_string())?;
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
-    fs::write(&path, json).map_err(|e| e.to_string())?;
-    Ok(())
+    write_atomic(&path, &json, false)
 }
 
 pub fn delete_custom_review_prompt(name: &str) -> Result<(), String> {
@@ -146,6 +355,381 @@ pub fn delete_custom_review_prompt(name: &str) -> Result<(), String> {
         .unwrap_or_default();
     custom.retain(|p| p.name != name);
     let json = serde_json::to_string_pretty(&custom).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
+    write_atomic(&path, &json, false)
+}
+
+// ─── Settings Bundle ─────────────────────────────────────────
+
+pub const SETTINGS_BUNDLE_VERSION: u32 = 1;
+pub const PRESET_BUNDLE_VERSION: u32 = 1;
+
+pub fn export_settings_bundle(state: &ConfigState) -> SettingsBundle {
+    let mut app_config = state.snapshot();
+    // Approvals are for this machine's copies of the plugin scripts, so they don't travel
+    for project in app_config.projects.values_mut() {
+        project.approved_plugin_commands.clear();
+    }
+    SettingsBundle {
+        version: SETTINGS_BUNDLE_VERSION,
+        exported_at: chrono_now(),
+        app_config,
+        plugins: load_plugins(),
+        review_prompts: load_custom_review_prompts(),
+    }
+}
+
+// CodePack: 导入时合并而非覆盖：同名项目、工作区、价格覆盖以导入包为准，全局设置整体替换；
+// 插件命令的批准不随导入包生效，同名项目保留本机已有的批准
+pub fn merge_app_config(current: &mut AppConfig, incoming: AppConfig) {
+    for (path, mut project) in incoming.projects {
+        project.approved_plugin_commands = current
+            .projects
+            .remove(&path)
+            .map(|p| p.approved_plugin_commands)
+            .unwrap_or_default();
+        current.projects.insert(path, project);
+    }
+    current.workspaces.extend(incoming.workspaces);
+    current.model_prices.extend(incoming.model_prices);
+    current.settings = incoming.settings;
+    current.locale = incoming.locale;
+}
+
+pub fn validate_preset_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Preset name must not be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+// CodePack: 合并预设文件：名称无效或有条目指向项目根目录外时整个文件被拒绝；同名预设仅在 overwrite 时替换。返回导入的预设名
+pub fn merge_presets(
+    presets: &mut HashMap<String, Preset>,
+    root: &Path,
+    incoming: HashMap<String, Preset>,
+    overwrite: bool,
+) -> Result<Vec<String>, String> {
+    let mut checked = Vec::new();
+    for (name, mut preset) in incoming {
+        let name = validate_preset_name(&name)?;
+        preset.paths = preset
+            .paths
+            .iter()
+            .map(|entry| {
+                preset_entry(root, entry)
+                    .ok_or_else(|| format!("Preset '{}' has an entry outside the project: {}", name, entry))
+            })
+            .collect::<Result<_, _>>()?;
+        checked.push((name, preset));
+    }
+    let mut imported = Vec::new();
+    for (name, preset) in checked {
+        if overwrite || !presets.contains_key(&name) {
+            presets.insert(name.clone(), preset);
+            imported.push(name);
+        }
+    }
+    imported.sort();
+    Ok(imported)
+}
+
+/// Withdraws the approvals of `plugins`' commands in every project, so imported plugins are asked about again
+pub fn revoke_plugin_commands(config: &mut AppConfig, plugins: &[PluginDef]) {
+    for (path, project) in config.projects.iter_mut() {
+        let root = Path::new(path);
+        let revoked: Vec<String> = plugins
+            .iter()
+            .flat_map(|plugin| plugin.commands.iter().map(|c| command_fingerprint(&plugin.name, c, root)))
+            .collect();
+        project.approved_plugin_commands.retain(|f| !revoked.contains(f));
+    }
+}
+
+pub fn import_settings_bundle(state: &ConfigState, bundle: SettingsBundle) -> Result<(), String> {
+    if bundle.version > SETTINGS_BUNDLE_VERSION {
+        return Err(format!(
+            "Settings bundle version {} is newer than supported ({})",
+            bundle.version, SETTINGS_BUNDLE_VERSION
+        ));
+    }
+    // Checked up front so a bad plugin rejects the whole bundle before anything is written
+    for plugin in &bundle.plugins {
+        validate_plugin(plugin)?;
+    }
+    state.update(|config| {
+        merge_app_config(config, bundle.app_config);
+        revoke_plugin_commands(config, &bundle.plugins);
+    })?;
+    for plugin in &bundle.plugins {
+        write_plugin(plugin)?;
+    }
+    for prompt in bundle.review_prompts.iter().filter(|p| !p.builtin) {
+        save_custom_review_prompt(prompt)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ExportFormat, ProjectConfig, SecretPolicy, Tokenizer};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_project_file_config_toml() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(load_project_file_config(dir.path()), Ok(None));
+
+        fs::write(
+            dir.path().join("codepack.toml"),
+            "excludes = [\"fixtures\"]\nextra_extensions = [\".proto\"]\nformat = \"markdown\"\nmax_file_bytes = 65536\n\n[presets]\napi = [\"src/api\", \"README.md\"]\n\n[presets.docs]\npaths = [\"docs\"]\nformat = \"xml\"\norder_by_churn = 6\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join(".codepack.json"), "{\"format\": \"xml\"}").unwrap();
+        let config = load_project_file_config(dir.path()).unwrap().unwrap();
+        assert_eq!(config.excludes, vec!["fixtures"]);
+        assert_eq!(config.extra_extensions, vec![".proto"]);
+        assert_eq!(config.format, Some(ExportFormat::Markdown));
+        assert_eq!(config.max_file_bytes, Some(65536));
+        assert_eq!(config.presets["api"].paths, vec!["src/api", "README.md"]);
+        assert_eq!(config.presets["api"].format, None);
+        assert_eq!(config.presets["docs"].paths, vec!["docs"]);
+        assert_eq!(config.presets["docs"].format, Some(ExportFormat::Xml));
+        assert_eq!(config.presets["docs"].order_by_churn, Some(6));
+    }
+
+    #[test]
+    fn test_presets_keep_legacy_paths() {
+        let config: AppConfig = serde_json::from_str(
+            "{\"projects\": {\"/p\": {\"project_path\": \"/p\", \"checked_paths\": [], \"excluded_paths\": [], \"last_opened\": \"0\", \"presets\": {\"old\": [\"/p/src\"], \"new\": {\"paths\": [\"/p/lib\"], \"excludes\": [\"fixtures\"], \"review_prompt\": \"Security\"}}}}}",
+        )
+        .unwrap();
+        let presets = &config.projects["/p"].presets;
+        assert_eq!(presets["old"].paths, vec!["/p/src"]);
+        assert!(presets["old"].excludes.is_empty());
+        assert_eq!(presets["new"].excludes, vec!["fixtures"]);
+        assert_eq!(presets["new"].review_prompt.as_deref(), Some("Security"));
+
+        // Saved back in the new form, which loads to the same presets
+        let json = serde_json::to_string(&config).unwrap();
+        let reloaded: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.projects["/p"].presets, *presets);
+    }
+
+    #[test]
+    fn test_load_project_file_config_json_and_errors() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".codepack.json"), "{\"format\": \"xml\"}").unwrap();
+        let config = load_project_file_config(dir.path()).unwrap().unwrap();
+        assert_eq!(config.format, Some(ExportFormat::Xml));
+        assert!(config.excludes.is_empty());
+
+        fs::write(dir.path().join("codepack.toml"), "excludes = \"fixtures\"").unwrap();
+        let err = load_project_file_config(dir.path()).unwrap_err();
+        assert!(err.starts_with("Failed to parse codepack.toml"));
+    }
+
+    #[test]
+    fn test_app_settings_keep_legacy_tokenizer() {
+        let legacy = "{\"projects\": {}, \"default_tokenizer\": \"o200k_base\", \"locale\": \"en\"}";
+        let config: AppConfig = serde_json::from_str(legacy).unwrap();
+        assert_eq!(config.settings.default_tokenizer, Tokenizer::O200kBase);
+        assert_eq!(config.settings.secret_policy, SecretPolicy::Allow);
+        assert!(config.settings.default_format.is_none());
+
+        let mut config = config;
+        config.settings.default_excludes = vec!["fixtures".to_string()];
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["default_tokenizer"], "o200k_base");
+        assert_eq!(saved["default_excludes"][0], "fixtures");
+    }
+
+    #[test]
+    fn test_merge_app_config() {
+        let mut current: AppConfig = serde_json::from_str(
+            "{\"projects\": {}, \"model_prices\": {\"gpt-4o\": 2.5, \"claude\": 3.0}}",
+        )
+        .unwrap();
+        let incoming: AppConfig = serde_json::from_str(
+            "{\"projects\": {\"/repo\": {\"project_path\": \"/repo\", \"checked_paths\": [], \"excluded_paths\": [], \"last_opened\": \"0\"}}, \"model_prices\": {\"claude\": 1.0}, \"default_tokenizer\": \"claude\"}",
+        )
+        .unwrap();
+        merge_app_config(&mut current, incoming);
+        assert!(current.projects.contains_key("/repo"));
+        assert_eq!(current.model_prices["gpt-4o"], 2.5);
+        assert_eq!(current.model_prices["claude"], 1.0);
+        assert_eq!(current.settings.default_tokenizer, Tokenizer::Claude);
+    }
+
+    #[test]
+    fn test_import_drops_command_approvals() {
+        let command: crate::plugins::PluginCommand =
+            serde_json::from_str("{\"stage\": \"pre_pack\", \"program\": \"./tool.sh\"}").unwrap();
+        let plugin = PluginDef {
+            name: "Tool".to_string(),
+            commands: vec![command.clone()],
+            ..Default::default()
+        };
+        let approved = command_fingerprint("Tool", &command, Path::new("/repo"));
+        let project = |approvals: Vec<String>| ProjectConfig {
+            approved_plugin_commands: approvals,
+            ..ProjectConfig::new("/repo".to_string())
+        };
+
+        let dir = TempDir::new().unwrap();
+        let state = ConfigState::at(dir.path().join("codepack_config.json"));
+        state
+            .update(|c| {
+                c.projects.insert("/repo".to_string(), project(vec![approved.clone(), "other".to_string()]));
+                c.projects.insert("/local".to_string(), project(vec!["local".to_string()]));
+            })
+            .unwrap();
+        let bundle = export_settings_bundle(&state);
+        assert!(bundle.app_config.projects.values().all(|p| p.approved_plugin_commands.is_empty()));
+
+        // Approvals in the bundle are ignored; local ones survive unless they belong to an imported plugin
+        let mut app_config = AppConfig::default();
+        app_config.projects.insert("/repo".to_string(), project(vec!["smuggled".to_string()]));
+        app_config.projects.insert("/new".to_string(), project(vec!["smuggled".to_string()]));
+        let mut current = state.snapshot();
+        merge_app_config(&mut current, app_config);
+        revoke_plugin_commands(&mut current, &[plugin]);
+        assert_eq!(current.projects["/repo"].approved_plugin_commands, vec!["other"]);
+        assert_eq!(current.projects["/local"].approved_plugin_commands, vec!["local"]);
+        assert!(current.projects["/new"].approved_plugin_commands.is_empty());
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_plugin_name() {
+        let dir = TempDir::new().unwrap();
+        let state = ConfigState::at(dir.path().join("codepack_config.json"));
+        let mut app_config = AppConfig::default();
+        app_config.model_prices.insert("gpt-4o".into(), 2.5);
+        let bundle = SettingsBundle {
+            version: SETTINGS_BUNDLE_VERSION,
+            exported_at: String::new(),
+            app_config,
+            plugins: vec![serde_json::from_str("{\"name\": \"../../evil\"}").unwrap()],
+            review_prompts: Vec::new(),
+        };
+        assert!(import_settings_bundle(&state, bundle).is_err());
+        assert!(state.read(|c| c.model_prices.is_empty()));
+    }
+
+    #[test]
+    fn test_merge_presets() {
+        let root = Path::new("/repo");
+        let preset = |paths: &[&str]| Preset {
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let incoming = || {
+            HashMap::from([
+                ("api".to_string(), preset(&["/repo/src/api", "./README.md"])),
+                (" docs ".to_string(), preset(&["docs/**/*.md"])),
+            ])
+        };
+
+        let mut presets = HashMap::from([("api".to_string(), preset(&["src/old.rs"]))]);
+        assert_eq!(merge_presets(&mut presets, root, incoming(), false).unwrap(), vec!["docs"]);
+        assert_eq!(presets["api"].paths, vec!["src/old.rs"]);
+        assert_eq!(presets["docs"].paths, vec!["docs/**/*.md"]);
+
+        assert_eq!(merge_presets(&mut presets, root, incoming(), true).unwrap(), vec!["api", "docs"]);
+        assert_eq!(presets["api"].paths, vec!["src/api", "README.md"]);
+
+        // One bad entry or name rejects the whole file
+        let escaping = HashMap::from([
+            ("ok".to_string(), preset(&["src"])),
+            ("leak".to_string(), preset(&["../other/secrets.env"])),
+        ]);
+        assert!(merge_presets(&mut presets, root, escaping, true).is_err());
+        let unnamed = HashMap::from([(" ".to_string(), preset(&["src"]))]);
+        assert!(merge_presets(&mut presets, root, unnamed, true).is_err());
+        assert_eq!(presets.len(), 2);
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_backup_and_recovers() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("codepack_config.json");
+        let first = "{\"projects\": {}, \"default_tokenizer\": \"claude\"}";
+        write_atomic(&path, first, true).unwrap();
+        assert!(!sibling(&path, "bak").exists());
+        write_atomic(&path, "{\"projects\": {}}", true).unwrap();
+        assert_eq!(fs::read_to_string(sibling(&path, "bak")).unwrap(), first);
+        assert!(!sibling(&path, "tmp").exists());
+
+        // Truncated mid-write: the backup is loaded and written back, the broken file kept aside
+        fs::write(&path, "{\"projects\": {").unwrap();
+        let (config, recovery) = load_app_config_from(&path);
+        let recovery = recovery.unwrap();
+        assert!(recovery.restored_from_backup);
+        assert_eq!(config.settings.default_tokenizer, Tokenizer::Claude);
+        assert_eq!(fs::read_to_string(sibling(&path, "corrupt")).unwrap(), "{\"projects\": {");
+        assert_eq!(fs::read_to_string(&path).unwrap(), first);
+        assert!(load_app_config_from(&path).1.is_none());
+
+        // A corrupt file never replaces a good backup
+        fs::write(&path, "").unwrap();
+        write_atomic(&path, first, true).unwrap();
+        assert_eq!(fs::read_to_string(sibling(&path, "bak")).unwrap(), first);
+    }
+
+    #[test]
+    fn test_load_corrupt_config_without_backup() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("codepack_config.json");
+        assert!(load_app_config_from(&path).1.is_none());
+        fs::write(&path, "not json").unwrap();
+        let (config, recovery) = load_app_config_from(&path);
+        assert!(config.projects.is_empty());
+        let recovery = recovery.unwrap();
+        assert!(!recovery.restored_from_backup);
+        assert!(recovery.corrupt_copy.is_some());
+    }
+    #[test]
+    fn test_config_state_sees_other_instance() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("codepack_config.json");
+        let first = ConfigState::at(path.clone());
+        let second = ConfigState::at(path.clone());
+
+        first.update(|c| c.model_prices.insert("gpt-4o".into(), 2.5)).unwrap();
+        assert_eq!(second.read(|c| c.model_prices.get("gpt-4o").copied()), Some(2.5));
+
+        // The second instance builds on the first one's write instead of overwriting it
+        second.update(|c| c.model_prices.insert("claude".into(), 3.0)).unwrap();
+        let merged = first.snapshot();
+        assert_eq!(merged.model_prices.len(), 2);
+        assert!(sibling(&path, "lock").exists());
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let dir = TempDir::new().unwrap();
+        let state = ConfigState::at(dir.path().join("codepack_config.json"));
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let state = &state;
+                scope.spawn(move || state.update(|c| c.model_prices.insert(format!("model-{}", i), 1.0)).unwrap());
+            }
+        });
+        assert_eq!(state.read(|c| c.model_prices.len()), 8);
+        assert_eq!(ConfigState::at(state.path.clone()).snapshot().model_prices.len(), 8);
+    }
+
+    #[test]
+    fn test_failed_update_is_not_saved() {
+        let dir = TempDir::new().unwrap();
+        let state = ConfigState::at(dir.path().join("codepack_config.json"));
+        let result: Result<(), String> = state.try_update(|c| {
+            c.model_prices.insert("gpt-4o".into(), 2.5);
+            Err("rejected".to_string())
+        });
+        assert_eq!(result, Err("rejected".to_string()));
+        assert!(state.read(|c| c.model_prices.is_empty()));
+        assert!(!state.path.exists());
+    }
+}
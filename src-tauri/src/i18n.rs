@@ -0,0 +1,32 @@
+use crate::types::{Locale, ProjectType};
+
+// CodePack: 显示文本本地化；后端序列化的值保持语言无关，仅在展示时翻译
+
+/// Display name of a project type. Detected types are proper nouns (Rust, Next.js...)
+/// and stay as they are; only the generic fallback is translated
+pub fn project_type_label(project_type: &ProjectType, locale: Locale) -> String {
+    match (project_type, locale) {
+        (ProjectType::Generic, Locale::En) => "Generic".to_string(),
+        (ProjectType::Generic, Locale::ZhCn) => "通用".to_string(),
+        (ProjectType::Named(name), _) => name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_type_label() {
+        assert_eq!(project_type_label(&ProjectType::Generic, Locale::En), "Generic");
+        assert_eq!(project_type_label(&ProjectType::Generic, Locale::ZhCn), "通用");
+        assert_eq!(project_type_label(&ProjectType::from("Rust"), Locale::ZhCn), "Rust");
+    }
+
+    #[test]
+    fn test_legacy_generic_name_round_trips() {
+        let legacy: ProjectType = serde_json::from_str("\"通用\"").unwrap();
+        assert_eq!(legacy, ProjectType::Generic);
+        assert_eq!(serde_json::to_string(&legacy).unwrap(), "\"generic\"");
+    }
+}
@@ -1,16 +1,25 @@
 pub mod types;
 pub mod config;
+pub mod i18n;
 pub mod plugins;
 pub mod scanner;
+pub mod scan_cache;
 pub mod metadata;
+pub mod maven;
+pub mod monorepo;
 pub mod stats;
+pub mod tokens;
 pub mod packer;
+pub mod paths;
 pub mod git;
 pub mod security;
 pub mod watcher;
+pub mod workspace;
+pub mod wasm;
 pub mod commands;
 
 use commands::*;
+use tauri::Manager;
 
 pub fn run() {
     tauri::Builder::default()
@@ -18,13 +27,53 @@ pub fn run() {
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_fs::init())
         .manage(watcher::WatcherState::new())
+        .manage(tokens::TokenCacheState::new())
+        .manage(scanner::ScanCacheState::new())
+        .manage(metadata::MetadataCacheState::new())
+        .manage(security::SecretScanState::new())
+        .manage(security::SecretCacheState::new())
+        .manage(config::ConfigState::new())
+        .setup(|app| {
+            let tokenizer = app.state::<config::ConfigState>().read(|c| c.settings.default_tokenizer);
+            tokens::warm_up(tokenizer);
+            // WASM modules are compiled off the main thread; scans before that use the built-in rules
+            std::thread::spawn(|| security::set_plugin_rules(plugins::plugin_secret_rules(&plugins::load_plugins()).0));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_directory,
             scan_directory_async,
+            scan_directory_cached,
+            scan_remote_repo,
+            clear_remote_repos,
+            scan_directory_shallow,
+            refresh_metadata,
+            rescan_directory,
+            expand_directory,
+            select_tests,
+            apply_modified_since,
             read_file_content,
             save_project_config,
             load_project_config,
+            list_projects,
+            set_project_pinned,
             estimate_tokens,
+            estimate_tokens_detailed,
+            estimate_tokens_tree,
+            annotate_tree_tokens,
+            is_tokenizer_ready,
+            get_default_tokenizer,
+            set_default_tokenizer,
+            get_app_settings,
+            set_app_settings,
+            take_config_recovery,
+            export_settings,
+            import_settings,
+            get_locale,
+            set_locale,
+            get_project_type_label,
+            get_model_prices,
+            set_model_price,
             pack_files,
             copy_to_clipboard,
             export_to_file,
@@ -32,19 +81,51 @@ pub fn run() {
             get_file_size,
             save_preset,
             delete_preset,
+            rename_preset,
+            duplicate_preset,
+            export_presets,
+            import_presets,
             list_presets,
+            resolve_preset,
+            get_project_file_config,
+            save_workspace,
+            delete_workspace,
+            list_workspaces,
+            scan_workspace,
+            pack_workspace,
             list_plugins,
             save_plugin,
             delete_plugin,
+            fetch_plugin_registry,
+            install_plugin_from_registry,
+            test_plugin,
             get_project_stats,
+            get_churn_stats,
+            get_contributors,
             save_exclude_rules,
             load_exclude_rules,
             get_git_status_cmd,
+            get_changed_paths_for_selection,
+            get_file_history,
+            get_blame_summary,
+            install_git_hook,
             start_watching_cmd,
             stop_watching_cmd,
             pack_files_extended,
+            pack_commit_range,
+            pack_at_ref,
             scan_secrets_cmd,
             scan_all_secrets_cmd,
+            cancel_secret_scan,
+            generate_security_report,
+            export_security_report,
+            get_secret_baseline,
+            accept_secret_findings,
+            remove_secret_baseline_entry,
+            set_dotenv_policy,
+            set_project_plugins,
+            list_plugin_commands,
+            set_plugin_command_approved,
             mask_file_secrets_cmd,
             list_review_prompts_cmd,
             save_review_prompt_cmd,
@@ -52,6 +133,7 @@ pub fn run() {
             load_api_config_cmd,
             save_api_config_cmd,
             start_ai_review,
+            generate_change_summary,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use roxmltree::{Document, Node};
+
+/// Nested `${...}` references are followed this many levels before giving up
+const MAX_PROPERTY_DEPTH: usize = 10;
+/// Local parent POMs followed through `<relativePath>`
+const MAX_PARENT_DEPTH: usize = 5;
+
+// CodePack: Maven 坐标（依赖、父 POM、dependencyManagement 条目）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PomDependency {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: Option<String>,
+    pub scope: Option<String>,
+}
+
+// CodePack: 解析后的 pom.xml，属性占位符与父 POM 继承均已展开
+#[derive(Debug, Default)]
+pub struct Pom {
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub licenses: Vec<String>,
+    pub parent: Option<PomDependency>,
+    pub properties: HashMap<String, String>,
+    pub dependencies: Vec<PomDependency>,
+    /// `<dependencyManagement>` entries, own first, then inherited ones
+    pub managed: Vec<PomDependency>,
+    parent_path: Option<String>,
+}
+
+// ─── XML Parsing ───────────────────────────────────────────────
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    child(node, name)?
+        .text()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn parse_dependency(node: Node) -> PomDependency {
+    PomDependency {
+        group_id: child_text(node, "groupId").unwrap_or_default(),
+        artifact_id: child_text(node, "artifactId").unwrap_or_default(),
+        version: child_text(node, "version"),
+        scope: child_text(node, "scope"),
+    }
+}
+
+fn dependency_list(node: Option<Node>) -> Vec<PomDependency> {
+    node.map(|deps| children(deps, "dependency").map(parse_dependency).collect())
+        .unwrap_or_default()
+}
+
+/// One pom.xml as written, without inheritance or interpolation
+fn parse_pom(content: &str) -> Option<Pom> {
+    let doc = Document::parse(content).ok()?;
+    let project = doc.root_element();
+    if project.tag_name().name() != "project" {
+        return None;
+    }
+    let parent = child(project, "parent");
+    let properties = child(project, "properties")
+        .map(|props| {
+            props
+                .children()
+                .filter(|n| n.is_element())
+                .map(|n| (n.tag_name().name().to_string(), n.text().unwrap_or("").trim().to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let licenses = child(project, "licenses")
+        .map(|licenses| children(licenses, "license").filter_map(|l| child_text(l, "name")).collect())
+        .unwrap_or_default();
+
+    Some(Pom {
+        group_id: child_text(project, "groupId"),
+        artifact_id: child_text(project, "artifactId"),
+        version: child_text(project, "version"),
+        description: child_text(project, "description"),
+        licenses,
+        parent: parent.map(parse_dependency),
+        // An empty <relativePath/> explicitly disables the local lookup
+        parent_path: parent.map(|p| match child(p, "relativePath") {
+            Some(path) => path.text().unwrap_or("").trim().to_string(),
+            None => "../pom.xml".to_string(),
+        }),
+        properties,
+        dependencies: dependency_list(child(project, "dependencies")),
+        managed: dependency_list(child(project, "dependencyManagement").and_then(|m| child(m, "dependencies"))),
+    })
+}
+
+// ─── Inheritance & Interpolation ───────────────────────────────
+
+/// Replaces known `${name}` references; unknown ones are left as written
+fn interpolate(value: &str, properties: &HashMap<String, String>) -> String {
+    let mut result = value.to_string();
+    for _ in 0..MAX_PROPERTY_DEPTH {
+        let mut changed = false;
+        let mut out = String::new();
+        let mut rest = result.as_str();
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else { break };
+            let key = &rest[start + 2..start + 2 + len];
+            out.push_str(&rest[..start]);
+            match properties.get(key) {
+                Some(v) => {
+                    out.push_str(v);
+                    changed = true;
+                }
+                None => out.push_str(&rest[start..start + 3 + len]),
+            }
+            rest = &rest[start + 3 + len..];
+        }
+        out.push_str(rest);
+        result = out;
+        if !changed {
+            break;
+        }
+    }
+    result
+}
+
+/// The parent POM on disk, if `<relativePath>` points at one for the declared artifact
+fn local_parent(pom_path: &Path, pom: &Pom) -> Option<(PathBuf, Pom)> {
+    let relative = pom.parent_path.as_deref().filter(|p| !p.is_empty())?;
+    let mut path = pom_path.parent()?.join(relative);
+    if path.is_dir() {
+        path = path.join("pom.xml");
+    }
+    let parent = parse_pom(&fs::read_to_string(&path).ok()?)?;
+    let expected = pom.parent.as_ref()?;
+    (parent.artifact_id.as_deref() == Some(expected.artifact_id.as_str())).then_some((path, parent))
+}
+
+fn inherit(pom: &mut Pom, parent: Pom) {
+    for (key, value) in parent.properties {
+        pom.properties.entry(key).or_insert(value);
+    }
+    pom.managed.extend(parent.managed);
+    if pom.licenses.is_empty() {
+        pom.licenses = parent.licenses;
+    }
+    // Dependencies declared in a parent are inherited as-is
+    for dep in parent.dependencies {
+        let declared = pom.dependencies.iter().any(|d| d.group_id == dep.group_id && d.artifact_id == dep.artifact_id);
+        if !declared {
+            pom.dependencies.push(dep);
+        }
+    }
+}
+
+// CodePack: 读取项目根目录的 pom.xml，合并本地父 POM，展开 ${...} 并补全由 dependencyManagement 管理的版本
+pub fn read_pom(root: &Path) -> Option<Pom> {
+    let pom_path = root.join("pom.xml");
+    let mut pom = parse_pom(&fs::read_to_string(&pom_path).ok()?)?;
+
+    // groupId / version default to the parent's coordinates
+    if let Some(ref parent) = pom.parent {
+        if pom.group_id.is_none() && !parent.group_id.is_empty() {
+            pom.group_id = Some(parent.group_id.clone());
+        }
+        if pom.version.is_none() {
+            pom.version = parent.version.clone();
+        }
+    }
+
+    // Nearest parent first, so the closest definition of a property wins
+    let mut parents = Vec::new();
+    let mut next = local_parent(&pom_path, &pom);
+    while let Some((path, parent)) = next {
+        if parents.len() >= MAX_PARENT_DEPTH {
+            break;
+        }
+        next = local_parent(&path, &parent);
+        parents.push(parent);
+    }
+    for parent in parents {
+        inherit(&mut pom, parent);
+    }
+
+    let mut properties = pom.properties.clone();
+    let builtins = [
+        ("project.groupId", pom.group_id.clone()),
+        ("project.artifactId", pom.artifact_id.clone()),
+        ("project.version", pom.version.clone()),
+        ("pom.version", pom.version.clone()),
+        ("project.parent.groupId", pom.parent.as_ref().map(|p| p.group_id.clone())),
+        ("project.parent.version", pom.parent.as_ref().and_then(|p| p.version.clone())),
+    ];
+    for (key, value) in builtins {
+        if let Some(value) = value {
+            properties.insert(key.to_string(), value);
+        }
+    }
+
+    let resolve = |value: &mut Option<String>| {
+        if let Some(v) = value {
+            *v = interpolate(v, &properties);
+        }
+    };
+    resolve(&mut pom.version);
+    resolve(&mut pom.group_id);
+    resolve(&mut pom.description);
+    for dep in pom.dependencies.iter_mut().chain(pom.managed.iter_mut()) {
+        dep.group_id = interpolate(&dep.group_id, &properties);
+        resolve(&mut dep.version);
+    }
+    for value in pom.properties.values_mut() {
+        *value = interpolate(value, &properties);
+    }
+
+    let managed = pom.managed.clone();
+    for dep in pom.dependencies.iter_mut().filter(|d| d.version.is_none()) {
+        let entry = managed.iter().find(|m| m.group_id == dep.group_id && m.artifact_id == dep.artifact_id);
+        if let Some(entry) = entry {
+            dep.version = entry.version.clone();
+            dep.scope = dep.scope.take().or_else(|| entry.scope.clone());
+        }
+    }
+    Some(pom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_interpolate() {
+        let props: HashMap<String, String> = [
+            ("a".to_string(), "${b}-x".to_string()),
+            ("b".to_string(), "1.0".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(interpolate("${a}", &props), "1.0-x");
+        assert_eq!(interpolate("v${b} ${missing}", &props), "v1.0 ${missing}");
+    }
+
+    #[test]
+    fn test_read_pom_resolves_properties_and_parent() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pom.xml"), r#"<?xml version="1.0"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <groupId>com.acme</groupId>
+  <artifactId>acme-parent</artifactId>
+  <version>2.1.0</version>
+  <packaging>pom</packaging>
+  <properties>
+    <jackson.version>2.17.0</jackson.version>
+  </properties>
+  <dependencyManagement>
+    <dependencies>
+      <dependency>
+        <groupId>com.fasterxml.jackson.core</groupId>
+        <artifactId>jackson-databind</artifactId>
+        <version>${jackson.version}</version>
+      </dependency>
+    </dependencies>
+  </dependencyManagement>
+</project>"#).unwrap();
+        let service = dir.path().join("service");
+        fs::create_dir_all(&service).unwrap();
+        fs::write(service.join("pom.xml"), r#"<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <parent>
+    <groupId>com.acme</groupId>
+    <artifactId>acme-parent</artifactId>
+    <version>2.1.0</version>
+  </parent>
+  <artifactId>service</artifactId>
+  <properties>
+    <java.version>21</java.version>
+  </properties>
+  <dependencies>
+    <dependency>
+      <groupId>com.fasterxml.jackson.core</groupId>
+      <artifactId>jackson-databind</artifactId>
+    </dependency>
+    <dependency>
+      <groupId>${project.groupId}</groupId>
+      <artifactId>common</artifactId>
+      <version>${project.version}</version>
+    </dependency>
+  </dependencies>
+</project>"#).unwrap();
+
+        let pom = read_pom(&service).unwrap();
+        assert_eq!(pom.artifact_id.as_deref(), Some("service"));
+        // Inherited from <parent>, not the first <version> in the file
+        assert_eq!(pom.version.as_deref(), Some("2.1.0"));
+        assert_eq!(pom.group_id.as_deref(), Some("com.acme"));
+        assert_eq!(pom.properties.get("java.version").map(String::as_str), Some("21"));
+        assert_eq!(pom.dependencies[0].version.as_deref(), Some("2.17.0"));
+        assert_eq!(pom.dependencies[1].group_id, "com.acme");
+        assert_eq!(pom.dependencies[1].version.as_deref(), Some("2.1.0"));
+    }
+
+    #[test]
+    fn test_read_pom_rejects_malformed_xml() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pom.xml"), "<project><artifactId>x</project>").unwrap();
+        assert!(read_pom(dir.path()).is_none());
+    }
+}
@@ -1,7 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
 
-use crate::types::ProjectMetadata;
+use crate::git::{list_submodules, repository_info};
+use crate::maven::read_pom;
+use crate::monorepo::cargo_member_dirs;
+use crate::plugins::{apply_wasm_metadata, load_plugins};
+use crate::types::{CargoMember, CiPipeline, EcosystemDeps, ProjectMetadata, ProjectType};
 
 pub fn extract_metadata(root: &Path, project_type: &str) -> ProjectMetadata {
     let project_name = root
@@ -19,6 +28,14 @@ pub fn extract_metadata(root: &Path, project_type: &str) -> ProjectMetadata {
         entry_point: None,
         runtime: Vec::new(),
         requirements: Vec::new(),
+        ecosystems: Vec::new(),
+        workspace_members: Vec::new(),
+        license: None,
+        ci: Vec::new(),
+        frameworks: Vec::new(),
+        submodules: Vec::new(),
+        repository: None,
+        contributors: Vec::new(),
     };
 
     match project_type {
@@ -29,9 +46,618 @@ pub fn extract_metadata(root: &Path, project_type: &str) -> ProjectMetadata {
         "Flutter / Dart" => extract_pubspec_yaml(root, &mut meta),
         "Java / Maven" => extract_pom_xml(root, &mut meta),
         "Android / Gradle" | "Gradle" => extract_gradle_meta(root, &mut meta),
+        "Elixir" => extract_mix_exs(root, &mut meta),
+        "Ruby" => extract_ruby_meta(root, &mut meta),
+        "Docker" => extract_docker_meta(root, &mut meta),
+        "Terraform" => extract_terraform_meta(root, &mut meta),
+        "Pulumi" => extract_pulumi_meta(root, &mut meta),
+        "AWS CDK" => extract_cdk_meta(root, &mut meta),
+        "Unity" => extract_unity_meta(root, &mut meta),
+        "Godot" => extract_godot_meta(root, &mut meta),
+        "Unreal" => extract_unreal_meta(root, &mut meta),
         _ => {}
     }
+    if meta.license.is_none() {
+        meta.license = manifest_license(root).or_else(|| license_file(root));
+    }
+    meta.ci = extract_ci(root);
+    meta.frameworks = detect_frameworks(root, &meta);
+    meta.submodules = list_submodules(root);
+    meta.repository = repository_info(root);
+
+    meta
+}
+
+// ─── License ───────────────────────────────────────────────────
+
+const LICENSE_FILES: &[&str] = &[
+    "LICENSE", "LICENSE.md", "LICENSE.txt", "LICENCE", "LICENCE.md", "COPYING", "COPYING.md",
+    "LICENSE-MIT", "LICENSE-APACHE",
+];
+
+/// SPDX identifier declared in whichever manifest is present
+fn manifest_license(root: &Path) -> Option<String> {
+    let toml_doc = |name: &str| {
+        fs::read_to_string(root.join(name))
+            .ok()
+            .and_then(|c| c.parse::<toml::Table>().ok())
+    };
+    let toml_str = |value: Option<&toml::Value>| value.and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    if let Some(doc) = toml_doc("Cargo.toml") {
+        let license = toml_str(doc.get("package").and_then(|p| p.get("license")))
+            .or_else(|| toml_str(doc.get("workspace").and_then(|w| w.get("package")).and_then(|p| p.get("license"))));
+        if license.is_some() {
+            return license;
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join("package.json")) {
+        if let Ok(pkg) = serde_json::from_str::<serde_json::Value>(&content) {
+            // Either "MIT" or the deprecated { "type": "MIT", "url": ... }
+            let license = pkg.get("license").and_then(|l| l.as_str().or_else(|| l.get("type")?.as_str()));
+            if let Some(license) = license {
+                return Some(license.to_string());
+            }
+        }
+    }
+    if let Some(doc) = toml_doc("pyproject.toml") {
+        // PEP 639 string, the older { text = "..." } table, or Poetry's field
+        let project = doc.get("project").and_then(|p| p.get("license"));
+        let license = toml_str(project)
+            .or_else(|| toml_str(project.and_then(|l| l.get("text"))))
+            .or_else(|| toml_str(doc.get("tool").and_then(|t| t.get("poetry")).and_then(|p| p.get("license"))));
+        if license.is_some() {
+            return license;
+        }
+    }
+    read_pom(root).and_then(|pom| pom.licenses.into_iter().next())
+}
+
+/// Identifies the license text in LICENSE / COPYING; unrecognised texts are reported
+/// by file name so the reader still knows where to look
+fn license_file(root: &Path) -> Option<String> {
+    let (name, content) = LICENSE_FILES
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok().map(|c| (*name, c)))?;
+    Some(identify_license(&content).map(|id| id.to_string()).unwrap_or_else(|| format!("see {}", name)))
+}
+
+fn identify_license(text: &str) -> Option<&'static str> {
+    if let Some(line) = text.lines().find(|l| l.contains("SPDX-License-Identifier:")) {
+        let id = line.split("SPDX-License-Identifier:").nth(1)?.trim();
+        return match id {
+            "MIT" => Some("MIT"),
+            "Apache-2.0" => Some("Apache-2.0"),
+            "BSD-2-Clause" => Some("BSD-2-Clause"),
+            "BSD-3-Clause" => Some("BSD-3-Clause"),
+            "ISC" => Some("ISC"),
+            "MPL-2.0" => Some("MPL-2.0"),
+            "GPL-2.0-only" | "GPL-2.0" => Some("GPL-2.0"),
+            "GPL-3.0-only" | "GPL-3.0" => Some("GPL-3.0"),
+            _ => None,
+        };
+    }
+    let head: String = text.chars().take(2000).collect::<String>().to_lowercase();
+    let has = |needle: &str| head.contains(needle);
+    if has("gnu affero general public license") {
+        Some("AGPL-3.0")
+    } else if has("gnu lesser general public license") {
+        Some(if has("version 2.1") { "LGPL-2.1" } else { "LGPL-3.0" })
+    } else if has("gnu general public license") {
+        Some(if has("version 2") { "GPL-2.0" } else { "GPL-3.0" })
+    } else if has("apache license") && has("version 2.0") {
+        Some("Apache-2.0")
+    } else if has("mozilla public license") && has("2.0") {
+        Some("MPL-2.0")
+    } else if has("permission is hereby granted, free of charge") {
+        Some("MIT")
+    } else if has("permission to use, copy, modify, and/or distribute this software") {
+        Some("ISC")
+    } else if has("redistribution and use in source and binary forms") {
+        Some(if has("neither the name") { "BSD-3-Clause" } else { "BSD-2-Clause" })
+    } else if has("this is free and unencumbered software released into the public domain") {
+        Some("Unlicense")
+    } else {
+        None
+    }
+}
+
+// ─── CI ────────────────────────────────────────────────────────
+
+// GitLab top-level keywords that configure the pipeline rather than define a job
+const GITLAB_KEYWORDS: &[&str] = &[
+    "stages", "variables", "default", "include", "image", "services", "before_script",
+    "after_script", "cache", "workflow",
+];
+const JENKINS_TRIGGERS: &[&str] = &["cron", "pollSCM", "upstream", "githubPush", "gitlab"];
+
+// CodePack: 汇总 CI 配置中的任务名与触发条件，让阅读者知道构建/测试入口
+fn extract_ci(root: &Path) -> Vec<CiPipeline> {
+    let mut pipelines = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(root.join(".github/workflows")) {
+        let mut files: Vec<String> = entries
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".yml") || name.ends_with(".yaml"))
+            .collect();
+        files.sort();
+        for file in files {
+            let Ok(content) = fs::read_to_string(root.join(".github/workflows").join(&file)) else { continue };
+            let (name, triggers, jobs) = match parse_yaml(&content) {
+                Some(doc) => (
+                    doc.get("name").and_then(yaml_scalar),
+                    yaml_names(doc.get("on")),
+                    yaml_names(doc.get("jobs")),
+                ),
+                None => (
+                    yaml_top_level_value(&content, "name"),
+                    yaml_section_keys(&content, "on"),
+                    yaml_section_keys(&content, "jobs"),
+                ),
+            };
+            pipelines.push(CiPipeline {
+                provider: "GitHub Actions".to_string(),
+                file: format!(".github/workflows/{}", file),
+                name,
+                triggers,
+                jobs,
+            });
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join(".gitlab-ci.yml")) {
+        let keys = match parse_yaml(&content) {
+            Some(doc) => yaml_names(Some(&doc)),
+            None => yaml_top_level_keys(&content),
+        };
+        let jobs = keys
+            .into_iter()
+            .filter(|key| !key.starts_with('.') && !GITLAB_KEYWORDS.contains(&key.as_str()))
+            .collect();
+        // Pipeline sources named in `rules: - if: $CI_PIPELINE_SOURCE == "..."`
+        let mut triggers: Vec<String> = Vec::new();
+        for line in content.lines().filter(|l| l.contains("$CI_PIPELINE_SOURCE")) {
+            let after = line.split("$CI_PIPELINE_SOURCE").nth(1).unwrap_or("");
+            if let Some(source) = after.split(['"', '\'']).nth(1).filter(|s| !s.is_empty()) {
+                if !triggers.iter().any(|t| t == source) {
+                    triggers.push(source.to_string());
+                }
+            }
+        }
+        pipelines.push(CiPipeline {
+            provider: "GitLab CI".to_string(),
+            file: ".gitlab-ci.yml".to_string(),
+            name: None,
+            triggers,
+            jobs,
+        });
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join("Jenkinsfile")) {
+        let mut jobs = Vec::new();
+        let mut triggers = Vec::new();
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if let Some(rest) = trimmed.strip_prefix("stage(") {
+                if let Some(name) = rest.split(['"', '\'']).nth(1) {
+                    jobs.push(name.to_string());
+                }
+            }
+            for trigger in JENKINS_TRIGGERS {
+                let call = format!("{}(", trigger);
+                if let Some(pos) = trimmed.find(&call) {
+                    let args = &trimmed[pos + call.len()..];
+                    let arg = args.split(['"', '\'']).nth(1).filter(|a| !a.is_empty());
+                    triggers.push(match arg {
+                        Some(arg) => format!("{} {}", trigger, arg),
+                        None => trigger.to_string(),
+                    });
+                }
+            }
+        }
+        pipelines.push(CiPipeline {
+            provider: "Jenkins".to_string(),
+            file: "Jenkinsfile".to_string(),
+            name: None,
+            triggers,
+            jobs,
+        });
+    }
+
+    pipelines
+}
+
+/// Parsed YAML document, or `None` when it isn't a mapping so callers fall back to
+/// the line-based helpers below
+fn parse_yaml(content: &str) -> Option<serde_yaml::Value> {
+    serde_yaml::from_str::<serde_yaml::Value>(content)
+        .ok()
+        .filter(|doc| doc.is_mapping())
+}
+
+/// Strings, numbers and booleans as text (`sdk: 3.2` is a float to YAML)
+fn yaml_scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Mapping keys, sequence items or a lone scalar: `on: push`, `on: [push]` and
+/// `on: { push: ... }` all name the same trigger
+fn yaml_names(value: Option<&serde_yaml::Value>) -> Vec<String> {
+    match value {
+        Some(serde_yaml::Value::Mapping(map)) => map.keys().filter_map(yaml_scalar).collect(),
+        Some(serde_yaml::Value::Sequence(items)) => items.iter().filter_map(yaml_scalar).collect(),
+        Some(value) => yaml_scalar(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+fn yaml_unquote(raw: &str) -> String {
+    raw.trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+/// `(indent, key, inline value)` for every `key:` line, skipping comments and list items
+fn yaml_keys(content: &str) -> impl Iterator<Item = (usize, String, String)> + '_ {
+    content.lines().filter_map(|line| {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('-') {
+            return None;
+        }
+        let (key, value) = trimmed.split_once(':')?;
+        let indent = line.len() - line.trim_start().len();
+        Some((indent, yaml_unquote(key), value.trim().to_string()))
+    })
+}
+
+fn yaml_top_level_keys(content: &str) -> Vec<String> {
+    yaml_keys(content).filter(|(indent, _, _)| *indent == 0).map(|(_, key, _)| key).collect()
+}
+
+fn yaml_top_level_value(content: &str, key: &str) -> Option<String> {
+    yaml_keys(content)
+        .find(|(indent, k, _)| *indent == 0 && k == key)
+        .map(|(_, _, value)| yaml_unquote(&value))
+        .filter(|value| !value.is_empty())
+}
+
+/// Children of a top-level key: the keys one level below it, or its inline value
+/// (`on: push`, `on: [push, pull_request]`, or a `- push` list)
+fn yaml_section_keys(content: &str, section: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut in_section = false;
+    let mut child_indent: Option<usize> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            if in_section {
+                // A block list may sit at the same indent as its key
+                match trimmed.strip_prefix("- ") {
+                    Some(item) => {
+                        keys.push(yaml_unquote(item));
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            let Some((key, value)) = trimmed.split_once(':') else { continue };
+            if yaml_unquote(key) == section {
+                in_section = true;
+                let value = value.split(" #").next().unwrap_or("").trim();
+                let value = value.trim_start_matches('[').trim_end_matches(']');
+                keys.extend(value.split(',').map(yaml_unquote).filter(|v| !v.is_empty()));
+            }
+            continue;
+        }
+        if !in_section || indent != *child_indent.get_or_insert(indent) {
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            keys.push(yaml_unquote(item));
+        } else if let Some((key, _)) = trimmed.split_once(':') {
+            keys.push(yaml_unquote(key));
+        }
+    }
+    keys
+}
+
+// ─── Frameworks ────────────────────────────────────────────────
+
+// Dependency name → framework label; checked in this order
+const FRAMEWORKS: &[(&str, &str)] = &[
+    ("next", "Next.js"),
+    ("nuxt", "Nuxt"),
+    ("react", "React"),
+    ("vue", "Vue"),
+    ("@angular/core", "Angular"),
+    ("svelte", "Svelte"),
+    ("solid-js", "SolidJS"),
+    ("@nestjs/core", "NestJS"),
+    ("express", "Express"),
+    ("electron", "Electron"),
+    ("tauri", "Tauri"),
+    ("@tauri-apps/api", "Tauri"),
+    ("actix-web", "Actix Web"),
+    ("axum", "Axum"),
+    ("rocket", "Rocket"),
+    ("leptos", "Leptos"),
+    ("bevy", "Bevy"),
+    ("django", "Django"),
+    ("flask", "Flask"),
+    ("fastapi", "FastAPI"),
+    ("github.com/gin-gonic/gin", "Gin"),
+    ("github.com/labstack/echo/v4", "Echo"),
+    ("github.com/gofiber/fiber/v2", "Fiber"),
+    ("flutter", "Flutter"),
+    ("phoenix", "Phoenix"),
+    ("rails", "Rails"),
+];
+
+/// `^18.2.0` → `18`, `>=0.110,<1` → `0.110`; zero-major versions keep the minor
+fn major_version(spec: &str) -> Option<String> {
+    let start = spec.find(|c: char| c.is_ascii_digit())?;
+    let mut parts = spec[start..]
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?
+        .split('.')
+        .filter(|p| !p.is_empty());
+    let major = parts.next()?;
+    match (major, parts.next()) {
+        ("0", Some(minor)) => Some(format!("0.{}", minor)),
+        _ => Some(major.to_string()),
+    }
+}
+
+/// Version spec of `dep` in the requirement list (`name@spec`, `name>=spec`, `group:name:spec`)
+fn requirement_spec<'a>(requirements: &'a [String], dep: &str) -> Option<&'a str> {
+    requirements.iter().find_map(|req| {
+        let name = requirement_dep_name(req);
+        if lock_key(&name) == lock_key(dep) {
+            return Some(&req[name.len()..]);
+        }
+        let mut coords = req.split(':');
+        (coords.nth(1) == Some(dep)).then(|| coords.next()).flatten()
+    })
+}
+
+// CodePack: 根据依赖和配置文件推断主要框架及大版本，供头部和前端框架徽章使用
+fn detect_frameworks(root: &Path, meta: &ProjectMetadata) -> Vec<String> {
+    let mut frameworks: Vec<String> = Vec::new();
+    let mut push = |label: &str, version: Option<String>| {
+        let exists = frameworks.iter().any(|f| f == label || f.starts_with(&format!("{} ", label)));
+        if !exists {
+            frameworks.push(match version {
+                Some(v) => format!("{} {}", label, v),
+                None => label.to_string(),
+            });
+        }
+    };
+
+    let deps: Vec<&String> = meta
+        .dependencies
+        .iter()
+        .chain(&meta.dev_dependencies)
+        .chain(meta.ecosystems.iter().flat_map(|e| e.dependencies.iter().chain(&e.dev_dependencies)))
+        .collect();
+    for (dep, label) in FRAMEWORKS {
+        if deps.iter().any(|d| lock_key(d) == lock_key(dep)) {
+            push(label, requirement_spec(&meta.requirements, dep).and_then(major_version));
+        }
+    }
+
+    // Spring Boot versions usually come from the parent POM or the Gradle plugin
+    if let Some(pom) = read_pom(root) {
+        let boot = pom.parent.iter().chain(&pom.managed).chain(&pom.dependencies).find(|d| {
+            d.group_id == "org.springframework.boot" && d.version.is_some()
+        });
+        if let Some(boot) = boot {
+            push("Spring Boot", boot.version.as_deref().and_then(major_version));
+        } else if pom.dependencies.iter().any(|d| d.artifact_id.starts_with("spring-boot-starter")) {
+            push("Spring Boot", None);
+        }
+    }
+    for gradle in ["build.gradle.kts", "build.gradle"] {
+        let Ok(content) = fs::read_to_string(root.join(gradle)) else { continue };
+        if let Some(line) = content.lines().find(|l| l.contains("org.springframework.boot")) {
+            let version = line.split("version").nth(1).and_then(major_version);
+            push("Spring Boot", version);
+        }
+        break;
+    }
+    // Tauri apps keep the Rust side in src-tauri/, so the root manifest may not list it
+    for conf in ["src-tauri/tauri.conf.json", "tauri.conf.json"] {
+        let Ok(content) = fs::read_to_string(root.join(conf)) else { continue };
+        let Ok(doc) = serde_json::from_str::<serde_json::Value>(&content) else { continue };
+        // v1 nests everything under "tauri"; v2 moved "identifier" to the top level
+        let version = if doc.get("identifier").is_some() { "2" } else { "1" };
+        push("Tauri", Some(version.to_string()));
+        break;
+    }
+    if root.join("angular.json").exists() {
+        push("Angular", None);
+    }
+    if root.join("manage.py").exists() {
+        push("Django", None);
+    }
+    frameworks
+}
+
+// ─── Lockfiles ─────────────────────────────────────────────────
+
+/// Lookup key shared by every lockfile: PyPI treats `Foo_Bar` and `foo-bar` as one name
+fn lock_key(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+/// Name → exact version from whichever lockfiles exist at the root
+fn lockfile_versions(root: &Path) -> HashMap<String, String> {
+    let mut versions = HashMap::new();
+    // Cargo.lock, poetry.lock and uv.lock share the `[[package]] name / version` layout
+    for lockfile in ["Cargo.lock", "poetry.lock", "uv.lock"] {
+        let Ok(content) = fs::read_to_string(root.join(lockfile)) else { continue };
+        let Ok(doc) = content.parse::<toml::Table>() else { continue };
+        for package in doc.get("package").and_then(|v| v.as_array()).into_iter().flatten() {
+            let name = package.get("name").and_then(|v| v.as_str());
+            let version = package.get("version").and_then(|v| v.as_str());
+            if let (Some(name), Some(version)) = (name, version) {
+                versions.entry(lock_key(name)).or_insert_with(|| version.to_string());
+            }
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join("package-lock.json")) {
+        if let Ok(lock) = serde_json::from_str::<serde_json::Value>(&content) {
+            // lockfileVersion 2/3 key by install path, v1 by package name
+            if let Some(packages) = lock.get("packages").and_then(|v| v.as_object()) {
+                for (path, info) in packages {
+                    let Some(name) = path.strip_prefix("node_modules/") else { continue };
+                    if name.contains("/node_modules/") {
+                        continue;
+                    }
+                    if let Some(version) = info.get("version").and_then(|v| v.as_str()) {
+                        versions.entry(lock_key(name)).or_insert_with(|| version.to_string());
+                    }
+                }
+            } else if let Some(deps) = lock.get("dependencies").and_then(|v| v.as_object()) {
+                for (name, info) in deps {
+                    if let Some(version) = info.get("version").and_then(|v| v.as_str()) {
+                        versions.entry(lock_key(name)).or_insert_with(|| version.to_string());
+                    }
+                }
+            }
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join(".terraform.lock.hcl")) {
+        // provider "registry.terraform.io/hashicorp/aws" { version = "5.31.0" ... }
+        for block in content.split("provider \"").skip(1) {
+            let Some((address, body)) = block.split_once('"') else { continue };
+            let source = address.split_once('/').map_or(address, |(_, source)| source);
+            if let Some(version) = hcl_attr(body, "version") {
+                versions.entry(lock_key(source)).or_insert(version);
+            }
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join("go.sum")) {
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+                if !version.ends_with("/go.mod") {
+                    // go.sum keeps every version the build ever checked; the newest is the one in use
+                    let key = lock_key(module);
+                    let newer = versions
+                        .get(&key)
+                        .is_none_or(|current| go_version_key(version) > go_version_key(current));
+                    if newer {
+                        versions.insert(key, version.to_string());
+                    }
+                }
+            }
+        }
+    }
+    versions
+}
+
+/// Sort key for Go module versions: numeric `vMAJOR.MINOR.PATCH`, then releases after
+/// pre-releases (`v1.2.0-rc.1` < `v1.2.0`); `+incompatible` is ignored
+fn go_version_key(version: &str) -> (Vec<u64>, bool) {
+    let version = version.trim_start_matches('v');
+    let version = version.split('+').next().unwrap_or(version);
+    let (core, pre) = match version.split_once('-') {
+        Some((core, _)) => (core, true),
+        None => (version, false),
+    };
+    let numbers = core.split('.').map(|part| part.parse().unwrap_or(0)).collect();
+    (numbers, !pre)
+}
+
+/// Dependency name a requirement string is about: `tokio@1 [rt]`, `@types/node@^20`,
+/// `flask>=2.0`
+fn requirement_dep_name(requirement: &str) -> String {
+    match requirement.rsplit_once('@') {
+        Some((name, _)) if !name.is_empty() => name.to_string(),
+        _ => requirement_name(requirement),
+    }
+}
+
+// CodePack: 根据锁文件标注实际安装版本，便于 LLM 判断 API 差异
+/// Appends `(locked x.y.z)` to every requirement whose dependency is pinned in
+/// Cargo.lock, package-lock.json, poetry.lock, uv.lock or go.sum
+pub fn resolve_locked_versions(root: &Path, meta: &mut ProjectMetadata) {
+    let versions = lockfile_versions(root);
+    if versions.is_empty() {
+        return;
+    }
+    for requirement in &mut meta.requirements {
+        let name = requirement_dep_name(requirement);
+        if let Some(version) = versions.get(&lock_key(&name)) {
+            // Requirements that already are exact pins don't need the annotation
+            if !requirement.ends_with(&format!("@{}", version)) && !requirement.ends_with(&format!("=={}", version)) {
+                requirement.push_str(&format!(" (locked {})", version));
+            }
+        }
+    }
+}
+
+/// Package ecosystem whose manifest `extract_metadata` reads for this type
+fn ecosystem_of(project_type: &str) -> Option<&'static str> {
+    match project_type {
+        "Node.js" | "Next.js" | "Vite" | "Nuxt.js" => Some("npm"),
+        "Python" => Some("pypi"),
+        "Rust" => Some("cargo"),
+        "Go" => Some("go"),
+        "Flutter / Dart" => Some("pub"),
+        "Java / Maven" => Some("maven"),
+        "Android / Gradle" | "Gradle" => Some("gradle"),
+        "Elixir" => Some("hex"),
+        "Ruby" => Some("rubygems"),
+        "Terraform" => Some("terraform"),
+        _ => None,
+    }
+}
+
+// CodePack: 混合项目的元数据：主类型决定名称/版本，其余类型补充缺失字段，依赖按生态分组
+pub fn extract_metadata_multi(root: &Path, project_types: &[String]) -> ProjectMetadata {
+    let generic = ProjectType::Generic.to_string();
+    let primary = project_types.first().unwrap_or(&generic);
+    let mut meta = extract_metadata(root, primary);
+    let mut seen = Vec::new();
 
+    for (i, project_type) in project_types.iter().enumerate() {
+        let Some(ecosystem) = ecosystem_of(project_type) else {
+            // No package manifest (Docker, CMake...), but it may still describe the runtime
+            if i > 0 {
+                meta.runtime.extend(extract_metadata(root, project_type).runtime);
+            }
+            continue;
+        };
+        if seen.contains(&ecosystem) {
+            continue;
+        }
+        seen.push(ecosystem);
+        let extra = if i == 0 { meta.clone() } else { extract_metadata(root, project_type) };
+        if i > 0 {
+            meta.version = meta.version.or(extra.version.clone());
+            meta.description = meta.description.or(extra.description.clone());
+            meta.entry_point = meta.entry_point.or(extra.entry_point.clone());
+            meta.runtime.extend(extra.runtime.iter().cloned());
+            meta.requirements.extend(extra.requirements.iter().cloned());
+        }
+        meta.ecosystems.push(EcosystemDeps {
+            ecosystem: ecosystem.to_string(),
+            project_type: project_type.clone(),
+            dependencies: extra.dependencies,
+            dev_dependencies: extra.dev_dependencies,
+        });
+    }
+    // Secondary ecosystems may bring their own frameworks (Tauri + React)
+    meta.frameworks = detect_frameworks(root, &meta);
     meta
 }
 
@@ -95,43 +721,112 @@ fn extract_package_json(root: &Path, meta: &mut ProjectMetadata) {
 fn extract_cargo_toml(root: &Path, meta: &mut ProjectMetadata) {
     if let Ok(content) = fs::read_to_string(root.join("Cargo.toml")) {
         if let Ok(doc) = content.parse::<toml::Table>() {
+            let workspace = doc.get("workspace").and_then(|v| v.as_table());
+            let workspace_package = workspace.and_then(|w| w.get("package"));
+            let workspace_deps = workspace
+                .and_then(|w| w.get("dependencies"))
+                .and_then(|v| v.as_table());
             if let Some(pkg) = doc.get("package").and_then(|v| v.as_table()) {
                 if let Some(name) = pkg.get("name").and_then(|v| v.as_str()) {
                     meta.name = name.to_string();
                 }
-                if let Some(ver) = pkg.get("version").and_then(|v| v.as_str()) {
-                    meta.version = Some(ver.to_string());
-                }
-                if let Some(desc) = pkg.get("description").and_then(|v| v.as_str()) {
-                    if !desc.is_empty() { meta.description = Some(desc.to_string()); }
+                meta.version = cargo_package_field(pkg, "version", workspace_package);
+                if let Some(desc) = cargo_package_field(pkg, "description", workspace_package) {
+                    if !desc.is_empty() { meta.description = Some(desc); }
                 }
-                if let Some(edition) = pkg.get("edition").and_then(|v| v.as_str()) {
+                if let Some(edition) = cargo_package_field(pkg, "edition", workspace_package) {
                     meta.runtime.push(format!("rust edition {}", edition));
                 }
-                if let Some(msrv) = pkg.get("rust-version").and_then(|v| v.as_str()) {
+                if let Some(msrv) = cargo_package_field(pkg, "rust-version", workspace_package) {
                     meta.runtime.push(format!("rust >={}", msrv));
                 }
+            } else if workspace.is_some() {
+                // Virtual manifest: only shared package fields are available
+                let shared = workspace_package.and_then(|v| v.as_table());
+                meta.version = shared.and_then(|p| p.get("version")).and_then(|v| v.as_str()).map(|v| v.to_string());
+                if let Some(edition) = shared.and_then(|p| p.get("edition")).and_then(|v| v.as_str()) {
+                    meta.runtime.push(format!("rust edition {}", edition));
+                }
             }
-            if let Some(deps) = doc.get("dependencies").and_then(|v| v.as_table()) {
+            // A virtual manifest lists its shared dependencies under [workspace.dependencies]
+            let deps = doc
+                .get("dependencies")
+                .and_then(|v| v.as_table())
+                .or(workspace_deps.filter(|_| !doc.contains_key("package")));
+            if let Some(deps) = deps {
                 meta.dependencies = deps.keys().cloned().collect();
                 for (key, val) in deps {
-                    let ver_str = if let Some(v) = val.as_str() {
-                        v.to_string()
-                    } else if let Some(t) = val.as_table() {
-                        t.get("version").and_then(|v| v.as_str()).unwrap_or("*").to_string()
-                    } else {
-                        "*".to_string()
-                    };
-                    meta.requirements.push(format!("{}@{}", key, ver_str));
+                    meta.requirements.push(cargo_requirement(key, val, workspace_deps));
                 }
             }
             if let Some(deps) = doc.get("dev-dependencies").and_then(|v| v.as_table()) {
                 meta.dev_dependencies = deps.keys().cloned().collect();
             }
+            if workspace.is_some() {
+                meta.workspace_members = cargo_workspace_members(root, workspace_package);
+            }
         }
     }
 }
 
+/// `[package]` field, following `field.workspace = true` to `[workspace.package]`
+fn cargo_package_field(pkg: &toml::Table, key: &str, workspace_package: Option<&toml::Value>) -> Option<String> {
+    let value = pkg.get(key)?;
+    if let Some(s) = value.as_str() {
+        return Some(s.to_string());
+    }
+    let inherited = value.get("workspace").and_then(|v| v.as_bool()) == Some(true);
+    if inherited {
+        return workspace_package?.get(key)?.as_str().map(|s| s.to_string());
+    }
+    None
+}
+
+/// `tokio@1 [full, macros]`: version requirement plus explicitly enabled features.
+/// `dep = { workspace = true }` takes its version (and features) from [workspace.dependencies]
+fn cargo_requirement(name: &str, value: &toml::Value, workspace_deps: Option<&toml::Table>) -> String {
+    let inherited = value.get("workspace").and_then(|v| v.as_bool()) == Some(true);
+    let shared = workspace_deps.and_then(|w| w.get(name)).filter(|_| inherited);
+    let version = value
+        .as_str()
+        .or_else(|| value.get("version").and_then(|v| v.as_str()))
+        .or_else(|| shared.and_then(|s| s.as_str().or_else(|| s.get("version")?.as_str())))
+        .unwrap_or("*");
+    let features: Vec<&str> = [Some(value), shared]
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.get("features")?.as_array())
+        .flatten()
+        .filter_map(|f| f.as_str())
+        .collect();
+    if features.is_empty() {
+        format!("{}@{}", name, version)
+    } else {
+        format!("{}@{} [{}]", name, version, features.join(", "))
+    }
+}
+
+// CodePack: Cargo workspace 成员 crate 的名称与版本
+fn cargo_workspace_members(root: &Path, workspace_package: Option<&toml::Value>) -> Vec<CargoMember> {
+    cargo_member_dirs(root)
+        .into_iter()
+        .filter_map(|dir| {
+            let content = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+            let doc = content.parse::<toml::Table>().ok()?;
+            let pkg = doc.get("package")?.as_table()?;
+            Some(CargoMember {
+                name: pkg.get("name")?.as_str()?.to_string(),
+                version: cargo_package_field(pkg, "version", workspace_package),
+                path: dir
+                    .strip_prefix(root)
+                    .unwrap_or(&dir)
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+            })
+        })
+        .collect()
+}
+
 fn extract_python_meta(root: &Path, meta: &mut ProjectMetadata) {
     if let Ok(content) = fs::read_to_string(root.join("pyproject.toml")) {
         if let Ok(doc) = content.parse::<toml::Table>() {
@@ -150,21 +845,42 @@ fn extract_python_meta(root: &Path, meta: &mut ProjectMetadata) {
                 }
                 if let Some(deps) = project.get("dependencies").and_then(|v| v.as_array()) {
                     for dep in deps.iter().filter_map(|v| v.as_str()) {
-                        let name_only = dep.split(&['>', '<', '=', '~', '!', ';', '['][..]).next().unwrap_or(dep).trim().to_string();
-                        meta.dependencies.push(name_only);
+                        meta.dependencies.push(requirement_name(dep));
                         meta.requirements.push(dep.trim().to_string());
                     }
                 }
             }
+            // PEP 735 groups and uv's older dev-dependencies list
+            let dev_lists = [
+                doc.get("dependency-groups").and_then(|v| v.get("dev")),
+                doc.get("tool").and_then(|v| v.get("uv")).and_then(|v| v.get("dev-dependencies")),
+            ];
+            for list in dev_lists.into_iter().flatten().filter_map(|v| v.as_array()) {
+                for dep in list.iter().filter_map(|v| v.as_str()) {
+                    meta.dev_dependencies.push(requirement_name(dep));
+                }
+            }
+            if let Some(poetry) = doc.get("tool").and_then(|v| v.get("poetry")).and_then(|v| v.as_table()) {
+                // Poetry 2 puts name/version in [project]; that table wins when present
+                extract_poetry(poetry, meta, !doc.contains_key("project"));
+            }
         }
     }
+    if meta.dependencies.is_empty() {
+        extract_uv_lock(root, meta);
+    }
+    if meta.dependencies.is_empty() {
+        extract_pipfile(root, meta);
+    }
+    if meta.dependencies.is_empty() {
+        extract_conda_env(root, meta);
+    }
     if meta.dependencies.is_empty() {
         if let Ok(content) = fs::read_to_string(root.join("requirements.txt")) {
             for line in content.lines() {
                 let l = line.trim();
                 if l.is_empty() || l.starts_with('#') || l.starts_with('-') { continue; }
-                let name_only = l.split(&['>', '<', '=', '~', '!', ';', '['][..]).next().unwrap_or(l).trim().to_string();
-                meta.dependencies.push(name_only);
+                meta.dependencies.push(requirement_name(l));
                 meta.requirements.push(l.to_string());
             }
         }
@@ -183,6 +899,201 @@ fn extract_python_meta(root: &Path, meta: &mut ProjectMetadata) {
     }
 }
 
+/// `flask[async]>=2.0; python_version>"3.8"` → `flask`
+fn requirement_name(dep: &str) -> String {
+    dep.split(&['>', '<', '=', '~', '!', ';', '[', ' '][..]).next().unwrap_or(dep).trim().to_string()
+}
+
+/// Version spec of a Poetry / Pipfile entry: `"^2.0"`, `{ version = "^2.0", extras = [...] }`
+/// or a git / path table (no version, reported as `*`)
+fn toml_dep_spec(value: &toml::Value) -> String {
+    value
+        .as_str()
+        .or_else(|| value.get("version").and_then(|v| v.as_str()))
+        .unwrap_or("*")
+        .to_string()
+}
+
+// CodePack: Poetry 项目（[tool.poetry]），依赖写在表中而非 PEP 621 的 [project]
+fn extract_poetry(poetry: &toml::Table, meta: &mut ProjectMetadata, use_name: bool) {
+    if meta.version.is_none() {
+        meta.version = poetry.get("version").and_then(|v| v.as_str()).map(|v| v.to_string());
+    }
+    if meta.description.is_none() {
+        meta.description = poetry
+            .get("description")
+            .and_then(|v| v.as_str())
+            .filter(|d| !d.is_empty())
+            .map(|d| d.to_string());
+    }
+    if let Some(name) = poetry.get("name").and_then(|v| v.as_str()).filter(|_| use_name) {
+        meta.name = name.to_string();
+    }
+    if let Some(deps) = poetry.get("dependencies").and_then(|v| v.as_table()) {
+        for (name, value) in deps {
+            let spec = toml_dep_spec(value);
+            if name == "python" {
+                meta.runtime.push(format!("python {}", spec));
+                continue;
+            }
+            meta.dependencies.push(name.clone());
+            meta.requirements.push(format!("{}@{}", name, spec));
+        }
+    }
+    // Poetry < 1.2 used dev-dependencies; newer versions use named groups
+    let mut dev_tables: Vec<&toml::Table> = poetry
+        .get("dev-dependencies")
+        .and_then(|v| v.as_table())
+        .into_iter()
+        .collect();
+    if let Some(groups) = poetry.get("group").and_then(|v| v.as_table()) {
+        dev_tables.extend(groups.values().filter_map(|g| g.get("dependencies")?.as_table()));
+    }
+    for table in dev_tables {
+        meta.dev_dependencies.extend(table.keys().cloned());
+    }
+}
+
+// CodePack: uv.lock：根包（editable / virtual 源）的直接依赖及锁定版本
+fn extract_uv_lock(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(content) = fs::read_to_string(root.join("uv.lock")) else { return };
+    let Ok(doc) = content.parse::<toml::Table>() else { return };
+    let packages: Vec<&toml::Table> = doc
+        .get("package")
+        .and_then(|v| v.as_array())
+        .map(|a| a.iter().filter_map(|p| p.as_table()).collect())
+        .unwrap_or_default();
+    let names = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|d| d.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let Some(project) = packages.iter().find(|p| {
+        p.get("source")
+            .is_some_and(|s| s.get("editable").is_some() || s.get("virtual").is_some())
+    }) else {
+        return;
+    };
+    for dep in names(project.get("dependencies")) {
+        let requirement = match locked_version(&packages, &dep) {
+            Some(version) => format!("{}=={}", dep, version),
+            None => dep.clone(),
+        };
+        meta.requirements.push(requirement);
+        meta.dependencies.push(dep);
+    }
+    if let Some(groups) = project.get("dev-dependencies").and_then(|v| v.as_table()) {
+        for group in groups.values() {
+            meta.dev_dependencies.extend(names(Some(group)));
+        }
+    }
+    if meta.runtime.is_empty() {
+        if let Some(rp) = doc.get("requires-python").and_then(|v| v.as_str()) {
+            meta.runtime.push(format!("python {}", rp));
+        }
+    }
+}
+
+// CodePack: Conda 的 environment.yml（数据科学项目常用）；结构简单，按行解析，不走 parse_yaml
+fn extract_conda_env(root: &Path, meta: &mut ProjectMetadata) {
+    let content = ["environment.yml", "environment.yaml"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok());
+    let Some(content) = content else { return };
+
+    let mut in_deps = false;
+    // Indent of the `- pip:` item while inside its sub-list
+    let mut pip_indent: Option<usize> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 && !trimmed.starts_with('-') {
+            in_deps = trimmed == "dependencies:";
+            pip_indent = None;
+            continue;
+        }
+        let Some(item) = trimmed.strip_prefix('-').map(str::trim) else { continue };
+        if !in_deps {
+            continue;
+        }
+        if pip_indent.is_some_and(|pip| indent <= pip) {
+            pip_indent = None;
+        }
+        if item == "pip:" {
+            pip_indent = Some(indent);
+            continue;
+        }
+        let item = item.trim_matches(|c| c == '\'' || c == '"');
+        if pip_indent.is_some() {
+            // `-r requirements.txt` / `-e .` are pip options, not packages
+            if !item.starts_with('-') {
+                meta.dependencies.push(requirement_name(item));
+                meta.requirements.push(item.to_string());
+            }
+            continue;
+        }
+        // Conda specs: `numpy`, `numpy>=1.26`, `python=3.11`, `conda-forge::scipy 1.12.*`
+        let spec = item.rsplit_once("::").map(|(_, s)| s).unwrap_or(item);
+        let name = requirement_name(spec);
+        let version = spec[name.len()..].trim().trim_start_matches('=');
+        match name.as_str() {
+            "python" => {
+                if !version.is_empty() {
+                    meta.runtime.push(format!("python {}", version));
+                }
+            }
+            "pip" => {}
+            _ => {
+                meta.requirements.push(spec.to_string());
+                meta.dependencies.push(name);
+            }
+        }
+    }
+}
+
+fn locked_version<'a>(packages: &[&'a toml::Table], name: &str) -> Option<&'a str> {
+    packages
+        .iter()
+        .find(|p| p.get("name").and_then(|v| v.as_str()) == Some(name))
+        .and_then(|p| p.get("version"))
+        .and_then(|v| v.as_str())
+}
+
+// CodePack: Pipenv 的 Pipfile（TOML 格式）
+fn extract_pipfile(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(content) = fs::read_to_string(root.join("Pipfile")) else { return };
+    let Ok(doc) = content.parse::<toml::Table>() else { return };
+    if let Some(packages) = doc.get("packages").and_then(|v| v.as_table()) {
+        for (name, value) in packages {
+            let spec = toml_dep_spec(value);
+            meta.dependencies.push(name.clone());
+            meta.requirements.push(if spec == "*" { name.clone() } else { format!("{}{}", name, spec) });
+        }
+    }
+    if let Some(dev) = doc.get("dev-packages").and_then(|v| v.as_table()) {
+        meta.dev_dependencies.extend(dev.keys().cloned());
+    }
+    if meta.runtime.is_empty() {
+        let requires = doc.get("requires");
+        let version = requires
+            .and_then(|r| r.get("python_full_version"))
+            .or_else(|| requires.and_then(|r| r.get("python_version")))
+            .and_then(|v| v.as_str());
+        if let Some(version) = version {
+            meta.runtime.push(format!("python {}", version));
+        }
+    }
+}
+
 fn extract_go_mod(root: &Path, meta: &mut ProjectMetadata) {
     if let Ok(content) = fs::read_to_string(root.join("go.mod")) {
         for line in content.lines() {
@@ -213,67 +1124,155 @@ fn extract_go_mod(root: &Path, meta: &mut ProjectMetadata) {
 
 fn extract_pubspec_yaml(root: &Path, meta: &mut ProjectMetadata) {
     if let Ok(content) = fs::read_to_string(root.join("pubspec.yaml")) {
-        let mut in_deps = false;
-        let mut in_dev_deps = false;
-        let mut in_environment = false;
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if !line.starts_with(' ') && !line.starts_with('\t') {
-                in_deps = false; in_dev_deps = false; in_environment = false;
-                if trimmed.starts_with("name:") {
-                    meta.name = trimmed.strip_prefix("name:").unwrap_or("").trim().to_string();
-                } else if trimmed.starts_with("version:") {
-                    meta.version = Some(trimmed.strip_prefix("version:").unwrap_or("").trim().trim_matches('"').trim_matches('\'').to_string());
-                } else if trimmed.starts_with("description:") {
-                    let desc = trimmed.strip_prefix("description:").unwrap_or("").trim().trim_matches('"').trim_matches('\'').to_string();
-                    if !desc.is_empty() { meta.description = Some(desc); }
-                } else if trimmed == "dependencies:" { in_deps = true; }
-                else if trimmed == "dev_dependencies:" { in_dev_deps = true; }
-                else if trimmed == "environment:" { in_environment = true; }
-            } else if in_environment && trimmed.contains(':') {
-                let parts: Vec<&str> = trimmed.splitn(2, ':').collect();
-                if parts.len() == 2 {
-                    let key = parts[0].trim();
-                    let val = parts[1].trim().trim_matches('"').trim_matches('\'');
-                    if !val.is_empty() { meta.runtime.push(format!("{} {}", key, val)); }
-                }
-            } else if (in_deps || in_dev_deps) && trimmed.contains(':') {
-                let parts: Vec<&str> = trimmed.splitn(2, ':').collect();
-                let dep_name = parts[0].trim().to_string();
-                let dep_ver = parts.get(1).map(|v| v.trim().trim_matches('"').trim_matches('\'').to_string()).unwrap_or_default();
-                if !dep_name.is_empty() && dep_name != "sdk" {
-                    if in_deps {
-                        meta.dependencies.push(dep_name.clone());
-                        if !dep_ver.is_empty() && dep_ver != "^" {
-                            meta.requirements.push(format!("{}@{}", dep_name, dep_ver));
-                        }
-                    } else { meta.dev_dependencies.push(dep_name); }
-                }
-            }
+        match parse_yaml(&content) {
+            Some(doc) => extract_pubspec_doc(&doc, meta),
+            None => extract_pubspec_lines(&content, meta),
         }
     }
     if root.join("lib/main.dart").exists() { meta.entry_point = Some("lib/main.dart".to_string()); }
 }
 
-fn extract_pom_xml(root: &Path, meta: &mut ProjectMetadata) {
-    if let Ok(content) = fs::read_to_string(root.join("pom.xml")) {
-        if let Some(aid) = extract_xml_tag(&content, "artifactId") { meta.name = aid; }
-        if let Some(ver) = extract_xml_tag(&content, "version") { meta.version = Some(ver); }
-        if let Some(desc) = extract_xml_tag(&content, "description") {
-            if !desc.is_empty() { meta.description = Some(desc); }
+/// `^1.2.0`, `{ version: ^1.2.0 }`, `{ git: { url, ref } }`, `{ path: ../x }`; `None` for
+/// SDK packages and unconstrained deps
+fn pubspec_dep_spec(spec: &serde_yaml::Value) -> Option<String> {
+    if let Some(version) = yaml_scalar(spec) {
+        return Some(version).filter(|v| !v.is_empty() && v != "^" && v != "any");
+    }
+    if spec.get("sdk").is_some() {
+        return None;
+    }
+    if let Some(version) = spec.get("version").and_then(yaml_scalar) {
+        return Some(version);
+    }
+    if let Some(git) = spec.get("git") {
+        let url = yaml_scalar(git).or_else(|| git.get("url").and_then(yaml_scalar))?;
+        return Some(match git.get("ref").and_then(yaml_scalar) {
+            Some(git_ref) => format!("git {}#{}", url, git_ref),
+            None => format!("git {}", url),
+        });
+    }
+    spec.get("path").and_then(yaml_scalar).map(|path| format!("path {}", path))
+}
+
+fn extract_pubspec_doc(doc: &serde_yaml::Value, meta: &mut ProjectMetadata) {
+    if let Some(name) = doc.get("name").and_then(yaml_scalar) {
+        meta.name = name;
+    }
+    if let Some(ver) = doc.get("version").and_then(yaml_scalar) {
+        meta.version = Some(ver);
+    }
+    if let Some(desc) = doc.get("description").and_then(yaml_scalar).filter(|d| !d.is_empty()) {
+        meta.description = Some(desc);
+    }
+    if let Some(environment) = doc.get("environment").and_then(|v| v.as_mapping()) {
+        for (key, val) in environment {
+            if let (Some(key), Some(val)) = (yaml_scalar(key), yaml_scalar(val)) {
+                meta.runtime.push(format!("{} {}", key, val));
+            }
         }
-        if let Some(jv) = extract_xml_tag(&content, "java.version") {
-            meta.runtime.push(format!("java {}", jv));
-        } else if let Some(jv) = extract_xml_tag(&content, "maven.compiler.source") {
-            meta.runtime.push(format!("java {}", jv));
+    }
+    if let Some(deps) = doc.get("dependencies").and_then(|v| v.as_mapping()) {
+        for (name, spec) in deps {
+            let Some(name) = yaml_scalar(name) else { continue };
+            if let Some(spec) = pubspec_dep_spec(spec) {
+                meta.requirements.push(format!("{}@{}", name, spec));
+            }
+            meta.dependencies.push(name);
         }
-        let mut in_deps = false;
-        let mut cur_group = String::new();
-        let mut cur_artifact = String::new();
-        let mut cur_version = String::new();
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.contains("<dependencies>") { in_deps = true; }
+    }
+    meta.dev_dependencies.extend(yaml_names(doc.get("dev_dependencies")));
+}
+
+/// Line-based fallback for pubspec files serde_yaml rejects
+fn extract_pubspec_lines(content: &str, meta: &mut ProjectMetadata) {
+    let mut in_deps = false;
+    let mut in_dev_deps = false;
+    let mut in_environment = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if !line.starts_with(' ') && !line.starts_with('\t') {
+            in_deps = false; in_dev_deps = false; in_environment = false;
+            if trimmed.starts_with("name:") {
+                meta.name = trimmed.strip_prefix("name:").unwrap_or("").trim().to_string();
+            } else if trimmed.starts_with("version:") {
+                meta.version = Some(trimmed.strip_prefix("version:").unwrap_or("").trim().trim_matches('"').trim_matches('\'').to_string());
+            } else if trimmed.starts_with("description:") {
+                let desc = trimmed.strip_prefix("description:").unwrap_or("").trim().trim_matches('"').trim_matches('\'').to_string();
+                if !desc.is_empty() { meta.description = Some(desc); }
+            } else if trimmed == "dependencies:" { in_deps = true; }
+            else if trimmed == "dev_dependencies:" { in_dev_deps = true; }
+            else if trimmed == "environment:" { in_environment = true; }
+        } else if in_environment && trimmed.contains(':') {
+            let parts: Vec<&str> = trimmed.splitn(2, ':').collect();
+            if parts.len() == 2 {
+                let key = parts[0].trim();
+                let val = parts[1].trim().trim_matches('"').trim_matches('\'');
+                if !val.is_empty() { meta.runtime.push(format!("{} {}", key, val)); }
+            }
+        } else if (in_deps || in_dev_deps) && trimmed.contains(':') {
+            let parts: Vec<&str> = trimmed.splitn(2, ':').collect();
+            let dep_name = parts[0].trim().to_string();
+            let dep_ver = parts.get(1).map(|v| v.trim().trim_matches('"').trim_matches('\'').to_string()).unwrap_or_default();
+            if !dep_name.is_empty() && dep_name != "sdk" {
+                if in_deps {
+                    meta.dependencies.push(dep_name.clone());
+                    if !dep_ver.is_empty() && dep_ver != "^" {
+                        meta.requirements.push(format!("{}@{}", dep_name, dep_ver));
+                    }
+                } else { meta.dev_dependencies.push(dep_name); }
+            }
+        }
+    }
+}
+
+// CodePack: 依赖版本来自 dependencyManagement 或 ${...} 属性时也能给出实际值；test 作用域归入开发依赖
+fn extract_pom_xml(root: &Path, meta: &mut ProjectMetadata) {
+    let Some(pom) = read_pom(root) else {
+        // Not well-formed XML: the string scanner still gets most fields
+        extract_pom_lines(root, meta);
+        return;
+    };
+    if let Some(aid) = pom.artifact_id { meta.name = aid; }
+    if let Some(ver) = pom.version { meta.version = Some(ver); }
+    if let Some(desc) = pom.description { meta.description = Some(desc); }
+    let java = ["java.version", "maven.compiler.release", "maven.compiler.source"]
+        .iter()
+        .find_map(|key| pom.properties.get(*key).filter(|v| !v.is_empty()));
+    if let Some(jv) = java {
+        meta.runtime.push(format!("java {}", jv));
+    }
+    for dep in pom.dependencies {
+        if dep.scope.as_deref() == Some("test") {
+            meta.dev_dependencies.push(dep.artifact_id);
+            continue;
+        }
+        meta.requirements.push(match dep.version {
+            Some(ref version) => format!("{}:{}:{}", dep.group_id, dep.artifact_id, version),
+            None => format!("{}:{}", dep.group_id, dep.artifact_id),
+        });
+        meta.dependencies.push(dep.artifact_id);
+    }
+}
+
+fn extract_pom_lines(root: &Path, meta: &mut ProjectMetadata) {
+    if let Ok(content) = fs::read_to_string(root.join("pom.xml")) {
+        if let Some(aid) = extract_xml_tag(&content, "artifactId") { meta.name = aid; }
+        if let Some(ver) = extract_xml_tag(&content, "version") { meta.version = Some(ver); }
+        if let Some(desc) = extract_xml_tag(&content, "description") {
+            if !desc.is_empty() { meta.description = Some(desc); }
+        }
+        if let Some(jv) = extract_xml_tag(&content, "java.version") {
+            meta.runtime.push(format!("java {}", jv));
+        } else if let Some(jv) = extract_xml_tag(&content, "maven.compiler.source") {
+            meta.runtime.push(format!("java {}", jv));
+        }
+        let mut in_deps = false;
+        let mut cur_group = String::new();
+        let mut cur_artifact = String::new();
+        let mut cur_version = String::new();
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.contains("<dependencies>") { in_deps = true; }
             if trimmed.contains("</dependencies>") { in_deps = false; }
             if in_deps {
                 if let Some(v) = extract_xml_tag(trimmed, "groupId") { cur_group = v; }
@@ -311,6 +1310,634 @@ fn extract_gradle_meta(root: &Path, meta: &mut ProjectMetadata) {
     }
 }
 
+// CodePack: Ruby 项目：Gemfile（依赖与分组）、Gemfile.lock（锁定版本）、.ruby-version、*.gemspec
+fn extract_ruby_meta(root: &Path, meta: &mut ProjectMetadata) {
+    extract_gemspec(root, meta);
+    let locked = gemfile_lock_versions(root);
+
+    if let Ok(content) = fs::read_to_string(root.join("Gemfile")) {
+        // One entry per open `group ... do` block: whether it is development/test only
+        let mut groups: Vec<bool> = Vec::new();
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(rest) = trimmed.strip_prefix("group ") {
+                groups.push(is_dev_group(rest));
+                continue;
+            }
+            if trimmed.ends_with(" do") || trimmed == "do" {
+                // platforms / source / git blocks also close with `end`
+                groups.push(groups.last().copied().unwrap_or(false));
+                continue;
+            }
+            if trimmed == "end" {
+                groups.pop();
+                continue;
+            }
+            if let Some(version) = trimmed.strip_prefix("ruby ").and_then(ruby_string) {
+                meta.runtime.push(format!("ruby {}", version));
+                continue;
+            }
+            let Some(args) = trimmed.strip_prefix("gem ") else { continue };
+            let mut parts = args.split(',').map(str::trim);
+            let Some(name) = parts.next().and_then(ruby_string) else { continue };
+            let rest: Vec<&str> = parts.collect();
+            let constraints: Vec<String> = rest.iter().map_while(|p| ruby_string(p)).collect();
+            let inline_group = rest
+                .iter()
+                .find_map(|p| p.strip_prefix("group:").or_else(|| p.strip_prefix(":group =>")))
+                .is_some_and(is_dev_group);
+            if inline_group || groups.last().copied().unwrap_or(false) {
+                meta.dev_dependencies.push(name.clone());
+            } else {
+                meta.dependencies.push(name.clone());
+            }
+            let version = match locked.get(&name) {
+                Some(v) => v.clone(),
+                None if constraints.is_empty() => "*".to_string(),
+                None => constraints.join(", "),
+            };
+            meta.requirements.push(format!("{}@{}", name, version));
+        }
+    }
+
+    if meta.runtime.is_empty() {
+        if let Ok(ver) = fs::read_to_string(root.join(".ruby-version")) {
+            let v = ver.trim().trim_start_matches("ruby-").to_string();
+            if !v.is_empty() { meta.runtime.push(format!("ruby {}", v)); }
+        }
+    }
+    for entry in &["config.ru", "app.rb", "main.rb"] {
+        if root.join(entry).exists() {
+            meta.entry_point = Some(entry.to_string());
+            break;
+        }
+    }
+}
+
+/// `:development, :test do` → true; a group that also covers production is not dev-only
+fn is_dev_group(groups: &str) -> bool {
+    let groups = groups.trim_end_matches(" do");
+    (groups.contains(":development") || groups.contains(":test")) && !groups.contains(":production")
+}
+
+/// `"rails"` / `'rails'` → `rails`; anything else (symbols, constants) gives `None`
+fn ruby_string(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let quote = raw.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    raw[1..].strip_suffix(quote).map(|s| s.to_string())
+}
+
+/// Top-level specs of Gemfile.lock: `    rails (7.1.2)` (four spaces; deeper lines are
+/// transitive requirements)
+fn gemfile_lock_versions(root: &Path) -> HashMap<String, String> {
+    let mut versions = HashMap::new();
+    let Ok(content) = fs::read_to_string(root.join("Gemfile.lock")) else { return versions };
+    for line in content.lines() {
+        if !line.starts_with("    ") || line.starts_with("     ") {
+            continue;
+        }
+        if let Some((name, rest)) = line.trim().split_once(" (") {
+            let version = rest.trim_end_matches(')');
+            // Platform-specific builds look like `nokogiri (1.16.0-x86_64-linux)`
+            versions.entry(name.to_string()).or_insert_with(|| version.to_string());
+        }
+    }
+    versions
+}
+
+/// Name / version / summary of a gem being developed in this repository
+fn extract_gemspec(root: &Path, meta: &mut ProjectMetadata) {
+    let gemspec = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.extension().is_some_and(|e| e == "gemspec"));
+    let Some(content) = gemspec.and_then(|p| fs::read_to_string(p).ok()) else { return };
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else { continue };
+        let Some(value) = ruby_string(value) else { continue };
+        match key.trim().rsplit('.').next().unwrap_or("") {
+            "name" => meta.name = value,
+            "version" => meta.version = Some(value),
+            "summary" if meta.description.is_none() => meta.description = Some(value),
+            "description" => meta.description = Some(value),
+            "required_ruby_version" => meta.runtime.push(format!("ruby {}", value)),
+            "license" => meta.license = Some(value),
+            _ => {}
+        }
+    }
+}
+
+// CodePack: Docker 部署信息：基础镜像、暴露端口、compose 服务，写入 runtime
+fn extract_docker_meta(root: &Path, meta: &mut ProjectMetadata) {
+    let mut dockerfiles: Vec<String> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n == "Dockerfile" || n.starts_with("Dockerfile.") || n.ends_with(".Dockerfile"))
+        .collect();
+    dockerfiles.sort();
+
+    let mut push_unique = |entry: String| {
+        if !meta.runtime.contains(&entry) {
+            meta.runtime.push(entry);
+        }
+    };
+    for dockerfile in &dockerfiles {
+        let Ok(content) = fs::read_to_string(root.join(dockerfile)) else { continue };
+        // Stage names from `FROM x AS build`; a later `FROM build` is not a base image
+        let mut stages: Vec<String> = Vec::new();
+        for line in content.lines() {
+            let mut words = line.split_whitespace();
+            let Some(instruction) = words.next() else { continue };
+            let args: Vec<&str> = words.filter(|w| !w.starts_with("--")).collect();
+            if instruction.eq_ignore_ascii_case("FROM") {
+                let Some(image) = args.first() else { continue };
+                if !stages.iter().any(|s| s.eq_ignore_ascii_case(image)) && *image != "scratch" {
+                    push_unique(format!("docker {}", image));
+                }
+                if args.len() >= 3 && args[1].eq_ignore_ascii_case("AS") {
+                    stages.push(args[2].to_string());
+                }
+            } else if instruction.eq_ignore_ascii_case("EXPOSE") {
+                for port in args {
+                    push_unique(format!("expose {}", port));
+                }
+            }
+        }
+    }
+    for (service, image) in compose_services(root) {
+        push_unique(match image {
+            Some(image) => format!("compose {} ({})", service, image),
+            None => format!("compose {}", service),
+        });
+    }
+}
+
+/// Service names (with their `image:` when set) from the compose file, read line by line
+fn compose_services(root: &Path) -> Vec<(String, Option<String>)> {
+    let content = ["compose.yaml", "compose.yml", "docker-compose.yml", "docker-compose.yaml"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok());
+    let Some(content) = content else { return Vec::new() };
+    let Some(doc) = parse_yaml(&content) else { return compose_services_lines(&content) };
+    let Some(services) = doc.get("services").and_then(|v| v.as_mapping()) else { return Vec::new() };
+    services
+        .iter()
+        .filter_map(|(name, service)| {
+            let image = service.get("image").and_then(yaml_scalar);
+            Some((yaml_scalar(name)?, image))
+        })
+        .collect()
+}
+
+fn compose_services_lines(content: &str) -> Vec<(String, Option<String>)> {
+    let mut services: Vec<(String, Option<String>)> = Vec::new();
+    let mut in_services = false;
+    let mut service_indent: Option<usize> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            in_services = trimmed == "services:";
+            service_indent = None;
+            continue;
+        }
+        if !in_services {
+            continue;
+        }
+        // The first indented key fixes the indent used for service names
+        if indent == *service_indent.get_or_insert(indent) {
+            if let Some(name) = trimmed.strip_suffix(':') {
+                services.push((name.trim_matches(|c| c == '"' || c == '\'').to_string(), None));
+            }
+        } else if let Some(image) = trimmed.strip_prefix("image:") {
+            if let Some(last) = services.last_mut() {
+                last.1 = Some(image.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+        }
+    }
+    services
+}
+
+/// Value of a `key = "value"` attribute anywhere in an HCL snippet
+fn hcl_attr(text: &str, key: &str) -> Option<String> {
+    text.match_indices(key).find_map(|(pos, _)| {
+        let before = text[..pos].chars().last();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let rest = text[pos + key.len()..].trim_start().strip_prefix('=')?;
+        let value = rest.trim_start().strip_prefix('"')?;
+        value.split('"').next().map(|v| v.to_string())
+    })
+}
+
+fn push_terraform_source(meta: &mut ProjectMetadata, source: String, version: Option<String>) {
+    if meta.dependencies.contains(&source) {
+        return;
+    }
+    meta.requirements.push(match version {
+        Some(ref v) => format!("{}@{}", source, v),
+        None => source.clone(),
+    });
+    meta.dependencies.push(source);
+}
+
+// CodePack: Terraform 根模块：required_version、required_providers 与 module 来源
+fn extract_terraform_meta(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(entries) = fs::read_dir(root) else { return };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "tf"))
+        .collect();
+    files.sort();
+
+    for file in files {
+        let Ok(content) = fs::read_to_string(&file) else { continue };
+        let mut depth = 0i32;
+        // Brace depth at which the entries of `required_providers` live
+        let mut providers_depth: Option<i32> = None;
+        // Provider entry or module block being read: (label, text so far, depth it opened at)
+        let mut block: Option<(String, String, i32)> = None;
+        for line in content.lines() {
+            // Only whole-line and ` #` comments: `//` also appears in module source URLs
+            let trimmed = line.trim();
+            let trimmed = if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                ""
+            } else {
+                trimmed.split(" #").next().unwrap_or(trimmed).trim()
+            };
+            let opens = trimmed.matches('{').count() as i32;
+            let closes = trimmed.matches('}').count() as i32;
+
+            if trimmed.starts_with("required_version") {
+                if let Some(version) = hcl_attr(trimmed, "required_version") {
+                    meta.runtime.push(format!("terraform {}", version));
+                }
+            } else if trimmed.starts_with("required_providers") && opens > 0 {
+                providers_depth = Some(depth + 1);
+            } else if block.is_none() && providers_depth == Some(depth) {
+                if let Some((name, _)) = trimmed.split_once('=') {
+                    let name = name.trim();
+                    if opens > 0 {
+                        block = Some((format!("provider {}", name), String::new(), depth));
+                    } else if let Some(version) = hcl_attr(trimmed, name) {
+                        // Terraform 0.12 shorthand `aws = "~> 3.0"` means hashicorp/aws
+                        push_terraform_source(meta, format!("hashicorp/{}", name), Some(version));
+                    }
+                }
+            } else if depth == 0 && trimmed.starts_with("module ") && opens > 0 {
+                let name = trimmed.split('"').nth(1).unwrap_or("");
+                block = Some((format!("module {}", name), String::new(), depth));
+            }
+
+            if let Some((_, ref mut text, _)) = block {
+                text.push_str(trimmed);
+                text.push('\n');
+            }
+            depth += opens - closes;
+            if providers_depth.is_some_and(|d| depth < d) {
+                providers_depth = None;
+            }
+            // The block is complete once the depth falls back to where it opened
+            if block.as_ref().is_some_and(|(_, _, start)| depth <= *start) {
+                let (label, text, _) = block.take().unwrap_or_default();
+                let source = hcl_attr(&text, "source");
+                let version = hcl_attr(&text, "version");
+                match (label.strip_prefix("provider "), source) {
+                    (Some(name), source) => {
+                        let source = source.unwrap_or_else(|| format!("hashicorp/{}", name));
+                        push_terraform_source(meta, source, version);
+                    }
+                    (None, Some(source)) => push_terraform_source(meta, source, version),
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+    if root.join("main.tf").exists() {
+        meta.entry_point = Some("main.tf".to_string());
+    }
+}
+
+// CodePack: Pulumi.yaml 中的项目名、描述与运行时（runtime 可为字符串或 { name: ... }）
+fn extract_pulumi_meta(root: &Path, meta: &mut ProjectMetadata) {
+    let content = ["Pulumi.yaml", "Pulumi.yml"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok());
+    let Some(content) = content else { return };
+    if let Some(name) = yaml_top_level_value(&content, "name") {
+        meta.name = name;
+    }
+    if let Some(desc) = yaml_top_level_value(&content, "description") {
+        meta.description = Some(desc);
+    }
+    let runtime = yaml_top_level_value(&content, "runtime").or_else(|| {
+        content
+            .lines()
+            .skip_while(|l| !l.starts_with("runtime:"))
+            .skip(1)
+            .take_while(|l| l.starts_with(' '))
+            .find_map(|l| l.trim().strip_prefix("name:").map(yaml_unquote))
+    });
+    if let Some(runtime) = runtime {
+        meta.runtime.push(format!("pulumi {}", runtime));
+    }
+}
+
+// CodePack: cdk.json 的 app 命令指向 CDK 应用入口
+fn extract_cdk_meta(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(content) = fs::read_to_string(root.join("cdk.json")) else { return };
+    let Ok(cdk) = serde_json::from_str::<serde_json::Value>(&content) else { return };
+    if let Some(app) = cdk.get("app").and_then(|v| v.as_str()) {
+        meta.runtime.push(format!("cdk app: {}", app));
+        // `npx ts-node --prefer-ts-exts bin/app.ts` → bin/app.ts
+        let entry = app.split_whitespace().rev().find(|arg| !arg.starts_with('-') && arg.contains('.'));
+        if let Some(entry) = entry {
+            meta.entry_point = Some(entry.to_string());
+        }
+    }
+}
+
+// CodePack: Unity 编辑器版本与 Packages/manifest.json 中的包
+fn extract_unity_meta(root: &Path, meta: &mut ProjectMetadata) {
+    if let Ok(content) = fs::read_to_string(root.join("ProjectSettings/ProjectVersion.txt")) {
+        if let Some(version) = content.lines().find_map(|l| l.strip_prefix("m_EditorVersion:")) {
+            meta.runtime.push(format!("unity {}", version.trim()));
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join("Packages/manifest.json")) {
+        if let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&content) {
+            if let Some(deps) = manifest.get("dependencies").and_then(|v| v.as_object()) {
+                for (name, version) in deps {
+                    meta.dependencies.push(name.clone());
+                    if let Some(v) = version.as_str() {
+                        meta.requirements.push(format!("{}@{}", name, v));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// CodePack: Godot 的 project.godot 是 INI 风格，名称在 config/name，引擎版本在 config/features
+fn extract_godot_meta(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(content) = fs::read_to_string(root.join("project.godot")) else { return };
+    for line in content.lines() {
+        if let Some(name) = line.strip_prefix("config/name=") {
+            meta.name = name.trim().trim_matches('"').to_string();
+        } else if let Some(desc) = line.strip_prefix("config/description=") {
+            let desc = desc.trim().trim_matches('"');
+            if !desc.is_empty() {
+                meta.description = Some(desc.to_string());
+            }
+        } else if let Some(main) = line.strip_prefix("run/main_scene=") {
+            meta.entry_point = Some(main.trim().trim_matches('"').to_string());
+        } else if let Some(features) = line.strip_prefix("config/features=") {
+            // PackedStringArray("4.2", "Forward Plus"): the version is the first element
+            if let Some(version) = features.split('"').nth(1) {
+                meta.runtime.push(format!("godot {}", version));
+            }
+        }
+    }
+}
+
+// CodePack: Unreal 的 .uproject 是 JSON：引擎版本与启用的插件
+fn extract_unreal_meta(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(entries) = fs::read_dir(root) else { return };
+    let Some(path) = entries.flatten().map(|e| e.path()).find(|p| p.extension().is_some_and(|e| e == "uproject")) else {
+        return;
+    };
+    if let Some(stem) = path.file_stem() {
+        meta.name = stem.to_string_lossy().to_string();
+    }
+    let Ok(content) = fs::read_to_string(&path) else { return };
+    let Ok(project) = serde_json::from_str::<serde_json::Value>(&content) else { return };
+    if let Some(engine) = project.get("EngineAssociation").and_then(|v| v.as_str()) {
+        if !engine.is_empty() {
+            meta.runtime.push(format!("unreal {}", engine));
+        }
+    }
+    if let Some(desc) = project.get("Description").and_then(|v| v.as_str()) {
+        if !desc.is_empty() {
+            meta.description = Some(desc.to_string());
+        }
+    }
+    for plugin in project.get("Plugins").and_then(|v| v.as_array()).into_iter().flatten() {
+        let enabled = plugin.get("Enabled").and_then(|v| v.as_bool()).unwrap_or(false);
+        if let Some(name) = plugin.get("Name").and_then(|v| v.as_str()).filter(|_| enabled) {
+            meta.dependencies.push(name.to_string());
+        }
+    }
+}
+
+// CodePack: 解析 mix.exs（Elixir / Phoenix），不执行 Elixir 代码，仅按常见写法逐行匹配
+fn extract_mix_exs(root: &Path, meta: &mut ProjectMetadata) {
+    let Ok(content) = fs::read_to_string(root.join("mix.exs")) else { return };
+
+    // Module attributes such as `@version "0.1.0"`, referenced as `version: @version`
+    let mut attributes: HashMap<String, String> = HashMap::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix('@') {
+            if let Some((name, value)) = rest.split_once(char::is_whitespace) {
+                if let Some(value) = elixir_string(value) {
+                    attributes.insert(name.to_string(), value);
+                }
+            }
+        }
+    }
+    let value_of = |raw: &str| -> Option<String> {
+        let raw = raw.trim().trim_end_matches(',');
+        match raw.strip_prefix('@') {
+            Some(attr) => attributes.get(attr).cloned(),
+            None => elixir_string(raw),
+        }
+    };
+
+    let mut in_deps = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("defp deps") || trimmed.starts_with("def deps") {
+            in_deps = true;
+            continue;
+        }
+        if in_deps {
+            if trimmed == "end" {
+                in_deps = false;
+            } else if let Some(dep) = parse_mix_dep(trimmed) {
+                let (name, requirement, dev_only) = dep;
+                if dev_only {
+                    meta.dev_dependencies.push(name.clone());
+                } else {
+                    meta.dependencies.push(name.clone());
+                }
+                meta.requirements.push(format!("{}@{}", name, requirement.unwrap_or_else(|| "*".to_string())));
+            }
+            continue;
+        }
+        if let Some(app) = trimmed.strip_prefix("app:") {
+            let app = app.trim().trim_end_matches(',').trim_start_matches(':');
+            if !app.is_empty() {
+                meta.name = app.to_string();
+            }
+        } else if let Some(ver) = trimmed.strip_prefix("version:") {
+            meta.version = value_of(ver);
+        } else if let Some(desc) = trimmed.strip_prefix("description:") {
+            meta.description = value_of(desc).filter(|d| !d.is_empty());
+        } else if let Some(req) = trimmed.strip_prefix("elixir:") {
+            if let Some(req) = value_of(req) {
+                meta.runtime.push(format!("elixir {}", req));
+            }
+        }
+    }
+
+    let app_entry = [
+        format!("lib/{}/application.ex", meta.name),
+        format!("lib/{}.ex", meta.name),
+    ];
+    meta.entry_point = app_entry.into_iter().find(|p| root.join(p).exists());
+}
+
+/// `"~> 1.7"` → `~> 1.7`; anything that isn't a plain string literal gives `None`
+fn elixir_string(raw: &str) -> Option<String> {
+    let raw = raw.trim().trim_end_matches(',');
+    raw.strip_prefix('"')?.strip_suffix('"').map(|s| s.to_string())
+}
+
+/// One `deps()` entry: `{:phoenix, "~> 1.7"}` or `{:credo, "~> 1.6", only: [:dev, :test]}`.
+/// Returns (name, version requirement, whether it is excluded from :prod)
+fn parse_mix_dep(line: &str) -> Option<(String, Option<String>, bool)> {
+    let inner = line.trim_end_matches(',').strip_prefix("{:")?.strip_suffix('}')?;
+    let mut parts = inner.split(',').map(str::trim);
+    let name = parts.next()?.to_string();
+    let rest: Vec<&str> = parts.collect();
+    let requirement = rest.first().and_then(|p| elixir_string(p));
+    let only = rest
+        .iter()
+        .position(|p| p.starts_with("only:"))
+        .map(|i| rest[i..].join(","));
+    let dev_only = only.is_some_and(|o| {
+        let o = o.split(']').next().unwrap_or(&o).to_string();
+        !o.contains(":prod")
+    });
+    Some((name, requirement, dev_only))
+}
+
+// ─── Cache ─────────────────────────────────────────────────────
+
+/// Besides the root's own files, metadata is read from these directories
+const METADATA_DIRS: &[&str] = &[".github/workflows", "src-tauri", "ProjectSettings", "Packages"];
+
+/// Name, mtime and size of every file the extractors may read. Top-level source edits
+/// also change it, which only costs a re-parse
+pub fn manifest_fingerprint(root: &Path) -> u64 {
+    let dirs = std::iter::once(root.to_path_buf()).chain(METADATA_DIRS.iter().map(|d| root.join(d)));
+    let mut files: Vec<_> = dirs
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten().map(|e| e.path()))
+        .collect();
+    files.extend(cargo_member_dirs(root).into_iter().map(|dir| dir.join("Cargo.toml")));
+
+    let mut stamps: Vec<(String, u128, u64)> = files
+        .iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(path).ok().filter(|m| m.is_file())?;
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            Some((path.to_string_lossy().to_string(), mtime, meta.len()))
+        })
+        .collect();
+    stamps.sort();
+    let mut hasher = DefaultHasher::new();
+    stamps.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct CachedMetadata {
+    project_types: Vec<String>,
+    fingerprint: u64,
+    metadata: ProjectMetadata,
+}
+
+// CodePack: 按项目缓存元数据，清单文件未变化时扫描和打包都不再重新解析
+#[derive(Default)]
+pub struct MetadataCacheState {
+    entries: Mutex<HashMap<String, CachedMetadata>>,
+}
+
+impl MetadataCacheState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lookup(&self, root: &Path, fingerprint: u64, matches: impl Fn(&[String]) -> bool) -> Option<ProjectMetadata> {
+        let entries = self.entries.lock().ok()?;
+        let cached = entries.get(root.to_string_lossy().as_ref())?;
+        if cached.fingerprint != fingerprint || !matches(&cached.project_types) {
+            return None;
+        }
+        let mut metadata = cached.metadata.clone();
+        drop(entries);
+        // Commits and checkouts move HEAD without touching any manifest
+        metadata.submodules = list_submodules(root);
+        metadata.repository = repository_info(root);
+        Some(metadata)
+    }
+
+    fn extract(&self, root: &Path, project_types: &[String], fingerprint: u64) -> ProjectMetadata {
+        let mut metadata = extract_metadata_multi(root, project_types);
+        // A failing module only loses its extra fields; test_plugin shows the error to the author
+        let _ = apply_wasm_metadata(&load_plugins(), root, project_types, &mut metadata);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                root.to_string_lossy().to_string(),
+                CachedMetadata {
+                    project_types: project_types.to_vec(),
+                    fingerprint,
+                    metadata: metadata.clone(),
+                },
+            );
+        }
+        metadata
+    }
+
+    /// Metadata for `root`, extracted again only when a manifest changed or the
+    /// detected types differ from the cached ones
+    pub fn get(&self, root: &Path, project_types: &[String]) -> ProjectMetadata {
+        let fingerprint = manifest_fingerprint(root);
+        self.lookup(root, fingerprint, |cached| cached == project_types)
+            .unwrap_or_else(|| self.extract(root, project_types, fingerprint))
+    }
+
+    /// Packing only knows the primary type; a cached scan with the same primary is reused
+    pub fn get_for_pack(&self, root: &Path, project_type: &str) -> ProjectMetadata {
+        let fingerprint = manifest_fingerprint(root);
+        self.lookup(root, fingerprint, |cached| {
+            cached.first().is_some_and(|t| t == project_type)
+        })
+        .unwrap_or_else(|| self.extract(root, &[project_type.to_string()], fingerprint))
+    }
+
+    /// Extracts unconditionally, replacing whatever was cached
+    pub fn refresh(&self, root: &Path, project_types: &[String]) -> ProjectMetadata {
+        self.extract(root, project_types, manifest_fingerprint(root))
+    }
+}
+
 pub fn extract_xml_tag(text: &str, tag: &str) -> Option<String> {
     let open = format!("<{}>", tag);
     let close = format!("</{}>", tag);
@@ -389,6 +2016,396 @@ tempfile = "3"
         assert!(meta.dev_dependencies.contains(&"jest".to_string()));
     }
 
+    #[test]
+    fn test_extract_metadata_pubspec_nested_deps() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pubspec.yaml"), r#"name: app
+version: 1.0.0+1
+environment:
+  sdk: ">=3.0.0 <4.0.0"
+dependencies:
+  flutter:
+    sdk: flutter
+  http: ^1.1.0
+  shared:
+    path: ../shared
+  kit:
+    git:
+      url: https://github.com/acme/kit.git
+      ref: v2
+  intl:
+    hosted: https://pub.example.com
+    version: ^0.19.0
+dev_dependencies:
+  flutter_test:
+    sdk: flutter
+"#).unwrap();
+        let meta = extract_metadata(dir.path(), "Flutter / Dart");
+        assert_eq!(meta.version.as_deref(), Some("1.0.0+1"));
+        assert_eq!(meta.runtime, vec!["sdk >=3.0.0 <4.0.0"]);
+        assert_eq!(meta.dependencies, vec!["flutter", "http", "shared", "kit", "intl"]);
+        assert_eq!(meta.requirements, vec![
+            "http@^1.1.0",
+            "shared@path ../shared",
+            "kit@git https://github.com/acme/kit.git#v2",
+            "intl@^0.19.0",
+        ]);
+        assert_eq!(meta.dev_dependencies, vec!["flutter_test"]);
+    }
+
+    #[test]
+    fn test_extract_metadata_pubspec_falls_back_on_invalid_yaml() {
+        let dir = TempDir::new().unwrap();
+        // Duplicate keys are rejected by serde_yaml
+        fs::write(dir.path().join("pubspec.yaml"), "name: app\ndependencies:\n  http: ^1.1.0\n  http: ^1.2.0\n").unwrap();
+        let meta = extract_metadata(dir.path(), "Flutter / Dart");
+        assert_eq!(meta.name, "app");
+        assert_eq!(meta.dependencies, vec!["http", "http"]);
+    }
+
+    #[test]
+    fn test_extract_metadata_pom() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pom.xml"), r#"<project>
+  <parent>
+    <groupId>org.springframework.boot</groupId>
+    <artifactId>spring-boot-starter-parent</artifactId>
+    <version>3.2.1</version>
+  </parent>
+  <artifactId>demo</artifactId>
+  <version>0.0.1-SNAPSHOT</version>
+  <properties>
+    <java.version>17</java.version>
+    <guava.version>33.0.0-jre</guava.version>
+  </properties>
+  <dependencies>
+    <dependency>
+      <groupId>org.springframework.boot</groupId>
+      <artifactId>spring-boot-starter-web</artifactId>
+    </dependency>
+    <dependency>
+      <groupId>com.google.guava</groupId>
+      <artifactId>guava</artifactId>
+      <version>${guava.version}</version>
+    </dependency>
+    <dependency>
+      <groupId>org.junit.jupiter</groupId>
+      <artifactId>junit-jupiter</artifactId>
+      <version>5.10.1</version>
+      <scope>test</scope>
+    </dependency>
+  </dependencies>
+</project>"#).unwrap();
+        let meta = extract_metadata(dir.path(), "Java / Maven");
+        assert_eq!(meta.name, "demo");
+        assert_eq!(meta.version.as_deref(), Some("0.0.1-SNAPSHOT"));
+        assert_eq!(meta.runtime, vec!["java 17"]);
+        assert_eq!(meta.requirements, vec![
+            "org.springframework.boot:spring-boot-starter-web",
+            "com.google.guava:guava:33.0.0-jre",
+        ]);
+        assert_eq!(meta.dev_dependencies, vec!["junit-jupiter"]);
+    }
+
+    #[test]
+    fn test_extract_metadata_cargo_workspace() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "0.4.0"
+edition = "2021"
+
+[workspace.dependencies]
+tokio = { version = "1", features = ["rt"] }
+serde = "1"
+"#).unwrap();
+        fs::create_dir_all(dir.path().join("crates/core")).unwrap();
+        fs::write(dir.path().join("crates/core/Cargo.toml"), r#"
+[package]
+name = "acme-core"
+version.workspace = true
+"#).unwrap();
+        fs::create_dir_all(dir.path().join("crates/cli")).unwrap();
+        fs::write(dir.path().join("crates/cli/Cargo.toml"), "[package]\nname = \"acme-cli\"\nversion = \"0.1.0\"\n").unwrap();
+
+        let meta = extract_metadata(dir.path(), "Rust");
+        assert_eq!(meta.version, Some("0.4.0".to_string()));
+        assert!(meta.requirements.iter().any(|r| r == "tokio@1 [rt]"));
+        let members: Vec<(&str, Option<&str>, &str)> = meta
+            .workspace_members
+            .iter()
+            .map(|m| (m.name.as_str(), m.version.as_deref(), m.path.as_str()))
+            .collect();
+        assert_eq!(members, vec![
+            ("acme-cli", Some("0.1.0"), "crates/cli"),
+            ("acme-core", Some("0.4.0"), "crates/core"),
+        ]);
+    }
+
+    #[test]
+    fn test_cargo_requirement_features() {
+        let value: toml::Value = toml::from_str::<toml::Table>("d = { version = \"1\", features = [\"full\", \"macros\"] }")
+            .unwrap()["d"]
+            .clone();
+        assert_eq!(cargo_requirement("tokio", &value, None), "tokio@1 [full, macros]");
+        let inherited: toml::Value = toml::from_str::<toml::Table>("d = { workspace = true, features = [\"derive\"] }")
+            .unwrap()["d"]
+            .clone();
+        let shared = toml::from_str::<toml::Table>("serde = \"1.0\"").unwrap();
+        assert_eq!(cargo_requirement("serde", &inherited, Some(&shared)), "serde@1.0 [derive]");
+    }
+
+    #[test]
+    fn test_license_from_manifest_and_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"name": "a", "license": "Apache-2.0"}"#).unwrap();
+        fs::write(dir.path().join("LICENSE"), "MIT License\n\nPermission is hereby granted, free of charge, ...").unwrap();
+        // The manifest's declaration wins over the text
+        assert_eq!(extract_metadata(dir.path(), "Node.js").license, Some("Apache-2.0".to_string()));
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("COPYING"), "GNU GENERAL PUBLIC LICENSE\nVersion 3, 29 June 2007").unwrap();
+        assert_eq!(extract_metadata(dir.path(), "C").license, Some("GPL-3.0".to_string()));
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("LICENSE.txt"), "All rights reserved.").unwrap();
+        assert_eq!(extract_metadata(dir.path(), "Go").license, Some("see LICENSE.txt".to_string()));
+
+        let dir = TempDir::new().unwrap();
+        assert_eq!(extract_metadata(dir.path(), "Go").license, None);
+    }
+
+    #[test]
+    fn test_extract_metadata_docker() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Dockerfile"), "FROM --platform=linux/amd64 node:20-alpine AS build\nRUN npm ci\nFROM nginx:1.25\nCOPY --from=build /app/dist /usr/share/nginx/html\nEXPOSE 80 443/tcp\n").unwrap();
+        fs::write(dir.path().join("docker-compose.yml"), "version: \"3.9\"\nservices:\n  web:\n    build: .\n    ports:\n      - \"8080:80\"\n  db:\n    image: postgres:16\nvolumes:\n  data:\n").unwrap();
+
+        let meta = extract_metadata(dir.path(), "Docker");
+        assert_eq!(meta.runtime, vec![
+            "docker node:20-alpine",
+            "docker nginx:1.25",
+            "expose 80",
+            "expose 443/tcp",
+            "compose web",
+            "compose db (postgres:16)",
+        ]);
+    }
+
+    #[test]
+    fn test_extract_ci_pipelines() {
+        let dir = TempDir::new().unwrap();
+        let workflows = dir.path().join(".github/workflows");
+        fs::create_dir_all(&workflows).unwrap();
+        fs::write(workflows.join("ci.yml"), "name: CI\non:\n  push:\n    branches: [main]\n  pull_request:\njobs:\n  build:\n    runs-on: ubuntu-latest\n    steps:\n      - run: cargo build\n  test:\n    needs: build\n").unwrap();
+        fs::write(workflows.join("release.yaml"), "\"on\": [push, workflow_dispatch]\njobs:\n    publish:\n        runs-on: ubuntu-latest\n").unwrap();
+        fs::write(dir.path().join(".gitlab-ci.yml"), "stages: [build, test]\nvariables:\n  X: 1\n.template:\n  image: rust\nbuild:\n  stage: build\n  rules:\n    - if: $CI_PIPELINE_SOURCE == \"merge_request_event\"\nunit-test:\n  stage: test\n").unwrap();
+        fs::write(dir.path().join("Jenkinsfile"), "pipeline {\n  triggers { cron('H 4 * * *') }\n  stages {\n    stage('Build') { steps { sh 'make' } }\n    stage(\"Test\") { steps { sh 'make test' } }\n  }\n}\n").unwrap();
+
+        let ci = extract_metadata(dir.path(), "Generic").ci;
+        assert_eq!(ci.len(), 4);
+        assert_eq!(ci[0].file, ".github/workflows/ci.yml");
+        assert_eq!(ci[0].name.as_deref(), Some("CI"));
+        assert_eq!(ci[0].triggers, vec!["push", "pull_request"]);
+        assert_eq!(ci[0].jobs, vec!["build", "test"]);
+        assert_eq!(ci[1].name, None);
+        assert_eq!(ci[1].triggers, vec!["push", "workflow_dispatch"]);
+        assert_eq!(ci[1].jobs, vec!["publish"]);
+        assert_eq!(ci[2].provider, "GitLab CI");
+        assert_eq!(ci[2].jobs, vec!["build", "unit-test"]);
+        assert_eq!(ci[2].triggers, vec!["merge_request_event"]);
+        assert_eq!(ci[3].provider, "Jenkins");
+        assert_eq!(ci[3].jobs, vec!["Build", "Test"]);
+        assert_eq!(ci[3].triggers, vec!["cron H 4 * * *"]);
+    }
+
+    #[test]
+    fn test_yaml_section_keys_block_list() {
+        assert_eq!(yaml_section_keys("on:\n- push\n- pull_request\njobs:\n", "on"), vec!["push", "pull_request"]);
+        assert_eq!(yaml_section_keys("on: push # default\n", "on"), vec!["push"]);
+    }
+
+    #[test]
+    fn test_detect_frameworks() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"dependencies":{"react":"^18.2.0","@tauri-apps/api":"^2.0.0"},"devDependencies":{"vite":"^5.0.0"}}"#).unwrap();
+        fs::create_dir_all(dir.path().join("src-tauri")).unwrap();
+        fs::write(dir.path().join("src-tauri/tauri.conf.json"), r#"{"identifier":"com.example.app"}"#).unwrap();
+        let meta = extract_metadata(dir.path(), "Node.js");
+        assert_eq!(meta.frameworks, vec!["React 18", "Tauri 2"]);
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("requirements.txt"), "Django>=5.0,<6\nfastapi[all]>=0.110\n").unwrap();
+        let meta = extract_metadata(dir.path(), "Python");
+        assert_eq!(meta.frameworks, vec!["Django 5", "FastAPI 0.110"]);
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pom.xml"), "<project>\n<parent>\n<groupId>org.springframework.boot</groupId>\n<artifactId>spring-boot-starter-parent</artifactId>\n<version>3.2.1</version>\n</parent>\n<artifactId>demo</artifactId>\n</project>\n").unwrap();
+        let meta = extract_metadata(dir.path(), "Java / Maven");
+        assert_eq!(meta.frameworks, vec!["Spring Boot 3"]);
+    }
+
+    #[test]
+    fn test_major_version() {
+        assert_eq!(major_version("@^18.2.0").as_deref(), Some("18"));
+        assert_eq!(major_version("@v1.9.1").as_deref(), Some("1"));
+        assert_eq!(major_version(">=0.110,<1").as_deref(), Some("0.110"));
+        assert_eq!(major_version("@*"), None);
+    }
+
+    #[test]
+    fn test_extract_game_engine_meta() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("project.godot"), "config_version=5\n\n[application]\n\nconfig/name=\"Dodge\"\nrun/main_scene=\"res://main.tscn\"\nconfig/features=PackedStringArray(\"4.2\", \"Forward Plus\")\n").unwrap();
+        let meta = extract_metadata(dir.path(), "Godot");
+        assert_eq!(meta.name, "Dodge");
+        assert_eq!(meta.entry_point.as_deref(), Some("res://main.tscn"));
+        assert_eq!(meta.runtime, vec!["godot 4.2"]);
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Shooter.uproject"), r#"{"EngineAssociation":"5.3","Plugins":[{"Name":"ModelingToolsEditorMode","Enabled":true},{"Name":"Paper2D","Enabled":false}]}"#).unwrap();
+        let meta = extract_metadata(dir.path(), "Unreal");
+        assert_eq!(meta.name, "Shooter");
+        assert_eq!(meta.runtime, vec!["unreal 5.3"]);
+        assert_eq!(meta.dependencies, vec!["ModelingToolsEditorMode"]);
+
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("ProjectSettings")).unwrap();
+        fs::create_dir_all(dir.path().join("Packages")).unwrap();
+        fs::write(dir.path().join("ProjectSettings/ProjectVersion.txt"), "m_EditorVersion: 2022.3.10f1\n").unwrap();
+        fs::write(dir.path().join("Packages/manifest.json"), r#"{"dependencies":{"com.unity.inputsystem":"1.7.0"}}"#).unwrap();
+        let meta = extract_metadata(dir.path(), "Unity");
+        assert_eq!(meta.runtime, vec!["unity 2022.3.10f1"]);
+        assert_eq!(meta.requirements, vec!["com.unity.inputsystem@1.7.0"]);
+    }
+
+    #[test]
+    fn test_extract_terraform_meta() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("versions.tf"), r#"terraform {
+  required_version = ">= 1.5"
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = "~> 5.0"
+    }
+    random = { source = "hashicorp/random" }
+    google = "~> 4.0" # legacy shorthand
+  }
+}
+"#).unwrap();
+        fs::write(dir.path().join("main.tf"), r#"module "vpc" {
+  source  = "terraform-aws-modules/vpc/aws"
+  version = "5.1.0"
+}
+
+module "net" {
+  source = "./modules/net"
+}
+"#).unwrap();
+        fs::write(dir.path().join(".terraform.lock.hcl"), "provider \"registry.terraform.io/hashicorp/aws\" {\n  version     = \"5.31.0\"\n  constraints = \"~> 5.0\"\n}\n").unwrap();
+
+        let mut meta = extract_metadata(dir.path(), "Terraform");
+        assert_eq!(meta.runtime, vec!["terraform >= 1.5"]);
+        assert_eq!(meta.entry_point.as_deref(), Some("main.tf"));
+        assert_eq!(meta.requirements, vec![
+            "terraform-aws-modules/vpc/aws@5.1.0",
+            "./modules/net",
+            "hashicorp/aws@~> 5.0",
+            "hashicorp/random",
+            "hashicorp/google@~> 4.0",
+        ]);
+        resolve_locked_versions(dir.path(), &mut meta);
+        assert!(meta.requirements.contains(&"hashicorp/aws@~> 5.0 (locked 5.31.0)".to_string()));
+    }
+
+    #[test]
+    fn test_extract_pulumi_and_cdk_meta() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Pulumi.yaml"), "name: infra\ndescription: Shared VPC\nruntime:\n  name: python\n  options:\n    virtualenv: venv\n").unwrap();
+        let meta = extract_metadata(dir.path(), "Pulumi");
+        assert_eq!(meta.name, "infra");
+        assert_eq!(meta.description.as_deref(), Some("Shared VPC"));
+        assert_eq!(meta.runtime, vec!["pulumi python"]);
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("cdk.json"), r#"{"app": "npx ts-node --prefer-ts-exts bin/app.ts"}"#).unwrap();
+        let meta = extract_metadata(dir.path(), "AWS CDK");
+        assert_eq!(meta.entry_point.as_deref(), Some("bin/app.ts"));
+    }
+
+    #[test]
+    fn test_metadata_cache_reparses_changed_manifest() {
+        let dir = TempDir::new().unwrap();
+        let manifest = dir.path().join("package.json");
+        fs::write(&manifest, r#"{"name":"app","version":"1.0.0"}"#).unwrap();
+        let cache = MetadataCacheState::new();
+        let types = vec!["Node.js".to_string()];
+        assert_eq!(cache.get(dir.path(), &types).version.as_deref(), Some("1.0.0"));
+
+        // Same size and an older mtime would fool a check on either alone
+        fs::write(&manifest, r#"{"name":"app","version":"1.0.1"}"#).unwrap();
+        let file = fs::File::options().write(true).open(&manifest).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1)).unwrap();
+        assert_eq!(cache.get(dir.path(), &types).version.as_deref(), Some("1.0.1"));
+        assert_eq!(cache.get_for_pack(dir.path(), "Node.js").version.as_deref(), Some("1.0.1"));
+    }
+
+    #[test]
+    fn test_metadata_cache_hit_skips_extraction() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"t\"\n").unwrap();
+        let cache = MetadataCacheState::new();
+        let types = vec!["Rust".to_string()];
+        cache.get(dir.path(), &types);
+        // A cache hit returns the stored value as-is
+        if let Ok(mut entries) = cache.entries.lock() {
+            entries.values_mut().for_each(|e| e.metadata.name = "cached".to_string());
+        }
+        assert_eq!(cache.get(dir.path(), &types).name, "cached");
+        assert_eq!(cache.refresh(dir.path(), &types).name, "t");
+    }
+
+    #[test]
+    fn test_resolve_locked_versions() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("package-lock.json"), r#"{
+  "lockfileVersion": 3,
+  "packages": {
+    "": { "name": "app" },
+    "node_modules/react": { "version": "18.2.0" },
+    "node_modules/@types/node": { "version": "20.11.5" },
+    "node_modules/foo/node_modules/react": { "version": "16.0.0" }
+  }
+}"#).unwrap();
+        fs::write(dir.path().join("poetry.lock"), "[[package]]\nname = \"Flask_Login\"\nversion = \"0.6.3\"\n").unwrap();
+        fs::write(dir.path().join("go.sum"), "github.com/a/b v1.0.0 h1:x=\ngithub.com/a/b v1.0.0/go.mod h1:y=\ngithub.com/a/b v1.1.0 h1:z=\ngithub.com/c/d v1.10.0 h1:x=\ngithub.com/c/d v1.9.0 h1:y=\n").unwrap();
+
+        let mut meta = extract_metadata(dir.path(), "Unknown");
+        meta.requirements = vec![
+            "react@^18".to_string(),
+            "@types/node@^20".to_string(),
+            "flask-login>=0.6".to_string(),
+            "github.com/a/b@v1.1.0".to_string(),
+            "github.com/c/d@v1.9".to_string(),
+            "left-pad@1".to_string(),
+        ];
+        resolve_locked_versions(dir.path(), &mut meta);
+        assert_eq!(meta.requirements, vec![
+            "react@^18 (locked 18.2.0)",
+            "@types/node@^20 (locked 20.11.5)",
+            "flask-login>=0.6 (locked 0.6.3)",
+            "github.com/a/b@v1.1.0",
+            "github.com/c/d@v1.9 (locked v1.10.0)",
+            "left-pad@1",
+        ]);
+        assert!(go_version_key("v1.2.0-rc.1") < go_version_key("v1.2.0"));
+        assert!(go_version_key("v2.0.0+incompatible") > go_version_key("v1.10.0"));
+    }
+
     #[test]
     fn test_extract_metadata_python_pyproject() {
         let dir = TempDir::new().unwrap();
@@ -421,6 +2438,139 @@ dependencies = ["flask>=2.0", "requests"]
         assert_eq!(meta.entry_point, Some("main.py".to_string()));
     }
 
+    #[test]
+    fn test_extract_metadata_python_poetry() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pyproject.toml"), r#"
+[tool.poetry]
+name = "svc"
+version = "1.2.0"
+description = "Poetry service"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+fastapi = "^0.110"
+sqlalchemy = { version = "^2.0", extras = ["asyncio"] }
+
+[tool.poetry.group.dev.dependencies]
+pytest = "^8.0"
+"#).unwrap();
+
+        let meta = extract_metadata(dir.path(), "Python");
+        assert_eq!(meta.name, "svc");
+        assert_eq!(meta.version, Some("1.2.0".to_string()));
+        assert!(meta.runtime.iter().any(|r| r == "python ^3.11"));
+        assert!(meta.dependencies.contains(&"fastapi".to_string()));
+        assert!(!meta.dependencies.contains(&"python".to_string()));
+        assert!(meta.requirements.iter().any(|r| r == "sqlalchemy@^2.0"));
+        assert_eq!(meta.dev_dependencies, vec!["pytest"]);
+    }
+
+    #[test]
+    fn test_extract_metadata_python_uv_lock() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("uv.lock"), r#"
+version = 1
+requires-python = ">=3.12"
+
+[[package]]
+name = "app"
+version = "0.1.0"
+source = { virtual = "." }
+dependencies = [{ name = "httpx" }]
+
+[package.dev-dependencies]
+dev = [{ name = "ruff" }]
+
+[[package]]
+name = "httpx"
+version = "0.27.0"
+source = { registry = "https://pypi.org/simple" }
+"#).unwrap();
+
+        let meta = extract_metadata(dir.path(), "Python");
+        assert_eq!(meta.dependencies, vec!["httpx"]);
+        assert_eq!(meta.requirements, vec!["httpx==0.27.0"]);
+        assert_eq!(meta.dev_dependencies, vec!["ruff"]);
+        assert!(meta.runtime.iter().any(|r| r == "python >=3.12"));
+    }
+
+    #[test]
+    fn test_extract_metadata_python_pipfile() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Pipfile"), r#"
+[packages]
+django = "==5.0"
+requests = "*"
+
+[dev-packages]
+black = "*"
+
+[requires]
+python_version = "3.11"
+"#).unwrap();
+
+        let meta = extract_metadata(dir.path(), "Python");
+        assert_eq!(meta.dependencies.len(), 2);
+        assert!(meta.requirements.iter().any(|r| r == "django==5.0"));
+        assert!(meta.requirements.iter().any(|r| r == "requests"));
+        assert_eq!(meta.dev_dependencies, vec!["black"]);
+        assert!(meta.runtime.iter().any(|r| r == "python 3.11"));
+    }
+
+    #[test]
+    fn test_extract_metadata_conda_environment() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("environment.yml"), r#"name: ds-env
+channels:
+  - conda-forge
+dependencies:
+  - python=3.11
+  - numpy>=1.26
+  - conda-forge::pandas
+  - pip
+  - pip:
+    - torch==2.2.0
+    - -r requirements-extra.txt
+"#).unwrap();
+
+        let meta = extract_metadata(dir.path(), "Python");
+        assert_eq!(meta.dependencies, vec!["numpy", "pandas", "torch"]);
+        assert_eq!(meta.requirements, vec!["numpy>=1.26", "pandas", "torch==2.2.0"]);
+        assert!(meta.runtime.iter().any(|r| r == "python 3.11"));
+    }
+
+    #[test]
+    fn test_extract_metadata_ruby() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Gemfile"), r#"source "https://rubygems.org"
+ruby "3.2.2"
+
+gem "rails", "~> 7.1.0"
+gem 'pg', ">= 0.18", "< 2.0"
+gem "bootsnap", require: false
+gem "debug", group: :development
+
+group :development, :test do
+  gem "rspec-rails"
+end
+
+gem "puma"
+"#).unwrap();
+        fs::write(dir.path().join("Gemfile.lock"), "GEM\n  remote: https://rubygems.org/\n  specs:\n    rails (7.1.2)\n      actionpack (= 7.1.2)\n    puma (6.4.0)\n").unwrap();
+        fs::write(dir.path().join("config.ru"), "run Rails.application").unwrap();
+
+        let meta = extract_metadata(dir.path(), "Ruby");
+        assert_eq!(meta.dependencies, vec!["rails", "pg", "bootsnap", "puma"]);
+        assert_eq!(meta.dev_dependencies, vec!["debug", "rspec-rails"]);
+        assert!(meta.requirements.iter().any(|r| r == "rails@7.1.2"));
+        assert!(meta.requirements.iter().any(|r| r == "pg@>= 0.18, < 2.0"));
+        assert!(meta.requirements.iter().any(|r| r == "bootsnap@*"));
+        assert!(!meta.requirements.iter().any(|r| r.starts_with("actionpack")));
+        assert_eq!(meta.runtime, vec!["ruby 3.2.2"]);
+        assert_eq!(meta.entry_point, Some("config.ru".to_string()));
+    }
+
     #[test]
     fn test_extract_metadata_go() {
         let dir = TempDir::new().unwrap();
@@ -434,6 +2584,69 @@ dependencies = ["flask>=2.0", "requests"]
         assert_eq!(meta.entry_point, Some("main.go".to_string()));
     }
 
+    #[test]
+    fn test_extract_metadata_multi_groups_by_ecosystem() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"core\"\n\n[dependencies]\nserde = \"1\"\n").unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"name": "web", "version": "2.0.0", "dependencies": {"react": "^18"}}"#).unwrap();
+
+        let types = vec!["Rust".to_string(), "Vite".to_string(), "Node.js".to_string(), "Docker".to_string()];
+        let meta = extract_metadata_multi(dir.path(), &types);
+        assert_eq!(meta.name, "core");
+        assert_eq!(meta.project_type, "Rust");
+        // Cargo.toml has no version, so package.json fills it in
+        assert_eq!(meta.version, Some("2.0.0".to_string()));
+        assert_eq!(meta.dependencies, vec!["serde"]);
+        let ecosystems: Vec<&str> = meta.ecosystems.iter().map(|e| e.ecosystem.as_str()).collect();
+        assert_eq!(ecosystems, vec!["cargo", "npm"]);
+        assert_eq!(meta.ecosystems[1].dependencies, vec!["react"]);
+        assert!(meta.requirements.iter().any(|r| r == "react@^18"));
+    }
+
+    #[test]
+    fn test_extract_metadata_elixir() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("lib/shop")).unwrap();
+        fs::write(dir.path().join("lib/shop/application.ex"), "").unwrap();
+        fs::write(dir.path().join("mix.exs"), r#"
+defmodule Shop.MixProject do
+  use Mix.Project
+
+  @version "0.3.1"
+
+  def project do
+    [
+      app: :shop,
+      version: @version,
+      elixir: "~> 1.14",
+      description: "Phoenix storefront",
+      deps: deps()
+    ]
+  end
+
+  defp deps do
+    [
+      {:phoenix, "~> 1.7.10"},
+      {:ecto_sql, "~> 3.10"},
+      {:credo, "~> 1.7", only: [:dev, :test], runtime: false},
+      {:heroicons, github: "tailwindlabs/heroicons", sparse: "optimized"}
+    ]
+  end
+end
+"#).unwrap();
+
+        let meta = extract_metadata(dir.path(), "Elixir");
+        assert_eq!(meta.name, "shop");
+        assert_eq!(meta.version, Some("0.3.1".to_string()));
+        assert_eq!(meta.description, Some("Phoenix storefront".to_string()));
+        assert!(meta.runtime.iter().any(|r| r == "elixir ~> 1.14"));
+        assert_eq!(meta.dependencies, vec!["phoenix", "ecto_sql", "heroicons"]);
+        assert_eq!(meta.dev_dependencies, vec!["credo"]);
+        assert!(meta.requirements.iter().any(|r| r == "phoenix@~> 1.7.10"));
+        assert!(meta.requirements.iter().any(|r| r == "heroicons@*"));
+        assert_eq!(meta.entry_point, Some("lib/shop/application.ex".to_string()));
+    }
+
     #[test]
     fn test_extract_metadata_unknown_type() {
         let dir = TempDir::new().unwrap();
@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+
+use crate::metadata::extract_metadata;
+use crate::scanner::{count_files, detect_project_type, is_excluded_dir};
+use crate::types::{FileNode, MonorepoInfo, MonorepoPackage};
+
+/// Member globs rarely go deeper than `apps/group/name`
+const MAX_MEMBER_DEPTH: usize = 4;
+/// Nx's conventional layout when no package-manager workspaces are declared
+const NX_DEFAULT_PATTERNS: &[&str] = &["apps/*", "libs/*", "packages/*"];
+
+// ─── Workspace Config Parsing ──────────────────────────────────
+
+/// Reads the `packages:` list of pnpm-workspace.yaml without a full YAML parser
+fn pnpm_patterns(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') && !line.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                let item = item.trim().trim_matches(|c| c == '\'' || c == '"');
+                if !item.is_empty() {
+                    patterns.push(item.to_string());
+                }
+            }
+        }
+    }
+    Some(patterns)
+}
+
+/// `workspaces` in package.json is either an array or `{ "packages": [...] }` (yarn classic)
+fn package_json_patterns(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("package.json")).ok()?;
+    let pkg = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+    let workspaces = pkg.get("workspaces")?;
+    let list = workspaces
+        .as_array()
+        .or_else(|| workspaces.get("packages").and_then(|v| v.as_array()))?;
+    Some(
+        list.iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+    )
+}
+
+fn cargo_patterns(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("Cargo.toml")).ok()?;
+    let doc = content.parse::<toml::Table>().ok()?;
+    let workspace = doc.get("workspace")?.as_table()?;
+    let strings = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default()
+    };
+    let mut patterns = strings("members");
+    patterns.extend(strings("exclude").into_iter().map(|e| format!("!{}", e)));
+    Some(patterns)
+}
+
+// ─── Detection ─────────────────────────────────────────────────
+
+pub fn detect_monorepo(root: &Path) -> Option<MonorepoInfo> {
+    let js_patterns = pnpm_patterns(root).or_else(|| package_json_patterns(root));
+    let has_nx = root.join("nx.json").exists();
+
+    let (tool, patterns, manifests): (&str, Vec<String>, &[&str]) = if has_nx {
+        let patterns = js_patterns
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| NX_DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect());
+        ("nx", patterns, &["project.json", "package.json"])
+    } else if let Some(patterns) = js_patterns {
+        let tool = if root.join("turbo.json").exists() {
+            "turborepo"
+        } else if root.join("pnpm-workspace.yaml").exists() {
+            "pnpm"
+        } else if root.join("yarn.lock").exists() {
+            "yarn"
+        } else {
+            "npm"
+        };
+        (tool, patterns, &["package.json"])
+    } else if let Some(patterns) = cargo_patterns(root) {
+        ("cargo", patterns, &["Cargo.toml"])
+    } else {
+        return None;
+    };
+
+    let packages = member_dirs(root, &patterns, manifests)
+        .into_iter()
+        .map(|dir| describe_package(root, &dir))
+        .collect::<Vec<_>>();
+    if packages.is_empty() {
+        return None;
+    }
+    Some(MonorepoInfo {
+        tool: tool.to_string(),
+        packages,
+    })
+}
+
+/// Member crate directories of a Cargo workspace rooted at `root`, sorted by path
+pub fn cargo_member_dirs(root: &Path) -> Vec<PathBuf> {
+    cargo_patterns(root)
+        .map(|patterns| member_dirs(root, &patterns, &["Cargo.toml"]))
+        .unwrap_or_default()
+}
+
+/// Directories below the root that match the member globs and contain a manifest
+fn member_dirs(root: &Path, patterns: &[String], manifests: &[&str]) -> Vec<PathBuf> {
+    let mut builder = OverrideBuilder::new(root);
+    for pattern in patterns {
+        let _ = builder.add(pattern.trim_end_matches('/'));
+    }
+    let matcher = match builder.build() {
+        Ok(m) => m,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut walk_builder = WalkBuilder::new(root);
+    walk_builder
+        .max_depth(Some(MAX_MEMBER_DEPTH))
+        .git_global(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !is_excluded_dir(&entry.file_name().to_string_lossy(), &[])
+        });
+
+    walk_builder
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() > 0 && entry.file_type().is_some_and(|ft| ft.is_dir()))
+        .map(|entry| entry.into_path())
+        .filter(|dir| manifests.iter().any(|m| dir.join(m).is_file()))
+        .filter(|dir| matcher.matched(dir, true).is_whitelist())
+        .collect()
+}
+
+fn describe_package(root: &Path, dir: &Path) -> MonorepoPackage {
+    let project_type = detect_project_type(dir);
+    let metadata = extract_metadata(dir, &project_type);
+    let name = nx_project_name(dir).unwrap_or_else(|| metadata.name.clone());
+    MonorepoPackage {
+        name,
+        path: dir.to_string_lossy().to_string(),
+        relative_path: dir
+            .strip_prefix(root)
+            .unwrap_or(dir)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        project_type,
+        metadata,
+        file_count: 0,
+    }
+}
+
+fn nx_project_name(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("project.json")).ok()?;
+    let project = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+    project.get("name")?.as_str().map(|s| s.to_string())
+}
+
+// ─── Tree Grouping ─────────────────────────────────────────────
+
+/// Tags each package's directory node with the package name and fills in file counts
+pub fn tag_packages(tree: &mut FileNode, info: &mut MonorepoInfo) {
+    for package in &mut info.packages {
+        if let Some(node) = find_dir_mut(tree, &package.path) {
+            node.package = Some(package.name.clone());
+            package.file_count = count_files(node);
+        }
+    }
+}
+
+fn find_dir_mut<'a>(node: &'a mut FileNode, path: &str) -> Option<&'a mut FileNode> {
+    if node.path == path {
+        return Some(node);
+    }
+    node.children
+        .iter_mut()
+        .filter(|c| c.is_dir && path.starts_with(&c.path))
+        .find_map(|c| find_dir_mut(c, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::build_file_tree;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_pnpm_workspace() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "package.json", "{\"name\": \"root\"}");
+        write(dir.path(), "pnpm-workspace.yaml", "packages:\n  - 'packages/*'\n  - \"!packages/skip\"\n");
+        write(dir.path(), "packages/ui/package.json", "{\"name\": \"@acme/ui\"}");
+        write(dir.path(), "packages/ui/index.ts", "export {}");
+        write(dir.path(), "packages/skip/package.json", "{\"name\": \"skip\"}");
+        write(dir.path(), "docs/package.json", "{\"name\": \"docs\"}");
+
+        let mut info = detect_monorepo(dir.path()).unwrap();
+        assert_eq!(info.tool, "pnpm");
+        let names: Vec<&str> = info.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["@acme/ui"]);
+        assert_eq!(info.packages[0].relative_path, "packages/ui");
+
+        let mut tree = build_file_tree(dir.path(), &[], &[]);
+        tag_packages(&mut tree, &mut info);
+        assert_eq!(info.packages[0].file_count, 2);
+        let packages = tree.children.iter().find(|c| c.name == "packages").unwrap();
+        let ui = packages.children.iter().find(|c| c.name == "ui").unwrap();
+        assert_eq!(ui.package.as_deref(), Some("@acme/ui"));
+    }
+
+    #[test]
+    fn test_yarn_workspaces_object_form() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "package.json", "{\"workspaces\": {\"packages\": [\"apps/*\"]}}");
+        write(dir.path(), "yarn.lock", "");
+        write(dir.path(), "apps/web/package.json", "{\"name\": \"web\"}");
+
+        let info = detect_monorepo(dir.path()).unwrap();
+        assert_eq!(info.tool, "yarn");
+        assert_eq!(info.packages.len(), 1);
+    }
+
+    #[test]
+    fn test_cargo_workspace() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n");
+        write(dir.path(), "crates/core/Cargo.toml", "[package]\nname = \"acme-core\"\n");
+        write(dir.path(), "crates/old/Cargo.toml", "[package]\nname = \"old\"\n");
+
+        let info = detect_monorepo(dir.path()).unwrap();
+        assert_eq!(info.tool, "cargo");
+        let names: Vec<&str> = info.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["acme-core"]);
+        assert_eq!(info.packages[0].project_type, "Rust");
+    }
+
+    #[test]
+    fn test_nx_default_layout() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "nx.json", "{}");
+        write(dir.path(), "libs/auth/project.json", "{\"name\": \"auth\"}");
+
+        let info = detect_monorepo(dir.path()).unwrap();
+        assert_eq!(info.tool, "nx");
+        assert_eq!(info.packages[0].name, "auth");
+    }
+
+    #[test]
+    fn test_plain_project_is_not_monorepo() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "package.json", "{\"name\": \"app\"}");
+        assert!(detect_monorepo(dir.path()).is_none());
+    }
+}
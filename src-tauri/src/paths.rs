@@ -0,0 +1,158 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+// CodePack: Windows 长路径与 UNC 支持：内部使用 \\?\ 形式，返回前端前还原为普通路径
+
+const VERBATIM: &str = r"\\?\";
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+/// Classic Win32 MAX_PATH; shorter paths work without the verbatim prefix
+const MAX_PATH: usize = 260;
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7",
+    "com8", "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+fn is_drive_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\'
+}
+
+/// `C:\a` → `\\?\C:\a`, `\\server\share\a` → `\\?\UNC\server\share\a`. Relative paths,
+/// paths with `.` / `..` segments and already-verbatim paths come back as `None`
+fn to_verbatim(path: &str) -> Option<String> {
+    if path.starts_with(VERBATIM) || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let path = path.replace('/', "\\");
+    if path.split('\\').any(|part| part == "." || part == "..") {
+        return None;
+    }
+    if is_drive_absolute(&path) {
+        Some(format!("{}{}", VERBATIM, path))
+    } else {
+        path.strip_prefix(r"\\")
+            .filter(|rest| !rest.is_empty())
+            .map(|rest| format!("{}{}", VERBATIM_UNC, rest))
+    }
+}
+
+/// A component that Win32 would reinterpret (`NUL.txt`, a trailing dot or space...)
+fn is_unsafe_component(part: &str) -> bool {
+    if part.is_empty() || part == "." || part == ".." || part.ends_with('.') || part.ends_with(' ') {
+        return true;
+    }
+    let stem = part.split('.').next().unwrap_or(part).trim_end().to_lowercase();
+    RESERVED_NAMES.contains(&stem.as_str()) || part.contains(['/', '<', '>', '"', '|', '?', '*', ':'])
+}
+
+/// Dunce-style inverse of `to_verbatim`: strips the prefix only when the plain form
+/// means exactly the same file, otherwise `None`
+fn from_verbatim(path: &str) -> Option<String> {
+    let (plain, rest) = if let Some(rest) = path.strip_prefix(VERBATIM_UNC) {
+        (format!(r"\\{}", rest), rest)
+    } else {
+        let rest = path.strip_prefix(VERBATIM)?;
+        if !is_drive_absolute(rest) {
+            return None;
+        }
+        (rest.to_string(), &rest[3..])
+    };
+    if plain.len() >= MAX_PATH {
+        return None;
+    }
+    let parts: Vec<&str> = rest.split('\\').collect();
+    let last = parts.len() - 1;
+    // a trailing separator leaves one empty part, which is fine
+    let unsafe_part = parts
+        .iter()
+        .enumerate()
+        .any(|(i, part)| !(i == last && part.is_empty()) && is_unsafe_component(part));
+    (!unsafe_part).then_some(plain)
+}
+
+/// Form used for file-system access. On Windows this adds the `\\?\` prefix so deep
+/// trees past MAX_PATH and UNC shares work; elsewhere the path is returned unchanged
+pub fn extended(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        if let Some(verbatim) = path.to_str().and_then(to_verbatim) {
+            return PathBuf::from(verbatim);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// String form handed to the frontend: verbatim prefixes removed whenever that is lossless
+pub fn display(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    if cfg!(windows) {
+        if let Some(plain) = from_verbatim(&text) {
+            return plain;
+        }
+    }
+    text.to_string()
+}
+
+/// `fs::canonicalize` without the `\\?\` prefix Windows always puts on the result
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    let canonical = std::fs::canonicalize(extended(path))?;
+    Ok(PathBuf::from(display(&canonical)))
+}
+
+/// Whether `path` resolves to `root` or somewhere below it once `..` segments and
+/// symlinks are resolved; false when either side doesn't exist
+pub fn is_within(root: &Path, path: &Path) -> bool {
+    match (canonicalize(root), canonicalize(path)) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_verbatim() {
+        assert_eq!(to_verbatim(r"C:\src\app").as_deref(), Some(r"\\?\C:\src\app"));
+        assert_eq!(to_verbatim("C:/src/app").as_deref(), Some(r"\\?\C:\src\app"));
+        assert_eq!(to_verbatim(r"\\nas\share\repo").as_deref(), Some(r"\\?\UNC\nas\share\repo"));
+        assert_eq!(to_verbatim(r"\\?\C:\src"), None);
+        assert_eq!(to_verbatim(r"src\app"), None);
+        assert_eq!(to_verbatim(r"C:\src\..\app"), None);
+    }
+
+    #[test]
+    fn test_from_verbatim() {
+        assert_eq!(from_verbatim(r"\\?\C:\src\app").as_deref(), Some(r"C:\src\app"));
+        assert_eq!(from_verbatim(r"\\?\UNC\nas\share\repo").as_deref(), Some(r"\\nas\share\repo"));
+        assert_eq!(from_verbatim(r"\\?\C:\").as_deref(), Some(r"C:\"));
+        // Plain forms that would name a different file keep the prefix
+        assert_eq!(from_verbatim(r"\\?\C:\src\nul.txt"), None);
+        assert_eq!(from_verbatim(r"\\?\C:\src\trailing."), None);
+        assert_eq!(from_verbatim(r"\\?\GLOBALROOT\Device"), None);
+        let long = format!(r"\\?\C:\{}", "a".repeat(MAX_PATH));
+        assert_eq!(from_verbatim(&long), None);
+        assert_eq!(from_verbatim(r"C:\plain"), None);
+    }
+
+    #[test]
+    fn test_is_within() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("outside")).unwrap();
+        assert!(is_within(&root, &root));
+        assert!(is_within(&root, &root.join("src")));
+        assert!(!is_within(&root, &root.join("..").join("outside")));
+        assert!(!is_within(&root, &root.join("missing")));
+    }
+
+    #[test]
+    fn test_round_trip_is_identity_off_windows() {
+        let path = Path::new("/home/user/project");
+        if !cfg!(windows) {
+            assert_eq!(extended(path), path);
+            assert_eq!(display(path), "/home/user/project");
+        }
+    }
+}
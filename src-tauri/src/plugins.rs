@@ -1,8 +1,19 @@
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::scanner::{is_excluded_dir, validate_globs};
+use crate::types::{PluginRuleCheck, ProjectMetadata, ScanOptions, Severity};
+use crate::wasm::WasmMetadata;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PluginDef {
     pub name: String,
     #[serde(default)]
@@ -11,10 +22,64 @@ pub struct PluginDef {
     pub detect_files: Vec<String>,
     #[serde(default)]
     pub detect_dirs: Vec<String>,
+    /// Globs relative to the project root (`*.csproj`, `src/**/*.gleam`); each must match a file
+    #[serde(default)]
+    pub detect_globs: Vec<String>,
     #[serde(default)]
     pub exclude_dirs: Vec<String>,
+    /// Gitignore-style globs for generated or vendored files (`*.min.js`, `*.generated.ts`)
+    #[serde(default)]
+    pub exclude_files: Vec<String>,
     #[serde(default)]
     pub source_extensions: Vec<String>,
+    /// Detection for the ecosystem's own key formats, checked after the built-in secret rules
+    #[serde(default)]
+    pub secret_rules: Vec<PluginSecretRule>,
+    /// WASM module in the plugins directory providing `detect`, `metadata` or `secret_rules`
+    /// hooks; needs a build with the `wasm-plugins` feature
+    #[serde(default)]
+    pub wasm: Option<String>,
+    /// Lines appended to the pack header when the plugin matches; `{name}`, `{version}`,
+    /// `{frameworks}` and the other metadata placeholders are filled in by the packer
+    #[serde(default)]
+    pub header_template: Option<String>,
+    /// External programs run in the project root; each needs the user's approval per project
+    #[serde(default)]
+    pub commands: Vec<PluginCommand>,
+}
+
+// CodePack: 插件提供的密钥规则（PluginDef.secret_rules 或 WASM secret_rules 钩子）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginSecretRule {
+    pub description: String,
+    /// Regex; a `secret` capture group narrows the masked range like the built-in rules
+    pub pattern: String,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+// CodePack: 插件声明的外部命令，例如 `cargo metadata --format-version 1`。
+// 命令在项目根目录运行，stdout 须为 JSON（见 CommandOutput）；未经用户批准的命令不会执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginCommand {
+    pub stage: CommandStage,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Seconds before the process is killed; defaults to DEFAULT_COMMAND_TIMEOUT_SECS
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+// CodePack: 外部命令的运行时机
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommandStage {
+    /// After a scan; only the metadata part of the output is used
+    #[serde(rename = "post_scan")]
+    PostScan,
+    /// Before a pack is built; metadata and file annotations both go into the pack
+    #[serde(rename = "pre_pack")]
+    PrePack,
 }
 
 pub fn get_plugins_dir() -> PathBuf {
@@ -45,15 +110,238 @@ pub fn load_plugins() -> Vec<PluginDef> {
     plugins
 }
 
+/// File name of a plugin in the plugins directory; names that could point outside it are rejected
+pub fn plugin_file_name(name: &str) -> Result<String, String> {
+    let slug = name.to_lowercase().replace(' ', "-");
+    let unsafe_char = |c: char| c.is_control() || matches!(c, '/' | '\\' | ':' | '<' | '>' | '"' | '|' | '?' | '*');
+    if slug.is_empty() || slug.starts_with('.') || slug.contains("..") || slug.chars().any(unsafe_char) {
+        return Err(format!("Invalid plugin name '{}'", name));
+    }
+    Ok(slug + ".json")
+}
+
+/// Checks a plugin before it is written: its name, globs and secret rule patterns
+pub fn validate_plugin(plugin: &PluginDef) -> Result<(), String> {
+    plugin_file_name(&plugin.name)?;
+    validate_globs(Path::new("."), &plugin.detect_globs)?;
+    validate_globs(Path::new("."), &plugin.exclude_files)?;
+    for rule in &plugin.secret_rules {
+        regex::Regex::new(&rule.pattern)
+            .map_err(|e| format!("Invalid secret rule '{}': {}", rule.description, e))?;
+    }
+    Ok(())
+}
+
+/// Writes `plugin` to the plugins directory, replacing a plugin with the same name
+pub fn write_plugin(plugin: &PluginDef) -> Result<(), String> {
+    let filename = plugin_file_name(&plugin.name)?;
+    let dir = get_plugins_dir();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(plugin).map_err(|e| e.to_string())?;
+    fs::write(dir.join(filename), json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The plugin's WASM module; only a file name inside the plugins directory is accepted
+pub fn wasm_module_path(plugin: &PluginDef) -> Option<PathBuf> {
+    let file = plugin.wasm.as_deref()?.trim();
+    let name = Path::new(file).file_name().filter(|name| *name == file)?;
+    Some(get_plugins_dir().join(name))
+}
+
 pub fn plugin_matches(plugin: &PluginDef, root: &Path) -> bool {
-    let files_match = plugin.detect_files.is_empty()
-        || plugin.detect_files.iter().all(|f| root.join(f).exists());
-    let dirs_match = plugin.detect_dirs.is_empty()
-        || plugin.detect_dirs.iter().all(|d| root.join(d).is_dir());
+    plugin_evidence(plugin, root).is_some()
+}
+
+// CodePack: 插件匹配时返回命中的文件/目录（glob 规则返回第一个匹配的文件），不匹配时为 None
+pub fn plugin_evidence(plugin: &PluginDef, root: &Path) -> Option<Vec<String>> {
+    plugin_evidence_with(plugin, root, &GlobMatches::find(root, [plugin]))
+}
+
+/// `plugin_evidence` with the glob rules looked up in `globs`, shared by all plugins of one detection
+pub fn plugin_evidence_with(plugin: &PluginDef, root: &Path, globs: &GlobMatches) -> Option<Vec<String>> {
+    // A module that fails to run (or a build without the WASM runtime) leaves the static
+    // rules in charge; plugin_problems and check_plugin_rules report the error
+    if let Some(module) = wasm_module_path(plugin) {
+        if let Ok(Some(detection)) = crate::wasm::detect(&module, root) {
+            return detection.matches.then_some(detection.evidence);
+        }
+    }
     // At least one detect rule must be non-empty
-    (!plugin.detect_files.is_empty() || !plugin.detect_dirs.is_empty())
-        && files_match
-        && dirs_match
+    if plugin.detect_files.is_empty()
+        && plugin.detect_dirs.is_empty()
+        && plugin.detect_globs.is_empty()
+    {
+        return None;
+    }
+    if !static_rules_pass(plugin, root) {
+        return None;
+    }
+    let mut evidence: Vec<String> = plugin
+        .detect_files
+        .iter()
+        .chain(plugin.detect_dirs.iter())
+        .cloned()
+        .collect();
+    for glob in &plugin.detect_globs {
+        evidence.push(globs.first.get(glob)?.clone());
+    }
+    Some(evidence)
+}
+
+fn static_rules_pass(plugin: &PluginDef, root: &Path) -> bool {
+    plugin.detect_files.iter().all(|f| root.join(f).exists())
+        && plugin.detect_dirs.iter().all(|d| root.join(d).is_dir())
+}
+
+// CodePack: 逐条检查插件的识别规则，供 test_plugin 展示；WASM detect 钩子存在时由它决定是否匹配
+pub fn check_plugin_rules(plugin: &PluginDef, root: &Path) -> Vec<PluginRuleCheck> {
+    let check = |kind: &str, rule: &str, passed: bool| PluginRuleCheck {
+        kind: kind.to_string(),
+        rule: rule.to_string(),
+        passed,
+        matched: Vec::new(),
+        error: None,
+    };
+    let mut checks: Vec<PluginRuleCheck> = plugin
+        .detect_files
+        .iter()
+        .map(|f| check("file", f, root.join(f).exists()))
+        .chain(plugin.detect_dirs.iter().map(|d| check("dir", d, root.join(d).is_dir())))
+        .collect();
+    let globs = GlobMatches::find_globs(root, &plugin.detect_globs);
+    for glob in &plugin.detect_globs {
+        let mut result = check("glob", glob, false);
+        match OverrideBuilder::new(root).add(glob) {
+            Ok(_) => {
+                result.matched.extend(globs.first.get(glob).cloned());
+                result.passed = !result.matched.is_empty();
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        checks.push(result);
+    }
+    if let Some(module) = wasm_module_path(plugin) {
+        let mut result = check("wasm", &crate::paths::display(&module), false);
+        match crate::wasm::detect(&module, root) {
+            Ok(Some(detection)) => {
+                result.passed = detection.matches;
+                result.matched = detection.evidence;
+            }
+            Ok(None) => result.error = Some("module has no detect hook".to_string()),
+            Err(e) => result.error = Some(e),
+        }
+        checks.push(result);
+    }
+    checks
+}
+
+/// Authoring mistakes that don't depend on the directory being tested
+pub fn plugin_problems(plugin: &PluginDef) -> Vec<String> {
+    let mut problems = Vec::new();
+    if plugin.name.trim().is_empty() {
+        problems.push("Plugin name is empty".to_string());
+    }
+    if plugin.detect_files.is_empty()
+        && plugin.detect_dirs.is_empty()
+        && plugin.detect_globs.is_empty()
+        && plugin.wasm.is_none()
+    {
+        problems.push("No detect rules: the plugin never matches".to_string());
+    }
+    for glob in plugin.detect_globs.iter().chain(plugin.exclude_files.iter()) {
+        if let Err(e) = OverrideBuilder::new(Path::new(".")).add(glob) {
+            problems.push(format!("Invalid glob '{}': {}", glob, e));
+        }
+    }
+    if plugin.commands.iter().any(|c| c.program.trim().is_empty()) {
+        problems.push("Command without a program".to_string());
+    }
+    for rule in &plugin.secret_rules {
+        if let Err(e) = regex::Regex::new(&rule.pattern) {
+            problems.push(format!("Invalid secret rule '{}': {}", rule.description, e));
+        }
+    }
+    if let Some(file) = plugin.wasm.as_deref() {
+        match wasm_module_path(plugin) {
+            Some(module) if !module.is_file() => {
+                problems.push(format!("WASM module not found: {}", crate::paths::display(&module)))
+            }
+            Some(module) => problems.extend(crate::wasm::check_module(&module).err()),
+            None => problems.push(format!(
+                "WASM module must be a file name in the plugins directory: {}",
+                file
+            )),
+        }
+    }
+    problems
+}
+
+// CodePack: 一次遍历求出多条 detect_globs 的首个匹配文件，全部命中即提前结束；
+// 未命中的 glob 也只与其他 glob 共享这一次遍历
+pub struct GlobMatches {
+    first: HashMap<String, String>,
+}
+
+impl GlobMatches {
+    /// Globs of the `plugins` whose file and directory rules already pass
+    pub fn find<'a>(root: &Path, plugins: impl IntoIterator<Item = &'a PluginDef>) -> Self {
+        let globs: Vec<String> = plugins
+            .into_iter()
+            .filter(|plugin| !plugin.detect_globs.is_empty() && static_rules_pass(plugin, root))
+            .flat_map(|plugin| plugin.detect_globs.iter().cloned())
+            .collect();
+        Self::find_globs(root, &globs)
+    }
+
+    /// Ignored files and the built-in excluded directories are not searched
+    pub fn find_globs(root: &Path, globs: &[String]) -> Self {
+        let mut first = HashMap::new();
+        let mut combined = OverrideBuilder::new(root);
+        let mut pending: Vec<(&String, Override)> = Vec::new();
+        for glob in globs {
+            if pending.iter().any(|(seen, _)| *seen == glob) {
+                continue;
+            }
+            let mut single = OverrideBuilder::new(root);
+            let Ok(matcher) = single.add(glob).and_then(|builder| builder.build()) else { continue };
+            if combined.add(glob).is_ok() {
+                pending.push((glob, matcher));
+            }
+        }
+        if pending.is_empty() {
+            return Self { first };
+        }
+        let Ok(overrides) = combined.build() else { return Self { first } };
+        // The combined whitelist already hides files no glob matches
+        let walker = WalkBuilder::new(root)
+            .overrides(overrides)
+            .filter_entry(|entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                entry.depth() == 0
+                    || !is_dir
+                    || !is_excluded_dir(&entry.file_name().to_string_lossy(), &[])
+            })
+            .build();
+        for entry in walker.filter_map(Result::ok) {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(root) else { continue };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            pending.retain(|(glob, matcher)| {
+                let matched = matcher.matched(entry.path(), false).is_whitelist();
+                if matched {
+                    first.insert((*glob).clone(), relative.clone());
+                }
+                !matched
+            });
+            if pending.is_empty() {
+                break;
+            }
+        }
+        Self { first }
+    }
 }
 
 // CodePack: 收集所有插件的额外排除目录
@@ -64,6 +352,64 @@ pub fn get_plugin_excluded_dirs(plugins: &[PluginDef]) -> Vec<String> {
         .collect()
 }
 
+// CodePack: 收集所有插件的文件排除 glob
+pub fn get_plugin_excluded_files(plugins: &[PluginDef]) -> Vec<String> {
+    plugins
+        .iter()
+        .flat_map(|p| p.exclude_files.iter().cloned())
+        .collect()
+}
+
+// CodePack: 收集与项目匹配的插件提供的头部模板
+pub fn get_plugin_header_templates(plugins: &[PluginDef], root: &Path) -> Vec<String> {
+    let with_template: Vec<&PluginDef> = plugins
+        .iter()
+        .filter(|p| p.header_template.as_ref().is_some_and(|t| !t.trim().is_empty()))
+        .collect();
+    let globs = GlobMatches::find(root, with_template.iter().copied());
+    with_template
+        .into_iter()
+        .filter(|p| plugin_evidence_with(p, root, &globs).is_some())
+        .filter_map(|p| p.header_template.clone())
+        .collect()
+}
+
+// CodePack: 已识别为该插件类型的项目，用插件 WASM 模块的 metadata 钩子补充元数据；返回运行失败的插件及原因
+pub fn apply_wasm_metadata(
+    plugins: &[PluginDef],
+    root: &Path,
+    project_types: &[String],
+    meta: &mut ProjectMetadata,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for plugin in plugins.iter().filter(|p| project_types.contains(&p.name)) {
+        let Some(module) = wasm_module_path(plugin) else { continue };
+        match crate::wasm::metadata(&module, root) {
+            Ok(Some(extra)) => extra.apply(meta),
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{}: {}", plugin.name, e)),
+        }
+    }
+    errors
+}
+
+// CodePack: 收集所有插件的密钥规则（附插件名）：JSON 中声明的规则加上 WASM 钩子返回的规则（模块只能读取插件目录）；
+// 钩子失败的插件只保留 JSON 规则，错误一并返回
+pub fn plugin_secret_rules(plugins: &[PluginDef]) -> (Vec<(String, PluginSecretRule)>, Vec<String>) {
+    let dir = get_plugins_dir();
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for plugin in plugins {
+        rules.extend(plugin.secret_rules.iter().map(|rule| (plugin.name.clone(), rule.clone())));
+        let Some(module) = wasm_module_path(plugin) else { continue };
+        match crate::wasm::secret_rules(&module, &dir) {
+            Ok(extra) => rules.extend(extra.into_iter().map(|rule| (plugin.name.clone(), rule))),
+            Err(e) => errors.push(format!("Plugin {}: {}", plugin.name, e)),
+        }
+    }
+    (rules, errors)
+}
+
 // CodePack: 收集所有插件的额外源码扩展名
 pub fn get_plugin_source_extensions(plugins: &[PluginDef]) -> Vec<String> {
     plugins
@@ -72,6 +418,280 @@ pub fn get_plugin_source_extensions(plugins: &[PluginDef]) -> Vec<String> {
         .collect()
 }
 
+/// Merges plugin-provided rules into the per-scan options
+pub fn apply_plugin_rules(mut options: ScanOptions, plugins: &[PluginDef]) -> ScanOptions {
+    let mut extra_excludes = get_plugin_excluded_dirs(plugins);
+    extra_excludes.append(&mut options.extra_excludes);
+    options.extra_excludes = extra_excludes;
+    options.exclude_globs.extend(get_plugin_excluded_files(plugins));
+    options.extra_extensions.extend(get_plugin_source_extensions(plugins));
+    options
+}
+
+/// The plugins named in a project's enabled list; None keeps every installed plugin
+pub fn enabled_plugins(plugins: Vec<PluginDef>, enabled: Option<&[String]>) -> Vec<PluginDef> {
+    match enabled {
+        Some(names) => plugins.into_iter().filter(|p| names.contains(&p.name)).collect(),
+        None => plugins,
+    }
+}
+
+// ─── External Commands ─────────────────────────────────────────
+
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 30;
+/// `cargo metadata` on a large workspace runs to several megabytes
+const MAX_COMMAND_OUTPUT: u64 = 32 * 1024 * 1024;
+
+// CodePack: 外部命令的 stdout；metadata 只补充缺失字段，annotations 以相对路径为键附加到文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandOutput {
+    #[serde(default)]
+    pub metadata: WasmMetadata,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+    /// Commands that failed, as `plugin: command: error`; they add nothing to the output
+    #[serde(skip)]
+    pub errors: Vec<String>,
+}
+
+/// The file a program given by path runs, relative to the project root; None for bare names found on PATH
+fn program_file(command: &PluginCommand, root: &Path) -> Option<PathBuf> {
+    let program = Path::new(&command.program);
+    (program.is_absolute() || program.components().count() > 1).then(|| root.join(program))
+}
+
+/// Identifies a command for approval in the project at `root`; editing the program, its arguments
+/// or the script a program path points to requires a new one
+pub fn command_fingerprint(plugin: &str, command: &PluginCommand, root: &Path) -> String {
+    let mut hasher = Sha256::new();
+    for part in [plugin, command.program.as_str()].into_iter().chain(command.args.iter().map(String::as_str)) {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    if let Some(content) = program_file(command, root).and_then(|path| fs::read(path).ok()) {
+        hasher.update(&content);
+    }
+    hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `program arg1 arg2` as shown in the approval prompt
+pub fn command_line(command: &PluginCommand) -> String {
+    std::iter::once(command.program.as_str())
+        .chain(command.args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// CodePack: 运行匹配项目的插件在 stage 阶段的已批准命令，合并输出；单个命令失败不影响其他命令，错误收集在 errors 中
+pub fn run_plugin_commands(
+    plugins: &[PluginDef],
+    root: &Path,
+    stage: CommandStage,
+    approved: &[String],
+) -> CommandOutput {
+    let mut merged = CommandOutput::default();
+    let with_commands: Vec<(&PluginDef, Vec<&PluginCommand>)> = plugins
+        .iter()
+        .map(|plugin| {
+            let commands: Vec<&PluginCommand> = plugin
+                .commands
+                .iter()
+                .filter(|c| c.stage == stage && approved.contains(&command_fingerprint(&plugin.name, c, root)))
+                .collect();
+            (plugin, commands)
+        })
+        .filter(|(_, commands)| !commands.is_empty())
+        .collect();
+    let globs = GlobMatches::find(root, with_commands.iter().map(|(plugin, _)| *plugin));
+    for (plugin, commands) in with_commands {
+        if plugin_evidence_with(plugin, root, &globs).is_none() {
+            continue;
+        }
+        for command in commands {
+            match run_command(command, root) {
+                Ok(output) => {
+                    let CommandOutput { metadata, annotations, .. } = output;
+                    merge_metadata(&mut merged.metadata, metadata);
+                    for (path, note) in annotations {
+                        let slot = merged.annotations.entry(path).or_default();
+                        if !slot.is_empty() {
+                            slot.push_str("; ");
+                        }
+                        slot.push_str(&note);
+                    }
+                }
+                Err(e) => merged.errors.push(format!("{}: {}: {}", plugin.name, command_line(command), e)),
+            }
+        }
+    }
+    merged
+}
+
+/// Earlier commands win for single values; lists are appended without duplicates
+fn merge_metadata(target: &mut WasmMetadata, extra: WasmMetadata) {
+    target.version = target.version.take().or(extra.version);
+    target.description = target.description.take().or(extra.description);
+    target.license = target.license.take().or(extra.license);
+    target.entry_point = target.entry_point.take().or(extra.entry_point);
+    for (list, items) in [
+        (&mut target.frameworks, extra.frameworks),
+        (&mut target.runtime, extra.runtime),
+        (&mut target.dependencies, extra.dependencies),
+        (&mut target.dev_dependencies, extra.dev_dependencies),
+    ] {
+        for item in items {
+            if !list.contains(&item) {
+                list.push(item);
+            }
+        }
+    }
+}
+
+fn run_command(command: &PluginCommand, root: &Path) -> Result<CommandOutput, String> {
+    let mut child = Command::new(&command.program)
+        .args(&command.args)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start command: {}", e))?;
+    // Read on another thread so a large output can't fill the pipe and stall the child
+    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.take(MAX_COMMAND_OUTPUT + 1).read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let timeout = Duration::from_secs(command.timeout_secs.unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS));
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait().map_err(|e| format!("Failed to wait for command: {}", e))? {
+            Some(status) => break status,
+            None if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Timed out after {}s", timeout.as_secs()));
+            }
+            None => std::thread::sleep(Duration::from_millis(50)),
+        }
+    };
+    let output = reader
+        .join()
+        .map_err(|_| "Failed to read command output".to_string())?
+        .map_err(|e| format!("Failed to read command output: {}", e))?;
+    if !status.success() {
+        return Err(format!("Exited with {}", status));
+    }
+    if output.len() as u64 > MAX_COMMAND_OUTPUT {
+        return Err("Output too large".to_string());
+    }
+    serde_json::from_slice(&output).map_err(|e| format!("Failed to parse command output: {}", e))
+}
+
+// ─── Registry ──────────────────────────────────────────────────
+
+/// Registry index and plugin files larger than this are rejected
+const MAX_REGISTRY_BYTES: usize = 1024 * 1024;
+
+// CodePack: 社区插件索引中的一项；url 可以相对索引地址，sha256 为插件 JSON 文件的十六进制摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginRegistry {
+    pub plugins: Vec<RegistryEntry>,
+}
+
+fn parse_https_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| format!("Invalid registry URL '{}': {}", url, e))?;
+    if parsed.scheme() != "https" {
+        return Err(format!("Registry URL must use https: {}", url));
+    }
+    Ok(parsed)
+}
+
+async fn download(url: &reqwest::Url) -> Result<Vec<u8>, String> {
+    let too_large = || format!("{} is larger than {}KB", url, MAX_REGISTRY_BYTES / 1024);
+    let mut response = reqwest::get(url.clone())
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {}: HTTP {}", url, response.status()));
+    }
+    if response.content_length().is_some_and(|len| len > MAX_REGISTRY_BYTES as u64) {
+        return Err(too_large());
+    }
+    // The length header is optional, so the body is capped while it streams in
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?
+    {
+        if bytes.len() + chunk.len() > MAX_REGISTRY_BYTES {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+pub async fn fetch_registry(url: &str) -> Result<PluginRegistry, String> {
+    let bytes = download(&parse_https_url(url)?).await?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse plugin registry: {}", e))
+}
+
+/// Lowercase hex SHA-256 of `bytes`
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// CodePack: 下载插件 JSON，校验 sha256 后解析；摘要不符时拒绝安装
+pub async fn download_plugin(registry_url: &str, entry: &RegistryEntry) -> Result<PluginDef, String> {
+    let base = parse_https_url(registry_url)?;
+    let url = base
+        .join(entry.url.trim())
+        .map_err(|e| format!("Invalid plugin URL '{}': {}", entry.url, e))?;
+    if url.scheme() != "https" {
+        return Err(format!("Plugin URL must use https: {}", url));
+    }
+    let bytes = download(&url).await?;
+    verify_checksum(&bytes, &entry.sha256)?;
+    let plugin: PluginDef = serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Failed to parse plugin '{}': {}", entry.name, e))?;
+    check_registry_name(entry, &plugin)?;
+    Ok(plugin)
+}
+
+/// The downloaded plugin must be the one the index listed; its name also picks the file it is saved to
+fn check_registry_name(entry: &RegistryEntry, plugin: &PluginDef) -> Result<(), String> {
+    if plugin.name != entry.name {
+        return Err(format!(
+            "Plugin '{}' from the registry is named '{}' instead",
+            entry.name, plugin.name
+        ));
+    }
+    plugin_file_name(&plugin.name).map(|_| ())
+}
+
+fn verify_checksum(bytes: &[u8], expected: &str) -> Result<(), String> {
+    let actual = sha256_hex(bytes);
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!("Checksum mismatch: expected {}, got {}", expected.trim(), actual));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,10 +705,22 @@ mod tests {
             detect_files: files.into_iter().map(|s| s.to_string()).collect(),
             detect_dirs: dirs.into_iter().map(|s| s.to_string()).collect(),
             exclude_dirs: vec!["custom_out".to_string()],
+            exclude_files: vec!["*.min.js".to_string()],
             source_extensions: vec!["xyz".to_string()],
+            ..Default::default()
         }
     }
 
+    #[test]
+    fn test_enabled_plugins() {
+        let plugins = || vec![make_plugin("Alpha", vec![], vec![]), make_plugin("Beta", vec![], vec![])];
+        let names = |plugins: Vec<PluginDef>| plugins.into_iter().map(|p| p.name).collect::<Vec<_>>();
+        assert_eq!(names(enabled_plugins(plugins(), None)), vec!["Alpha", "Beta"]);
+        assert_eq!(names(enabled_plugins(plugins(), Some(&["Beta".to_string()]))), vec!["Beta"]);
+        assert!(enabled_plugins(plugins(), Some(&[])).is_empty());
+        assert!(enabled_plugins(plugins(), Some(&["Gamma".to_string()])).is_empty());
+    }
+
     #[test]
     fn test_plugin_matches_by_file() {
         let dir = TempDir::new().unwrap();
@@ -105,6 +737,52 @@ mod tests {
         assert!(plugin_matches(&plugin, dir.path()));
     }
 
+    #[test]
+    fn test_plugin_matches_by_glob() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/app")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("src/app/main.gleam"), "").unwrap();
+        fs::write(dir.path().join("node_modules/pkg/Web.csproj"), "").unwrap();
+
+        let mut plugin = make_plugin("Gleam", vec![], vec![]);
+        plugin.detect_globs = vec!["src/**/*.gleam".to_string()];
+        assert_eq!(plugin_evidence(&plugin, dir.path()), Some(vec!["src/app/main.gleam".to_string()]));
+
+        // Matches inside excluded directories don't count
+        plugin.detect_globs = vec!["*.csproj".to_string()];
+        assert!(!plugin_matches(&plugin, dir.path()));
+
+        fs::write(dir.path().join("Api.csproj"), "").unwrap();
+        assert!(plugin_matches(&plugin, dir.path()));
+    }
+
+    #[test]
+    fn test_glob_matches_in_one_walk() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.gleam"), "").unwrap();
+        fs::write(dir.path().join("Api.csproj"), "").unwrap();
+
+        let mut gleam = make_plugin("Gleam", vec![], vec![]);
+        gleam.detect_globs = vec!["src/**/*.gleam".to_string(), "*.nope".to_string()];
+        let mut dotnet = make_plugin("Dotnet", vec![], vec![]);
+        dotnet.detect_globs = vec!["*.csproj".to_string()];
+        // Static rules that fail keep the plugin's globs out of the walk
+        let mut skipped = make_plugin("Skipped", vec!["missing.toml"], vec![]);
+        skipped.detect_globs = vec!["*.gleam".to_string()];
+
+        let plugins = [gleam, dotnet, skipped];
+        let globs = GlobMatches::find(dir.path(), &plugins);
+        assert_eq!(globs.first.len(), 2);
+        assert_eq!(globs.first["*.csproj"], "Api.csproj");
+        assert_eq!(plugin_evidence_with(&plugins[0], dir.path(), &globs), None);
+        assert_eq!(
+            plugin_evidence_with(&plugins[1], dir.path(), &globs),
+            Some(vec!["Api.csproj".to_string()])
+        );
+    }
+
     #[test]
     fn test_plugin_no_match() {
         let dir = TempDir::new().unwrap();
@@ -130,6 +808,188 @@ mod tests {
         assert!(excludes.iter().all(|e| e == "custom_out"));
     }
 
+    #[test]
+    fn test_check_plugin_rules() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("special.config"), "").unwrap();
+        let mut plugin = make_plugin("Special", vec!["special.config", "other.config"], vec!["gen"]);
+        plugin.detect_globs = vec!["*.config".to_string(), "[".to_string()];
+        let checks = check_plugin_rules(&plugin, dir.path());
+        let passed: Vec<(&str, bool)> = checks.iter().map(|c| (c.rule.as_str(), c.passed)).collect();
+        assert_eq!(
+            passed,
+            vec![("special.config", true), ("other.config", false), ("gen", false), ("*.config", true), ("[", false)]
+        );
+        assert_eq!(checks[3].matched, vec!["special.config"]);
+        assert!(checks[4].error.is_some());
+
+        let problems = plugin_problems(&plugin);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Invalid glob '['"));
+        assert_eq!(plugin_problems(&make_plugin("", vec![], vec![])).len(), 2);
+    }
+
+    #[test]
+    fn test_plugin_secret_rules() {
+        let mut plugin = make_plugin("Supabase", vec![], vec![]);
+        plugin.secret_rules = serde_json::from_str(
+            "[{\"description\": \"Supabase service key\", \"pattern\": \"sbp_[a-f0-9]{40}\", \"severity\": \"critical\"}, {\"description\": \"Broken\", \"pattern\": \"(\"}]",
+        )
+        .unwrap();
+        assert_eq!(plugin.secret_rules[0].severity, Severity::Critical);
+        assert_eq!(plugin.secret_rules[1].severity, Severity::Medium);
+
+        let (rules, errors) = plugin_secret_rules(&[plugin.clone()]);
+        assert_eq!(rules.len(), 2);
+        assert!(errors.is_empty());
+        assert!(rules.iter().all(|(name, _)| name == "Supabase"));
+        let problems = plugin_problems(&plugin);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[1].starts_with("Invalid secret rule 'Broken'"));
+
+        let errors = crate::security::set_plugin_rules(rules);
+        assert_eq!(errors.len(), 1);
+        let line = format!("sbp_{}", "a1".repeat(20));
+        let found = |matches: Vec<crate::types::SecretMatch>| {
+            matches.iter().any(|m| m.description == "Supabase service key" && m.severity == Severity::Critical)
+        };
+        assert!(found(crate::security::scan_content(&line)));
+        assert!(found(crate::security::scan_content_for(&line, Some(&["Supabase".to_string()]))));
+        // Projects that don't enable the plugin don't get its rules
+        assert!(!found(crate::security::scan_content_for(&line, Some(&[]))));
+    }
+
+    #[test]
+    fn test_command_fingerprint() {
+        let command = PluginCommand {
+            stage: CommandStage::PrePack,
+            program: "npm".to_string(),
+            args: vec!["ls".to_string(), "--json".to_string()],
+            timeout_secs: None,
+        };
+        let dir = TempDir::new().unwrap();
+        let fingerprint = command_fingerprint("Node", &command, dir.path());
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(command_line(&command), "npm ls --json");
+        let mut changed = command.clone();
+        changed.args.push("--all".to_string());
+        assert_ne!(command_fingerprint("Node", &changed, dir.path()), fingerprint);
+        assert_ne!(command_fingerprint("Other", &command, dir.path()), fingerprint);
+
+        // A script run by path is part of what was approved
+        let script = PluginCommand { program: "./tool.sh".to_string(), args: Vec::new(), ..command };
+        fs::write(dir.path().join("tool.sh"), "echo '{}'\n").unwrap();
+        let approved = command_fingerprint("Node", &script, dir.path());
+        assert_eq!(command_fingerprint("Node", &script, dir.path()), approved);
+        fs::write(dir.path().join("tool.sh"), "echo changed\n").unwrap();
+        assert_ne!(command_fingerprint("Node", &script, dir.path()), approved);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_plugin_commands() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        let mut plugin = make_plugin("Rust", vec!["Cargo.toml"], vec![]);
+        let command = PluginCommand {
+            stage: CommandStage::PrePack,
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"echo '{"metadata": {"dependencies": ["serde"]}, "annotations": {"src/lib.rs": "crate root"}}'"#.to_string(),
+            ],
+            timeout_secs: None,
+        };
+        plugin.commands = vec![command.clone()];
+        let approved = vec![command_fingerprint("Rust", &command, dir.path())];
+
+        // Unapproved commands and other stages never run
+        assert!(run_plugin_commands(&[plugin.clone()], dir.path(), CommandStage::PrePack, &[]).annotations.is_empty());
+        assert!(run_plugin_commands(&[plugin.clone()], dir.path(), CommandStage::PostScan, &approved).annotations.is_empty());
+
+        let output = run_plugin_commands(&[plugin.clone()], dir.path(), CommandStage::PrePack, &approved);
+        assert_eq!(output.metadata.dependencies, vec!["serde".to_string()]);
+        assert_eq!(output.annotations["src/lib.rs"], "crate root");
+
+        assert!(output.errors.is_empty());
+
+        plugin.commands[0].args[1] = "sleep 5".to_string();
+        plugin.commands[0].timeout_secs = Some(0);
+        assert!(run_command(&plugin.commands[0], dir.path()).unwrap_err().starts_with("Timed out"));
+        let approved = vec![command_fingerprint("Rust", &plugin.commands[0], dir.path())];
+        let output = run_plugin_commands(&[plugin], dir.path(), CommandStage::PrePack, &approved);
+        assert_eq!(output.errors.len(), 1);
+        assert!(output.errors[0].starts_with("Rust: sh -c sleep 5: Timed out"), "{}", output.errors[0]);
+    }
+
+    #[test]
+    fn test_plugin_file_name() {
+        assert_eq!(plugin_file_name("Spring Boot").unwrap(), "spring-boot.json");
+        assert_eq!(plugin_file_name("C++").unwrap(), "c++.json");
+        for name in ["", "../../x", "a/b", "a\\b", ".hidden", "C:evil", "a..b"] {
+            assert!(plugin_file_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_get_plugin_excluded_files() {
+        let plugins = vec![make_plugin("A", vec![], vec![])];
+        assert_eq!(get_plugin_excluded_files(&plugins), vec!["*.min.js".to_string()]);
+    }
+
+    #[test]
+    fn test_scan_drops_plugin_excluded_files() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("app.js"), "").unwrap();
+        fs::write(dir.path().join("app.min.js"), "").unwrap();
+        let options = apply_plugin_rules(ScanOptions::default(), &[make_plugin("A", vec![], vec![])]);
+        let tree = crate::scanner::build_file_tree_with_options(dir.path(), &options);
+        let files = crate::scanner::collect_file_paths(&tree);
+        assert!(files.iter().any(|f| f.ends_with("/app.js")));
+        assert!(!files.iter().any(|f| f.ends_with("app.min.js")));
+    }
+
+    #[test]
+    fn test_get_plugin_header_templates() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("manifest.json"), "{}").unwrap();
+        let mut matching = make_plugin("HA", vec!["manifest.json"], vec![]);
+        matching.header_template = Some("This is a Home Assistant integration: {name}".to_string());
+        let mut other = make_plugin("Other", vec!["other.toml"], vec![]);
+        other.header_template = Some("Never shown".to_string());
+        let templates = get_plugin_header_templates(&[matching, other], dir.path());
+        assert_eq!(templates, vec!["This is a Home Assistant integration: {name}".to_string()]);
+    }
+
+    #[test]
+    fn test_registry_checksum_and_urls() {
+        let digest = sha256_hex(b"abc");
+        assert_eq!(digest, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert!(verify_checksum(b"abc", &digest.to_uppercase()).is_ok());
+        assert!(verify_checksum(b"abd", &digest).is_err());
+
+        assert!(parse_https_url("https://example.com/plugins/index.json").is_ok());
+        assert!(parse_https_url("http://example.com/plugins/index.json").is_err());
+        let base = parse_https_url("https://example.com/plugins/index.json").unwrap();
+        assert_eq!(base.join("gleam.json").unwrap().as_str(), "https://example.com/plugins/gleam.json");
+    }
+
+    #[test]
+    fn test_check_registry_name() {
+        let entry = |name: &str| RegistryEntry {
+            name: name.to_string(),
+            version: String::new(),
+            description: String::new(),
+            url: "gleam.json".to_string(),
+            sha256: String::new(),
+        };
+        let plugin = make_plugin("Gleam", vec![], vec![]);
+        assert!(check_registry_name(&entry("Gleam"), &plugin).is_ok());
+        assert!(check_registry_name(&entry("Other"), &plugin).is_err());
+        let traversal = make_plugin("../../settings", vec![], vec![]);
+        assert!(check_registry_name(&entry("../../settings"), &traversal).is_err());
+    }
+
     #[test]
     fn test_get_plugin_source_extensions() {
         let plugins = vec![make_plugin("A", vec![], vec![])];
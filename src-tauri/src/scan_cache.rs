@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::types::{ScanOptions, ScanResult};
+
+// CodePack: 磁盘上的扫描结果缓存，打开大型项目时先返回上次结果
+
+#[derive(Serialize, Deserialize)]
+struct CachedScanFile {
+    root: String,
+    fingerprint: u64,
+    result: ScanResult,
+}
+
+fn cache_dir() -> PathBuf {
+    let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("codepack_scan_cache")
+}
+
+/// First 8 bytes of the digest; unlike DefaultHasher this stays the same across Rust releases,
+/// so cache files written by one build are found by the next
+fn finish(hasher: Sha256) -> u64 {
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes"))
+}
+
+fn cache_file(dir: &Path, root: &str) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(root.as_bytes());
+    dir.join(format!("{:016x}.json", finish(hasher)))
+}
+
+/// Hash of the scan options plus name / mtime / size of the root's direct entries.
+/// Cheap to compute; a deep change that leaves the top level untouched is caught by
+/// the background refresh instead
+pub fn fingerprint(root: &Path, options: &ScanOptions) -> Option<u64> {
+    let mut hasher = Sha256::new();
+    let header = [
+        env!("CARGO_PKG_VERSION").to_string(),
+        root.to_string_lossy().to_string(),
+        serde_json::to_string(options).ok()?,
+    ];
+    for part in header {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+
+    let mut entries: Vec<(String, u128, u64)> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .map(|entry| {
+            let meta = entry.metadata().ok();
+            let mtime = meta
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            let len = meta.map(|m| m.len()).unwrap_or(0);
+            (entry.file_name().to_string_lossy().to_string(), mtime, len)
+        })
+        .collect();
+    entries.sort();
+    for (name, mtime, len) in entries {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(mtime.to_le_bytes());
+        hasher.update(len.to_le_bytes());
+    }
+    Some(finish(hasher))
+}
+
+fn load_from(dir: &Path, root: &str, options: &ScanOptions) -> Option<ScanResult> {
+    let data = fs::read_to_string(cache_file(dir, root)).ok()?;
+    let cached: CachedScanFile = serde_json::from_str(&data).ok()?;
+    let current = fingerprint(Path::new(root), options)?;
+    (cached.root == root && cached.fingerprint == current).then_some(cached.result)
+}
+
+fn save_to(dir: &Path, root: &str, options: &ScanOptions, result: &ScanResult) -> Result<(), String> {
+    let fingerprint = fingerprint(Path::new(root), options)
+        .ok_or_else(|| "Failed to fingerprint project root".to_string())?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create scan cache dir: {}", e))?;
+    let cached = CachedScanFile {
+        root: root.to_string(),
+        fingerprint,
+        result: result.clone(),
+    };
+    let json = serde_json::to_string(&cached).map_err(|e| e.to_string())?;
+    fs::write(cache_file(dir, root), json).map_err(|e| format!("Failed to write scan cache: {}", e))
+}
+
+/// Cached result for `root`, if one exists and the top level hasn't changed since
+pub fn load_cached_scan(root: &str, options: &ScanOptions) -> Option<ScanResult> {
+    load_from(&cache_dir(), root, options)
+}
+
+pub fn save_cached_scan(root: &str, options: &ScanOptions, result: &ScanResult) -> Result<(), String> {
+    save_to(&cache_dir(), root, options, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{build_file_tree, count_files};
+    use crate::types::ProjectMetadata;
+    use tempfile::TempDir;
+
+    fn scan(root: &Path) -> ScanResult {
+        let tree = build_file_tree(root, &[], &[]);
+        ScanResult {
+            project_type: "Rust".to_string(),
+            project_type_info: None,
+            project_types: Vec::new(),
+            frameworks: Vec::new(),
+            total_files: count_files(&tree),
+            tree,
+            metadata: ProjectMetadata {
+                name: "demo".to_string(),
+                project_type: "Rust".to_string(),
+                ..Default::default()
+            },
+            truncated: false,
+            monorepo: None,
+            exclusions: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_invalidation() {
+        let cache = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join("main.rs"), "fn main() {}").unwrap();
+        let root = project.path().to_string_lossy().to_string();
+        let options = ScanOptions::default();
+
+        assert!(load_from(cache.path(), &root, &options).is_none());
+        save_to(cache.path(), &root, &options, &scan(project.path())).unwrap();
+        let cached = load_from(cache.path(), &root, &options).unwrap();
+        assert_eq!(cached.total_files, 1);
+
+        // Different options never reuse the entry
+        let other = ScanOptions { count_lines: true, ..Default::default() };
+        assert!(load_from(cache.path(), &root, &other).is_none());
+
+        // A new top-level entry invalidates it
+        fs::write(project.path().join("lib.rs"), "").unwrap();
+        assert!(load_from(cache.path(), &root, &options).is_none());
+    }
+}
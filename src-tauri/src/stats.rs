@@ -1,8 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::types::{LangStat, ProjectStats};
+use git2::Repository;
+
+use crate::git::{commit_files, format_commit_date, head_revwalk};
+use crate::packer::comment_delimiter;
+use crate::tokens::count_tokens_fast;
+use crate::types::{ChurnStats, FileChurn, FileMarkers, FileSize, LangStat, MarkerCounts, ProjectStats, Tokenizer, TopFiles};
+
+const SECS_PER_MONTH: i64 = 30 * 86_400;
 
 pub fn ext_to_language(ext: &str) -> &str {
     match ext.to_lowercase().as_str() {
@@ -17,6 +25,11 @@ pub fn ext_to_language(ext: &str) -> &str {
         "dart" => "Dart",
         "go" => "Go",
         "rb" => "Ruby",
+        "ex" | "exs" => "Elixir",
+        "heex" | "eex" | "leex" => "Elixir Template",
+        "gd" => "GDScript",
+        "tscn" | "tres" | "godot" => "Godot Resource",
+        "shader" | "gdshader" | "hlsl" | "glsl" | "cginc" => "Shader",
         "php" => "PHP",
         "swift" => "Swift",
         "c" => "C",
@@ -45,19 +58,35 @@ pub fn ext_to_language(ext: &str) -> &str {
     }
 }
 
-pub fn compute_project_stats(paths: &[String]) -> ProjectStats {
-    let mut lang_map: HashMap<String, (String, u32, u64, u64)> = HashMap::new();
+// CodePack: 统计选中文件的语言分布、行类型、技术债标记，以及最大的 top_n 个文件
+pub fn compute_project_stats(paths: &[String], tokenizer: Tokenizer, top_n: usize) -> ProjectStats {
+    let mut lang_map: HashMap<String, LangStat> = HashMap::new();
     let mut total_files: u32 = 0;
     let mut total_lines: u64 = 0;
     let mut total_bytes: u64 = 0;
+    let mut total_kinds = LineKinds::default();
+    let mut markers = MarkerCounts::default();
+    let mut marker_files: Vec<FileMarkers> = Vec::new();
+    let mut sizes: Vec<FileSize> = Vec::new();
+    let mut tokens_exact = true;
 
     for path in paths {
         if let Ok(content) = fs::read_to_string(path) {
             let bytes = content.len() as u64;
             let lines = content.lines().count() as u64;
+            let file_markers = count_markers(&content);
+            let kinds = classify_lines(&content, path);
+            let (tokens, exact) = count_tokens_fast(&content, tokenizer);
+            tokens_exact &= exact;
+            sizes.push(FileSize { path: path.clone(), bytes, lines, tokens: tokens as u64 });
             total_files += 1;
             total_lines += lines;
             total_bytes += bytes;
+            total_kinds.add(&kinds);
+            markers.add(&file_markers);
+            if file_markers.total() > 0 {
+                marker_files.push(FileMarkers { path: path.clone(), markers: file_markers });
+            }
 
             let ext = Path::new(path)
                 .extension()
@@ -66,29 +95,395 @@ pub fn compute_project_stats(paths: &[String]) -> ProjectStats {
                 .to_lowercase();
             let lang = ext_to_language(&ext).to_string();
 
-            let entry = lang_map.entry(lang.clone()).or_insert((ext.clone(), 0, 0, 0));
-            entry.1 += 1;
-            entry.2 += lines;
-            entry.3 += bytes;
+            let entry = lang_map.entry(lang.clone()).or_insert_with(|| LangStat {
+                language: lang,
+                extension: ext.clone(),
+                file_count: 0,
+                line_count: 0,
+                byte_count: 0,
+                code_lines: 0,
+                comment_lines: 0,
+                blank_lines: 0,
+                markers: MarkerCounts::default(),
+            });
+            entry.file_count += 1;
+            entry.line_count += lines;
+            entry.byte_count += bytes;
+            entry.code_lines += kinds.code;
+            entry.comment_lines += kinds.comment;
+            entry.blank_lines += kinds.blank;
+            entry.markers.add(&file_markers);
         }
     }
 
-    let mut languages: Vec<LangStat> = lang_map
-        .into_iter()
-        .map(|(lang, (ext, fc, lc, bc))| LangStat {
-            language: lang,
-            extension: ext,
-            file_count: fc,
-            line_count: lc,
-            byte_count: bc,
-        })
-        .collect();
-    languages.sort_by(|a, b| b.line_count.cmp(&a.line_count));
+    let mut languages: Vec<LangStat> = lang_map.into_values().collect();
+    languages.sort_by(|a, b| b.code_lines.cmp(&a.code_lines).then(b.line_count.cmp(&a.line_count)));
+    marker_files.sort_by(|a, b| b.markers.total().cmp(&a.markers.total()).then_with(|| a.path.cmp(&b.path)));
 
     ProjectStats {
         total_files,
         total_lines,
         total_bytes,
+        total_code_lines: total_kinds.code,
+        total_comment_lines: total_kinds.comment,
+        total_blank_lines: total_kinds.blank,
         languages,
+        markers,
+        marker_files,
+        top_files: top_files(sizes, top_n, tokens_exact),
+    }
+}
+
+/// Ties go to the path that sorts first so the rankings are stable
+fn top_files(sizes: Vec<FileSize>, top_n: usize, tokens_exact: bool) -> TopFiles {
+    let rank = |key: fn(&FileSize) -> u64| {
+        let mut ranked: Vec<FileSize> = sizes.clone();
+        ranked.sort_by(|a, b| key(b).cmp(&key(a)).then_with(|| a.path.cmp(&b.path)));
+        ranked.truncate(top_n);
+        ranked
+    };
+    TopFiles {
+        by_bytes: rank(|f| f.bytes),
+        by_lines: rank(|f| f.lines),
+        by_tokens: rank(|f| f.tokens),
+        tokens_exact,
+    }
+}
+
+// ─── Line Kinds ──────────────────────────────────────────────
+
+#[derive(Debug, Default, PartialEq)]
+struct LineKinds {
+    code: u64,
+    comment: u64,
+    blank: u64,
+}
+
+impl LineKinds {
+    fn add(&mut self, other: &LineKinds) {
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+}
+
+/// Languages commented with `//` and `/* */`; comment_delimiter also falls back to `//` for
+/// anything it doesn't know, which must not be parsed for comments
+const C_STYLE_EXTENSIONS: &[&str] = &[
+    "rs", "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "hxx", "cs", "java", "kt", "kts", "scala", "groovy", "gradle",
+    "go", "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "swift", "dart", "php", "m", "mm", "proto", "zig",
+    "sol", "glsl", "hlsl", "wgsl", "jsonc",
+];
+
+/// Line comment and block comment delimiters for the file's language; neither for unknown
+/// extensions, whose lines all count as code
+fn comment_syntax(path: &str) -> (Option<&'static str>, Option<(&'static str, &'static str)>) {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match comment_delimiter(path) {
+        "//" if !C_STYLE_EXTENSIONS.contains(&ext.as_str()) => (None, None),
+        "<!--" => (None, Some(("<!--", "-->"))),
+        "/*" => (None, Some(("/*", "*/"))),
+        "//" => (Some("//"), Some(("/*", "*/"))),
+        other => (Some(other), None),
+    }
+}
+
+/// Whether `rest` opens a line comment; word tokens such as `REM` only count when followed by
+/// whitespace or the end of the line, and in any case (`rem` is common in batch files)
+fn starts_line_comment(rest: &str, token: &str) -> bool {
+    if !token.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return rest.starts_with(token);
+    }
+    rest.get(..token.len()).is_some_and(|head| head.eq_ignore_ascii_case(token))
+        && rest[token.len()..].chars().next().is_none_or(char::is_whitespace)
+}
+
+// CodePack: 按注释语法把每行归为代码、注释或空行；同一行既有代码又有注释时算代码。
+// 字符串里的注释符号不做识别，结果是近似值
+fn classify_lines(content: &str, path: &str) -> LineKinds {
+    let (line_comment, block) = comment_syntax(path);
+    let mut kinds = LineKinds::default();
+    let mut in_block = false;
+    for line in content.lines() {
+        let mut rest = line.trim();
+        if rest.is_empty() {
+            kinds.blank += 1;
+            continue;
+        }
+        let mut has_code = false;
+        // Walk the line: leave open blocks, skip closed ones, stop at a line comment
+        while !rest.is_empty() {
+            if in_block {
+                let (_, close) = block.unwrap_or_default();
+                match rest.find(close) {
+                    Some(end) => {
+                        in_block = false;
+                        rest = rest[end + close.len()..].trim_start();
+                    }
+                    None => break,
+                }
+            } else if line_comment.is_some_and(|token| starts_line_comment(rest, token)) {
+                break;
+            } else if let Some((open, _)) = block.filter(|(open, _)| rest.starts_with(open)) {
+                in_block = true;
+                rest = &rest[open.len()..];
+            } else {
+                has_code = true;
+                // Only a block opened later on the line (and not inside a trailing line
+                // comment) matters for the lines that follow
+                let line_at = line_comment.and_then(|token| rest.find(token));
+                match block.and_then(|(open, _)| rest.find(open).map(|start| (start, open))) {
+                    Some((start, open)) if line_at.is_none_or(|at| start < at) => {
+                        rest = &rest[start + open.len()..];
+                        in_block = true;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        if has_code {
+            kinds.code += 1;
+        } else {
+            kinds.comment += 1;
+        }
+    }
+    kinds
+}
+
+// ─── Markers ──────────────────────────────────────────────────
+
+// CodePack: 统计 TODO / FIXME / HACK / XXX；只认大写的完整单词，`TODOS`、`todo_list` 不算
+pub fn count_markers(content: &str) -> MarkerCounts {
+    let mut counts = MarkerCounts::default();
+    for word in content.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        match word {
+            "TODO" => counts.todo += 1,
+            "FIXME" => counts.fixme += 1,
+            "HACK" => counts.hack += 1,
+            "XXX" => counts.xxx += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+// ─── Churn ────────────────────────────────────────────────────
+
+// CodePack: 统计最近 months 个月内每个文件被多少次提交改动（忽略合并提交），按提交数降序
+pub fn compute_churn_stats(project_path: &str, months: u32) -> Result<ChurnStats, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    churn_since(project_path, months, now - i64::from(months) * SECS_PER_MONTH)
+}
+
+fn churn_since(project_path: &str, months: u32, since: i64) -> Result<ChurnStats, String> {
+    let repo = Repository::discover(project_path)
+        .map_err(|e| format!("Failed to open git repository: {}", e))?;
+    let repo_root = repo
+        .workdir()
+        .ok_or_else(|| "Repository has no working directory".to_string())?
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve repository root: {}", e))?;
+    let project_root = Path::new(project_path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve project path: {}", e))?;
+    // Repository paths are reported relative to the project when it is a subdirectory
+    let prefix = project_root
+        .strip_prefix(&repo_root)
+        .map(|p| {
+            let p = p.to_string_lossy().replace('\\', "/");
+            if p.is_empty() { p } else { format!("{}/", p) }
+        })
+        .unwrap_or_default();
+    let revwalk = head_revwalk(&repo).map_err(|e| format!("Failed to read history: {}", e))?;
+
+    let mut by_path: HashMap<String, (u32, HashSet<String>, i64)> = HashMap::new();
+    let mut total_commits: u32 = 0;
+    // Commit times are not monotonic (rebases, backdated commits), so older ones are skipped rather than ending the walk
+    for commit in revwalk
+        .filter_map(Result::ok)
+        .filter_map(|oid| repo.find_commit(oid).ok())
+        .filter(|commit| commit.time().seconds() >= since)
+    {
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let files = commit_files(&repo, &commit);
+        let files: Vec<&str> = files.iter().filter_map(|f| f.strip_prefix(prefix.as_str())).collect();
+        if files.is_empty() {
+            continue;
+        }
+        total_commits += 1;
+        let email = commit.author().email().unwrap_or("").to_lowercase();
+        let when = commit.time().seconds();
+        for file in files {
+            let entry = by_path.entry(file.to_string()).or_insert_with(|| (0, HashSet::new(), when));
+            entry.0 += 1;
+            entry.1.insert(email.clone());
+            entry.2 = entry.2.max(when);
+        }
+    }
+
+    let mut files: Vec<FileChurn> = by_path
+        .into_iter()
+        .map(|(path, (commits, authors, latest))| FileChurn {
+            path,
+            commits,
+            authors: authors.len() as u32,
+            last_changed: format_commit_date(git2::Time::new(latest, 0)),
+        })
+        .collect();
+    files.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.path.cmp(&b.path)));
+
+    Ok(ChurnStats {
+        months,
+        total_commits,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(repo: &Repository, files: &[(&str, &str)], author: &str, secs: i64) {
+        let root = repo.workdir().unwrap().to_path_buf();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let full = root.join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            fs::write(&full, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::new(author, &format!("{}@example.com", author), &git2::Time::new(secs, 0)).unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, "change", &tree, &parents).unwrap();
+    }
+
+    #[test]
+    fn test_classify_lines() {
+        let rust = "// header\n\nfn main() { /* inline */ }\n/* block\n   still comment\n*/\nlet x = 1; // trailing /* not a block\n/* a */ let y = 2;\nlet s = 1; /* opens\n closes */\n";
+        assert_eq!(classify_lines(rust, "src/main.rs"), LineKinds { code: 4, comment: 5, blank: 1 });
+
+        let python = "#!/usr/bin/env python\n# comment\n\nprint('hi')  # trailing\n";
+        assert_eq!(classify_lines(python, "app.py"), LineKinds { code: 1, comment: 2, blank: 1 });
+
+        let html = "<!-- a\nb -->\n<p>hi</p>\n";
+        assert_eq!(classify_lines(html, "index.html"), LineKinds { code: 1, comment: 2, blank: 0 });
+
+        let batch = "REM setup\nrem quiet\nREM\nREMOVE_ME=1\necho %REM%\n";
+        assert_eq!(classify_lines(batch, "run.bat"), LineKinds { code: 2, comment: 3, blank: 0 });
+
+        // No comment syntax is assumed for prose and data files
+        let markdown = "# Title\n\n/* not a comment\n// nor this\n";
+        assert_eq!(classify_lines(markdown, "README.md"), LineKinds { code: 3, comment: 0, blank: 1 });
+        assert_eq!(classify_lines("{\"a\": \"//x\"}\n", "data.json"), LineKinds { code: 1, comment: 0, blank: 0 });
+        assert_eq!(classify_lines("// note\n", "notes.txt"), LineKinds { code: 1, comment: 0, blank: 0 });
+    }
+
+    #[test]
+    fn test_count_markers() {
+        let counts = count_markers("// TODO: split\n// FIXME(ada) and TODO again\n/* HACK */ let XXX = 1;\nlet todo_list = TODOS;\n");
+        assert_eq!(counts, MarkerCounts { todo: 2, fixme: 1, hack: 1, xxx: 1 });
+        assert_eq!(counts.total(), 5);
+    }
+
+    #[test]
+    fn test_project_stats_markers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.py");
+        let c = dir.path().join("c.rs");
+        fs::write(&a, "// TODO one\n// TODO two\n").unwrap();
+        fs::write(&b, "# FIXME\n").unwrap();
+        fs::write(&c, "fn main() {}\n").unwrap();
+        let paths: Vec<String> = [&a, &b, &c].iter().map(|p| p.to_string_lossy().to_string()).collect();
+
+        let stats = compute_project_stats(&paths, Tokenizer::Gemini, 10);
+        assert_eq!(stats.markers, MarkerCounts { todo: 2, fixme: 1, hack: 0, xxx: 0 });
+        assert_eq!(stats.marker_files.len(), 2);
+        assert_eq!(stats.marker_files[0].path, paths[0]);
+        let rust = stats.languages.iter().find(|l| l.language == "Rust").unwrap();
+        assert_eq!(rust.file_count, 2);
+        assert_eq!(rust.markers.todo, 2);
+    }
+
+    #[test]
+    fn test_top_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wide = dir.path().join("wide.json");
+        let tall = dir.path().join("tall.rs");
+        let small = dir.path().join("small.rs");
+        fs::write(&wide, "x".repeat(400)).unwrap();
+        fs::write(&tall, "a\n".repeat(50)).unwrap();
+        fs::write(&small, "fn f() {}\n").unwrap();
+        let paths: Vec<String> = [&wide, &tall, &small].iter().map(|p| p.to_string_lossy().to_string()).collect();
+
+        let top = compute_project_stats(&paths, Tokenizer::Gemini, 2).top_files;
+        assert!(top.tokens_exact);
+        assert_eq!(top.by_bytes.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), vec![paths[0].as_str(), paths[1].as_str()]);
+        assert_eq!(top.by_lines[0].path, paths[1]);
+        assert_eq!(top.by_lines[0].lines, 50);
+        assert_eq!(top.by_tokens[0].path, paths[0]);
+        assert_eq!(top.by_tokens.len(), 2);
+    }
+
+    #[test]
+    fn test_churn_counts_commits_in_window() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &[("old.rs", "0")], "ada", 1_600_000_000);
+        commit(&repo, &[("a.rs", "1"), ("b.rs", "1")], "ada", 1_700_000_000);
+        commit(&repo, &[("a.rs", "2")], "bob", 1_700_100_000);
+
+        let stats = churn_since(&dir.path().to_string_lossy(), 6, 1_690_000_000).unwrap();
+        assert_eq!(stats.total_commits, 2);
+        assert_eq!(stats.files.len(), 2);
+        assert_eq!(stats.files[0].path, "a.rs");
+        assert_eq!(stats.files[0].commits, 2);
+        assert_eq!(stats.files[0].authors, 2);
+        assert_eq!(stats.files[0].last_changed, "2023-11-16");
+        assert_eq!(stats.files[1].path, "b.rs");
+    }
+
+    #[test]
+    fn test_churn_skips_backdated_commit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &[("a.rs", "1")], "ada", 1_700_000_000);
+        commit(&repo, &[("old.rs", "1")], "ada", 1_600_000_000);
+        commit(&repo, &[("b.rs", "1")], "bob", 1_700_100_000);
+
+        let stats = churn_since(&dir.path().to_string_lossy(), 6, 1_690_000_000).unwrap();
+        assert_eq!(stats.total_commits, 2);
+        let paths: Vec<&str> = stats.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.rs", "b.rs"]);
+    }
+
+    #[test]
+    fn test_churn_relative_to_subdirectory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &[("app/src/main.rs", "1"), ("docs/readme.md", "1")], "ada", 1_700_000_000);
+
+        let app = dir.path().join("app");
+        let stats = churn_since(&app.to_string_lossy(), 6, 0).unwrap();
+        assert_eq!(stats.files.len(), 1);
+        assert_eq!(stats.files[0].path, "src/main.rs");
+    }
+
+    #[test]
+    fn test_churn_non_repo() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(compute_churn_stats(&dir.path().to_string_lossy(), 6).is_err());
     }
 }
@@ -0,0 +1,614 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+
+use rayon::prelude::*;
+use tiktoken_rs::CoreBPE;
+
+use crate::types::{BudgetWarning, CostEstimate, DirTokenStat, FileNode, Tokenizer};
+
+// ─── Heuristic Ratios ──────────────────────────────────────────
+
+/// Claude's tokenizer isn't public; it produces roughly 10% more tokens than cl100k_base on code
+const CLAUDE_CL100K_RATIO: f64 = 1.1;
+
+/// Gemini (SentencePiece) averages about 4 characters per token on source code
+const GEMINI_CHARS_PER_TOKEN: f64 = 4.0;
+
+// ─── Context Windows ───────────────────────────────────────────
+
+/// Model-name prefixes and their context windows; the longest matching prefix wins
+const CONTEXT_WINDOWS: &[(&str, u64)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1-mini", 128_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude", 200_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini", 1_048_576),
+    ("deepseek", 65_536),
+];
+
+/// Default input prices in USD per 1M tokens; overridable via AppConfig.model_prices
+const MODEL_PRICES: &[(&str, f64)] = &[
+    ("gpt-4.1-mini", 0.40),
+    ("gpt-4.1", 2.00),
+    ("gpt-4o-mini", 0.15),
+    ("gpt-4o", 2.50),
+    ("gpt-4-turbo", 10.00),
+    ("gpt-4-32k", 60.00),
+    ("gpt-4", 30.00),
+    ("gpt-3.5-turbo", 0.50),
+    ("o1-mini", 1.10),
+    ("o1", 15.00),
+    ("o3", 2.00),
+    ("o4-mini", 1.10),
+    ("claude-3-haiku", 0.25),
+    ("claude-3-5-haiku", 0.80),
+    ("claude-3-opus", 15.00),
+    ("claude-opus", 15.00),
+    ("claude", 3.00),
+    ("gemini-1.5-flash", 0.075),
+    ("gemini-1.5-pro", 1.25),
+    ("gemini", 0.10),
+    ("deepseek", 0.27),
+];
+
+/// Fallback used while a BPE is still loading
+const FALLBACK_BYTES_PER_TOKEN: f64 = 4.0;
+
+// ─── BPE Backends ──────────────────────────────────────────────
+
+static CL100K_BPE: OnceLock<CoreBPE> = OnceLock::new();
+static O200K_BPE: OnceLock<CoreBPE> = OnceLock::new();
+static CL100K_LOADING: AtomicBool = AtomicBool::new(false);
+static O200K_LOADING: AtomicBool = AtomicBool::new(false);
+
+impl Tokenizer {
+    /// True when counts come from the vendor's real tokenizer rather than a ratio
+    pub fn is_exact(self) -> bool {
+        matches!(self, Tokenizer::Cl100kBase | Tokenizer::O200kBase)
+    }
+}
+
+fn bpe_cell(tokenizer: Tokenizer) -> (&'static OnceLock<CoreBPE>, &'static AtomicBool) {
+    match tokenizer {
+        Tokenizer::O200kBase => (&O200K_BPE, &O200K_LOADING),
+        _ => (&CL100K_BPE, &CL100K_LOADING),
+    }
+}
+
+/// Loads the BPE on the calling thread if it isn't ready yet
+fn bpe_for(tokenizer: Tokenizer) -> &'static CoreBPE {
+    let (cell, _) = bpe_cell(tokenizer);
+    cell.get_or_init(|| match tokenizer {
+        Tokenizer::O200kBase => {
+            tiktoken_rs::o200k_base().expect("failed to load o200k_base tokenizer")
+        }
+        _ => tiktoken_rs::cl100k_base().expect("failed to load cl100k_base tokenizer"),
+    })
+}
+
+/// Starts loading the BPE on a background thread; no-op if already loading or loaded
+pub fn warm_up(tokenizer: Tokenizer) {
+    let (cell, loading) = bpe_cell(tokenizer);
+    if cell.get().is_some() || loading.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(move || {
+        bpe_for(tokenizer);
+    });
+}
+
+/// Whether counts for this tokenizer can be produced without blocking
+pub fn is_ready(tokenizer: Tokenizer) -> bool {
+    match tokenizer {
+        Tokenizer::Gemini => true,
+        _ => bpe_cell(tokenizer).0.get().is_some(),
+    }
+}
+
+// ─── Count ─────────────────────────────────────────────────────
+
+/// Exact count for BPE tokenizers; blocks until the BPE is loaded
+pub fn count_tokens(text: &str, tokenizer: Tokenizer) -> usize {
+    match tokenizer {
+        Tokenizer::Cl100kBase | Tokenizer::O200kBase => {
+            bpe_for(tokenizer).encode_ordinary(text).len()
+        }
+        Tokenizer::Claude => {
+            let base = bpe_for(Tokenizer::Cl100kBase).encode_ordinary(text).len();
+            (base as f64 * CLAUDE_CL100K_RATIO).ceil() as usize
+        }
+        Tokenizer::Gemini => {
+            (text.chars().count() as f64 / GEMINI_CHARS_PER_TOKEN).ceil() as usize
+        }
+    }
+}
+
+/// Non-blocking count: falls back to bytes/4 while the BPE loads in the background.
+/// The flag is false when the fallback was used.
+pub fn count_tokens_fast(text: &str, tokenizer: Tokenizer) -> (usize, bool) {
+    if is_ready(tokenizer) {
+        return (count_tokens(text, tokenizer), true);
+    }
+    warm_up(tokenizer);
+    let estimate = (text.len() as f64 / FALLBACK_BYTES_PER_TOKEN).ceil() as usize;
+    (estimate, false)
+}
+
+// ─── Budget ────────────────────────────────────────────────────
+
+pub fn context_window(model: &str) -> Option<u64> {
+    let lower = model.to_lowercase();
+    CONTEXT_WINDOWS
+        .iter()
+        .filter(|(prefix, _)| lower.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| *window)
+}
+
+/// Returns a warning when `estimated_tokens` doesn't fit the model's context window
+pub fn check_budget(model: &str, estimated_tokens: f64) -> Option<BudgetWarning> {
+    let window = context_window(model)?;
+    let tokens = estimated_tokens.ceil() as u64;
+    if tokens <= window {
+        return None;
+    }
+    let excess = tokens - window;
+    Some(BudgetWarning {
+        model: model.to_string(),
+        context_window: window,
+        estimated_tokens: tokens,
+        excess_tokens: excess,
+        message: format!(
+            "Pack is ~{} tokens but {} accepts {}; remove at least {} tokens ({:.0}%)",
+            tokens,
+            model,
+            window,
+            excess,
+            excess as f64 / tokens as f64 * 100.0
+        ),
+    })
+}
+
+// ─── Cost ──────────────────────────────────────────────────────
+
+/// Merged price table: built-in defaults overlaid with user overrides
+pub fn price_table(overrides: &HashMap<String, f64>) -> HashMap<String, f64> {
+    let mut table: HashMap<String, f64> = MODEL_PRICES
+        .iter()
+        .map(|(model, price)| (model.to_string(), *price))
+        .collect();
+    for (model, price) in overrides {
+        table.insert(model.to_lowercase(), *price);
+    }
+    table
+}
+
+/// Looks up the price by longest matching model-name prefix
+pub fn price_per_million(model: &str, overrides: &HashMap<String, f64>) -> Option<f64> {
+    let lower = model.to_lowercase();
+    price_table(overrides)
+        .into_iter()
+        .filter(|(prefix, _)| lower.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| price)
+}
+
+pub fn estimate_cost(
+    model: &str,
+    tokens: f64,
+    overrides: &HashMap<String, f64>,
+) -> Option<CostEstimate> {
+    let usd_per_million = price_per_million(model, overrides)?;
+    let usd = tokens / 1_000_000.0 * usd_per_million;
+    Some(CostEstimate {
+        model: model.to_string(),
+        usd_per_million,
+        usd,
+        label: format!("${:.2} @ {}", usd, model),
+    })
+}
+
+// ─── Estimate Cache ────────────────────────────────────────────
+
+const TOKEN_CACHE_CAPACITY: usize = 20_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: String,
+    modified_nanos: u128,
+    len: u64,
+    tokenizer: Tokenizer,
+}
+
+/// LRU of per-file token counts; a changed mtime or size yields a new key, so stale
+/// entries simply age out
+pub struct TokenCache {
+    entries: HashMap<CacheKey, (usize, u64)>,
+    tick: u64,
+    capacity: usize,
+}
+
+impl TokenCache {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            tick: 0,
+            capacity,
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<usize> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|(tokens, last_used)| {
+            *last_used = tick;
+            *tokens
+        })
+    }
+
+    fn insert(&mut self, key: CacheKey, tokens: usize) {
+        self.tick += 1;
+        self.entries.insert(key, (tokens, self.tick));
+        if self.entries.len() > self.capacity {
+            // Evict the least recently used tenth in one pass rather than one entry per insert
+            let mut by_age: Vec<(u64, CacheKey)> = self
+                .entries
+                .iter()
+                .map(|(k, (_, last_used))| (*last_used, k.clone()))
+                .collect();
+            by_age.sort_by_key(|(last_used, _)| *last_used);
+            let evict = (self.capacity / 10).max(1);
+            for (_, key) in by_age.into_iter().take(evict) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+pub struct TokenCacheState {
+    cache: Mutex<TokenCache>,
+}
+
+impl Default for TokenCacheState {
+    fn default() -> Self {
+        Self {
+            cache: Mutex::new(TokenCache::with_capacity(TOKEN_CACHE_CAPACITY)),
+        }
+    }
+}
+
+impl TokenCacheState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns (bytes, tokens, exact) for a file, tokenizing only on a cache miss.
+    /// Fallback counts taken while the BPE is loading are not cached.
+    pub fn count_file(&self, path: &str, tokenizer: Tokenizer) -> Option<(u64, usize, bool)> {
+        let meta = fs::metadata(path).ok()?;
+        let modified_nanos = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let key = CacheKey {
+            path: path.to_string(),
+            modified_nanos,
+            len: meta.len(),
+            tokenizer,
+        };
+        if let Some(tokens) = self.cache.lock().ok()?.get(&key) {
+            return Some((key.len, tokens, true));
+        }
+        let content = fs::read_to_string(path).ok()?;
+        let (tokens, exact) = count_tokens_fast(&content, tokenizer);
+        if exact {
+            if let Ok(mut cache) = self.cache.lock() {
+                cache.insert(key, tokens);
+            }
+        }
+        Some((content.len() as u64, tokens, exact))
+    }
+
+    /// Counts many files across the rayon pool; unreadable files are left out.
+    /// The flag is false if any file fell back to the heuristic.
+    pub fn count_files(
+        &self,
+        paths: &[String],
+        tokenizer: Tokenizer,
+    ) -> (Vec<(String, u64, usize)>, bool) {
+        let counted: Vec<(String, u64, usize, bool)> = paths
+            .par_iter()
+            .filter_map(|path| {
+                self.count_file(path, tokenizer)
+                    .map(|(bytes, tokens, exact)| (path.clone(), bytes, tokens, exact))
+            })
+            .collect();
+        let all_exact = counted.iter().all(|(_, _, _, exact)| *exact);
+        let files = counted
+            .into_iter()
+            .map(|(path, bytes, tokens, _)| (path, bytes, tokens))
+            .collect();
+        (files, all_exact)
+    }
+}
+
+// ─── Directory Rollup ──────────────────────────────────────────
+
+/// Aggregates (path, bytes, tokens) per directory relative to `root`; every file counts
+/// toward each of its ancestors, the root being "". Sorted by tokens, heaviest first.
+pub fn rollup_by_directory(root: &Path, files: &[(String, u64, usize)]) -> Vec<DirTokenStat> {
+    let mut dirs: HashMap<String, DirTokenStat> = HashMap::new();
+    for (path, bytes, tokens) in files {
+        let relative = Path::new(path)
+            .strip_prefix(root)
+            .unwrap_or(Path::new(path))
+            .to_string_lossy()
+            .replace('\\', "/");
+        let mut ancestors = vec![String::new()];
+        let parts: Vec<&str> = relative.split('/').collect();
+        for i in 1..parts.len() {
+            ancestors.push(parts[..i].join("/"));
+        }
+        for dir in ancestors {
+            let stat = dirs.entry(dir.clone()).or_insert_with(|| DirTokenStat {
+                path: dir,
+                tokens: 0.0,
+                total_bytes: 0,
+                file_count: 0,
+            });
+            stat.tokens += *tokens as f64;
+            stat.total_bytes += bytes;
+            stat.file_count += 1;
+        }
+    }
+    let mut result: Vec<DirTokenStat> = dirs.into_values().collect();
+    result.sort_by(|a, b| {
+        b.tokens
+            .partial_cmp(&a.tokens)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    result
+}
+
+// ─── Tree Annotation ───────────────────────────────────────────
+
+/// Writes per-file counts into the tree and returns the subtree total; directories
+/// get the sum of their annotated descendants
+pub fn annotate_tree(node: &mut FileNode, counts: &HashMap<String, usize>) -> f64 {
+    if !node.is_dir {
+        node.tokens = counts.get(&node.path).map(|t| *t as f64);
+        return node.tokens.unwrap_or(0.0);
+    }
+    let total: f64 = node
+        .children
+        .iter_mut()
+        .map(|child| annotate_tree(child, counts))
+        .sum();
+    node.tokens = Some(total);
+    total
+}
+
+// ─── Tests ─────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "fn main() {\n    println!(\"hello, world\");\n}\n";
+
+    #[test]
+    fn test_claude_estimate_exceeds_cl100k() {
+        let base = count_tokens(SAMPLE, Tokenizer::Cl100kBase);
+        let claude = count_tokens(SAMPLE, Tokenizer::Claude);
+        assert!(claude > base);
+    }
+
+    #[test]
+    fn test_gemini_estimate_uses_char_ratio() {
+        assert_eq!(count_tokens("abcdefgh", Tokenizer::Gemini), 2);
+        assert_eq!(count_tokens("abcdefghi", Tokenizer::Gemini), 3);
+        assert_eq!(count_tokens("", Tokenizer::Gemini), 0);
+    }
+
+    #[test]
+    fn test_context_window_prefix_match() {
+        assert_eq!(context_window("gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_window("gpt-4"), Some(8_192));
+        assert_eq!(context_window("gpt-4-32k-0613"), Some(32_768));
+        assert_eq!(context_window("o1-mini-2024-09-12"), Some(128_000));
+        assert_eq!(context_window("claude-3-5-sonnet-latest"), Some(200_000));
+        assert_eq!(context_window("Gemini-1.5-Pro"), Some(2_097_152));
+        assert_eq!(context_window("my-local-llm"), None);
+    }
+
+    #[test]
+    fn test_check_budget() {
+        assert!(check_budget("claude-3-opus", 150_000.0).is_none());
+        let warning = check_budget("claude-3-opus", 250_000.0).unwrap();
+        assert_eq!(warning.context_window, 200_000);
+        assert_eq!(warning.excess_tokens, 50_000);
+        assert!(check_budget("unknown-model", 10_000_000.0).is_none());
+    }
+
+    #[test]
+    fn test_price_longest_prefix_wins() {
+        let none = HashMap::new();
+        assert_eq!(price_per_million("gpt-4o-mini-2024-07-18", &none), Some(0.15));
+        assert_eq!(price_per_million("gpt-4o", &none), Some(2.50));
+        assert_eq!(price_per_million("gpt-4-32k", &none), Some(60.00));
+        assert_eq!(price_per_million("claude-3-5-haiku-latest", &none), Some(0.80));
+        assert_eq!(price_per_million("llama-3", &none), None);
+    }
+
+    #[test]
+    fn test_price_override() {
+        let mut overrides = HashMap::new();
+        overrides.insert("gpt-4o".to_string(), 5.0);
+        overrides.insert("llama".to_string(), 0.0);
+        assert_eq!(price_per_million("gpt-4o", &overrides), Some(5.0));
+        assert_eq!(price_per_million("llama-3", &overrides), Some(0.0));
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let cost = estimate_cost("gpt-4o", 168_000.0, &HashMap::new()).unwrap();
+        assert!((cost.usd - 0.42).abs() < 1e-9);
+        assert_eq!(cost.label, "$0.42 @ gpt-4o");
+        assert!(estimate_cost("unknown", 1000.0, &HashMap::new()).is_none());
+    }
+
+    fn key(path: &str, len: u64) -> CacheKey {
+        CacheKey {
+            path: path.to_string(),
+            modified_nanos: 1,
+            len,
+            tokenizer: Tokenizer::Cl100kBase,
+        }
+    }
+
+    #[test]
+    fn test_token_cache_hit_and_miss() {
+        let mut cache = TokenCache::with_capacity(10);
+        cache.insert(key("a.rs", 10), 3);
+        assert_eq!(cache.get(&key("a.rs", 10)), Some(3));
+        // Same path with a different size is a different file version
+        assert_eq!(cache.get(&key("a.rs", 11)), None);
+    }
+
+    #[test]
+    fn test_token_cache_evicts_least_recently_used() {
+        let mut cache = TokenCache::with_capacity(3);
+        cache.insert(key("a", 1), 1);
+        cache.insert(key("b", 1), 1);
+        cache.insert(key("c", 1), 1);
+        cache.get(&key("a", 1));
+        cache.insert(key("d", 1), 1);
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get(&key("b", 1)).is_none());
+        assert!(cache.get(&key("a", 1)).is_some());
+    }
+
+    #[test]
+    fn test_token_cache_state_counts_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("main.rs");
+        fs::write(&path, SAMPLE).unwrap();
+        count_tokens(SAMPLE, Tokenizer::Cl100kBase);
+        let state = TokenCacheState::new();
+        let path = path.to_string_lossy().to_string();
+        let first = state.count_file(&path, Tokenizer::Cl100kBase).unwrap();
+        let second = state.count_file(&path, Tokenizer::Cl100kBase).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.0, SAMPLE.len() as u64);
+    }
+
+    #[test]
+    fn test_token_cache_state_counts_files_in_parallel() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let paths: Vec<String> = (0..20)
+            .map(|i| {
+                let path = dir.path().join(format!("f{}.rs", i));
+                fs::write(&path, SAMPLE).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .chain(std::iter::once("/nonexistent/file.rs".to_string()))
+            .collect();
+        let single = count_tokens(SAMPLE, Tokenizer::Cl100kBase);
+        let state = TokenCacheState::new();
+        let (counted, all_exact) = state.count_files(&paths, Tokenizer::Cl100kBase);
+        assert!(all_exact);
+        assert_eq!(counted.len(), 20);
+        assert!(counted.iter().all(|(_, _, tokens)| *tokens == single));
+    }
+
+    #[test]
+    fn test_rollup_by_directory() {
+        let root = Path::new("/proj");
+        let files = vec![
+            ("/proj/src/main.rs".to_string(), 10, 100),
+            ("/proj/src/ui/app.rs".to_string(), 20, 50),
+            ("/proj/README.md".to_string(), 5, 7),
+        ];
+        let stats = rollup_by_directory(root, &files);
+        let find = |p: &str| stats.iter().find(|s| s.path == p).unwrap();
+        assert_eq!(find("").tokens, 157.0);
+        assert_eq!(find("").file_count, 3);
+        assert_eq!(find("src").tokens, 150.0);
+        assert_eq!(find("src").total_bytes, 30);
+        assert_eq!(find("src/ui").tokens, 50.0);
+        assert_eq!(stats[0].path, "");
+        assert_eq!(stats.len(), 3);
+    }
+
+    #[test]
+    fn test_count_tokens_fast_is_exact_once_loaded() {
+        let exact = count_tokens(SAMPLE, Tokenizer::Cl100kBase);
+        assert!(is_ready(Tokenizer::Cl100kBase));
+        assert_eq!(count_tokens_fast(SAMPLE, Tokenizer::Cl100kBase), (exact, true));
+        assert!(is_ready(Tokenizer::Gemini));
+    }
+
+    #[test]
+    fn test_annotate_tree() {
+        let file = |path: &str| FileNode {
+            name: path.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let mut tree = FileNode {
+            name: "root".to_string(),
+            path: "root".to_string(),
+            is_dir: true,
+            children: vec![
+                FileNode {
+                    name: "src".to_string(),
+                    path: "root/src".to_string(),
+                    is_dir: true,
+                    children: vec![file("root/src/a.rs"), file("root/src/b.rs")],
+                    ..Default::default()
+                },
+                file("root/c.rs"),
+            ],
+            ..Default::default()
+        };
+        let counts: HashMap<String, usize> = [("root/src/a.rs", 10), ("root/src/b.rs", 5)]
+            .into_iter()
+            .map(|(p, t)| (p.to_string(), t))
+            .collect();
+        assert_eq!(annotate_tree(&mut tree, &counts), 15.0);
+        assert_eq!(tree.tokens, Some(15.0));
+        assert_eq!(tree.children[0].tokens, Some(15.0));
+        assert_eq!(tree.children[1].tokens, None);
+    }
+
+    #[test]
+    fn test_is_exact() {
+        assert!(Tokenizer::Cl100kBase.is_exact());
+        assert!(Tokenizer::O200kBase.is_exact());
+        assert!(!Tokenizer::Claude.is_exact());
+        assert!(!Tokenizer::Gemini.is_exact());
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::plugins::{CommandStage, PluginDef};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FileNode {
     pub name: String,
     pub path: String,
@@ -10,35 +12,428 @@ pub struct FileNode {
     pub checked: bool,
     #[serde(default)]
     pub indeterminate: bool,
+    /// Filled in lazily by annotate_tree_tokens; directories hold the sum of their files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<f64>,
+    /// Directories hold the total of their files
+    #[serde(default)]
+    pub size_bytes: u64,
+    /// Unix seconds; directories hold the newest descendant
+    #[serde(default)]
+    pub modified_at: Option<u64>,
+    /// Only populated when the scan was asked to count lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_count: Option<u64>,
+    /// Set on directories returned by a shallow scan whose children have not been loaded yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<u32>,
+    /// Set on the directory node of a monorepo member package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Content sniffing found NUL bytes or invalid UTF-8 in the first 8 KB
+    #[serde(default)]
+    pub is_binary: bool,
+    /// Language read from the shebang of an extensionless script
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Test file or test directory by naming convention
+    #[serde(default)]
+    pub is_test: bool,
+    /// Source file that also carries inline tests (Rust `#[cfg(test)]`)
+    #[serde(default)]
+    pub has_inline_tests: bool,
+    /// Why the file name marks it as a key / credentials file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive: Option<String>,
+}
+
+// CodePack: 扫描选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanOptions {
+    #[serde(default)]
+    pub extra_excludes: Vec<String>,
+    #[serde(default)]
+    pub extra_extensions: Vec<String>,
+    /// Whitelist globs relative to the root (e.g. `src/**/*.rs`); empty means everything
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+    /// Blacklist globs relative to the root; a leading `!` is optional
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    /// None: only the curated hidden allowlist; Some(true): all dotfiles; Some(false): none
+    #[serde(default)]
+    pub include_hidden: Option<bool>,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// Reads every file to fill FileNode.line_count; off by default since it costs I/O
+    #[serde(default)]
+    pub count_lines: bool,
+    /// Directories deeper than this are not descended into
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Traversal stops once this many files have been collected
+    #[serde(default)]
+    pub max_files: Option<u32>,
+    /// List every file regardless of extension; binaries come back flagged and unchecked
+    #[serde(default)]
+    pub include_all: bool,
+    /// How `.env*` files (other than templates such as `.env.example`) are treated
+    #[serde(default)]
+    pub dotenv_policy: DotenvPolicy,
+}
+
+// CodePack: .env 文件策略：默认总是排除（模板除外），或仅输出变量名存根
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum DotenvPolicy {
+    #[default]
+    #[serde(rename = "exclude")]
+    Exclude,
+    /// Keep the file but replace it with `KEY=` lines, values dropped
+    #[serde(rename = "stub")]
+    Stub,
+}
+
+// CodePack: 项目类型：识别出的具名类型，或未识别时的通用类型（序列化为 "generic"）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ProjectType {
+    Generic,
+    Named(String),
+}
+
+impl ProjectType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProjectType::Generic => "generic",
+            ProjectType::Named(name) => name,
+        }
+    }
+}
+
+impl From<&str> for ProjectType {
+    fn from(name: &str) -> Self {
+        match name {
+            // "通用" is what older versions wrote into configs and caches
+            "generic" | "通用" | "" => ProjectType::Generic,
+            other => ProjectType::Named(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ProjectType {
+    fn from(name: String) -> Self {
+        ProjectType::from(name.as_str())
+    }
+}
+
+impl From<ProjectType> for String {
+    fn from(project_type: ProjectType) -> Self {
+        project_type.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for ProjectType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<&str> for ProjectType {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+// CodePack: 项目类型识别详情：置信度 + 触发识别的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTypeInfo {
+    pub name: ProjectType,
+    /// 0.0 (fallback, nothing matched) to 1.0 (plugin match)
+    pub confidence: f32,
+    /// Files / directories at the root that triggered detection, e.g. `Cargo.toml`
+    pub evidence: Vec<String>,
+}
+
+// CodePack: 插件单条识别规则的检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginRuleCheck {
+    /// `file`, `dir`, `glob` or `wasm`
+    pub kind: String,
+    pub rule: String,
+    pub passed: bool,
+    /// The file a glob matched, or the evidence a WASM `detect` hook reported
+    #[serde(default)]
+    pub matched: Vec<String>,
+    /// Why the rule could not be evaluated (invalid glob, WASM error)
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+// CodePack: test_plugin 的结果：是否匹配、各规则明细，以及启用插件后扫描结果的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginTestReport {
+    pub matches: bool,
+    pub checks: Vec<PluginRuleCheck>,
+    /// Problems that would make the plugin misbehave regardless of the directory
+    pub problems: Vec<String>,
+    pub files_before: u32,
+    pub files_after: u32,
+    /// Files the plugin's extensions add to the scan, relative to the root (first 200)
+    pub added_files: Vec<String>,
+    /// Files its exclude_dirs / exclude_files drop from the scan (first 200)
+    pub removed_files: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResult {
     pub project_type: String,
+    #[serde(default)]
+    pub project_type_info: Option<ProjectTypeInfo>,
+    /// All matching types, best first; `project_type` is the first one
+    #[serde(default)]
+    pub project_types: Vec<ProjectTypeInfo>,
+    /// Same as `metadata.frameworks`, surfaced for the framework badge
+    #[serde(default)]
+    pub frameworks: Vec<String>,
     pub tree: FileNode,
     pub total_files: u32,
     pub metadata: ProjectMetadata,
+    /// True when max_depth or max_files cut the scan short
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub monorepo: Option<MonorepoInfo>,
+    #[serde(default)]
+    pub exclusions: Option<ExclusionReport>,
 }
 
+// CodePack: 扫描过滤统计，帮助排查文件为何没有出现在树中
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExclusionReport {
+    /// Excluded directory name → how many times it was skipped
+    pub excluded_dirs: HashMap<String, u32>,
+    /// Extension (`.png`, `(none)`) → files dropped because it isn't a source type
+    pub skipped_extensions: HashMap<String, u32>,
+    /// Dropped by .gitignore, .aiignore or .cursorignore
+    pub gitignored: u32,
+    /// A few of the gitignored paths, relative to the root
+    pub gitignored_samples: Vec<String>,
+    /// Matched by an exclude glob
+    pub glob_excluded: u32,
+    /// Dotfiles / dot-directories filtered out
+    pub hidden: u32,
+    /// Key / credentials files dropped by the extension filter, relative to the root
+    #[serde(default)]
+    pub sensitive_skipped: Vec<String>,
+}
+
+// CodePack: Monorepo 信息（pnpm / yarn / npm / Cargo / Nx / Turborepo）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonorepoInfo {
+    pub tool: String,
+    pub packages: Vec<MonorepoPackage>,
+}
+
+// CodePack: Monorepo 中的成员包，带各自的元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonorepoPackage {
+    pub name: String,
+    pub path: String,
+    pub relative_path: String,
+    pub project_type: String,
+    pub metadata: ProjectMetadata,
+    pub file_count: u32,
+}
+
+// CodePack: 提交在项目根目录的 codepack.toml / .codepack.json，团队共享的打包默认设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectFileConfig {
+    /// Directory names excluded on top of the built-in and user-level excludes
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    #[serde(default)]
+    pub extra_extensions: Vec<String>,
+    /// Used when a pack request does not name a format
+    #[serde(default)]
+    pub format: Option<ExportFormat>,
+    #[serde(default)]
+    pub max_file_bytes: Option<u64>,
+    /// Preset name -> preset whose paths are relative to the project root
+    #[serde(default)]
+    pub presets: HashMap<String, Preset>,
+}
+
+// CodePack: 预设：勾选的路径及打包选项；旧配置中只有路径数组的写法仍可读取
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "PresetRepr")]
+pub struct Preset {
+    pub paths: Vec<String>,
+    pub format: Option<ExportFormat>,
+    /// Exclude rules applied on top of the project's own while this preset is active
+    pub excludes: Vec<String>,
+    /// Same as `PackOptions::order_by_churn`
+    pub order_by_churn: Option<u32>,
+    /// Name of the review prompt attached to packs made from this preset
+    pub review_prompt: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PresetRepr {
+    Paths(Vec<String>),
+    Full {
+        #[serde(default)]
+        paths: Vec<String>,
+        #[serde(default)]
+        format: Option<ExportFormat>,
+        #[serde(default)]
+        excludes: Vec<String>,
+        #[serde(default)]
+        order_by_churn: Option<u32>,
+        #[serde(default)]
+        review_prompt: Option<String>,
+    },
+}
+
+impl From<PresetRepr> for Preset {
+    fn from(repr: PresetRepr) -> Self {
+        match repr {
+            PresetRepr::Paths(paths) => Preset { paths, ..Default::default() },
+            PresetRepr::Full { paths, format, excludes, order_by_churn, review_prompt } => Preset {
+                paths,
+                format,
+                excludes,
+                order_by_churn,
+                review_prompt,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub project_path: String,
     pub checked_paths: Vec<String>,
     pub excluded_paths: Vec<String>,
     pub last_opened: String,
     #[serde(default)]
-    pub presets: HashMap<String, Vec<String>>,
+    pub presets: HashMap<String, Preset>,
     #[serde(default)]
     pub pinned: bool,
+    /// Secret findings the user accepted; matching findings are no longer reported
+    #[serde(default)]
+    pub secret_baseline: Vec<SecretBaselineEntry>,
+    #[serde(default)]
+    pub dotenv_policy: DotenvPolicy,
+    /// Names of the plugins applied to this project; None applies every installed plugin
+    #[serde(default)]
+    pub enabled_plugins: Option<Vec<String>>,
+    /// Fingerprints of plugin commands the user allowed to run in this project
+    #[serde(default)]
+    pub approved_plugin_commands: Vec<String>,
+}
+
+impl ProjectConfig {
+    /// Empty settings for a project first seen now
+    pub fn new(project_path: String) -> Self {
+        Self {
+            project_path,
+            last_opened: crate::config::chrono_now(),
+            ..Default::default()
+        }
+    }
+}
+
+// CodePack: 插件外部命令及其在项目中的批准状态，供界面请求用户确认
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginCommandInfo {
+    pub plugin: String,
+    pub stage: CommandStage,
+    pub command: String,
+    pub fingerprint: String,
+    pub approved: bool,
+}
+
+// CodePack: 密钥处理策略：打包时是否默认遮蔽检测到的密钥
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum SecretPolicy {
+    #[default]
+    #[serde(rename = "allow")]
+    Allow,
+    #[serde(rename = "mask")]
+    Mask,
+}
+
+// CodePack: 应用级默认设置，命令未显式传参（且项目 codepack.toml 未指定）时使用
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AppSettings {
+    #[serde(default)]
+    pub default_format: Option<ExportFormat>,
+    #[serde(default)]
+    pub default_max_file_bytes: Option<u64>,
+    #[serde(default)]
+    pub default_tokenizer: Tokenizer,
+    #[serde(default)]
+    pub secret_policy: SecretPolicy,
+    /// Directory names excluded from every scan, on top of the built-in list
+    #[serde(default)]
+    pub default_excludes: Vec<String>,
+    /// HTTPS index of community plugins used by install_plugin_from_registry
+    #[serde(default)]
+    pub plugin_registry_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
     pub projects: HashMap<String, ProjectConfig>,
+    /// Flattened so `default_tokenizer` stays where older config files have it
+    #[serde(flatten)]
+    pub settings: AppSettings,
+    /// User overrides for input price (USD per 1M tokens), keyed by model-name prefix
+    #[serde(default)]
+    pub model_prices: HashMap<String, f64>,
+    #[serde(default)]
+    pub workspaces: HashMap<String, Workspace>,
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+impl AppConfig {
+    /// The project's settings, created empty on first use
+    pub fn entry_or_default(&mut self, project_path: &str) -> &mut ProjectConfig {
+        self.projects
+            .entry(project_path.to_string())
+            .or_insert_with(|| ProjectConfig::new(project_path.to_string()))
+    }
+}
+
+// CodePack: 界面语言，决定项目类型等名称的显示文本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+// CodePack: 多根目录工作区，多个独立目录合并为一棵虚拟树
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    pub roots: Vec<String>,
+    pub last_opened: String,
+}
+
+// CodePack: 工作区中的单个根目录及其在虚拟树中的显示名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRoot {
+    pub label: String,
+    pub path: String,
 }
 
 // CodePack: 导出格式
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub enum ExportFormat {
     #[default]
     #[serde(rename = "plain")]
@@ -49,6 +444,93 @@ pub enum ExportFormat {
     Xml,
 }
 
+// CodePack: git hook 自动导出设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHookOptions {
+    /// Where the pack is written, relative to the project root unless absolute
+    pub output: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// pre-commit only: `git add` the refreshed pack so it lands in the same commit
+    #[serde(default)]
+    pub stage_output: bool,
+    /// Replace an existing hook that CodePack did not write
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+// CodePack: Token 计数所用的分词器（claude / gemini 为近似估算）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum Tokenizer {
+    #[default]
+    #[serde(rename = "cl100k_base")]
+    Cl100kBase,
+    #[serde(rename = "o200k_base")]
+    O200kBase,
+    #[serde(rename = "claude")]
+    Claude,
+    #[serde(rename = "gemini")]
+    Gemini,
+}
+
+// CodePack: 打包选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub max_file_bytes: Option<u64>,
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+    #[serde(default)]
+    pub target_model: Option<String>,
+    /// Workspace roots; when set, file paths are shown as `<label>/<path within root>`
+    #[serde(default)]
+    pub roots: Vec<WorkspaceRoot>,
+    /// Annotate header requirements with the exact versions pinned in lockfiles
+    #[serde(default)]
+    pub resolve_lockfiles: bool,
+    /// Append the last N commits from git log
+    #[serde(default)]
+    pub include_history: Option<u32>,
+    /// List the files each appended commit touched
+    #[serde(default)]
+    pub history_files: bool,
+    /// Annotate each file with a blame summary (top authors, last change per region)
+    #[serde(default)]
+    pub include_blame: bool,
+    /// List the top contributors in the header
+    #[serde(default)]
+    pub include_contributors: bool,
+    /// Summarize TODO / FIXME / HACK / XXX counts of the packed files in the header
+    #[serde(default)]
+    pub include_markers: bool,
+    /// Read file contents from this commit/tag/branch instead of the working tree
+    #[serde(default)]
+    pub at_ref: Option<String>,
+    /// Order files by commits in the last N months, hottest first
+    #[serde(default)]
+    pub order_by_churn: Option<u32>,
+    /// Mask detected secrets in file bodies and diffs before they are written into the pack
+    #[serde(default)]
+    pub mask_secrets: bool,
+    /// Plugins whose secret rules apply to masking and diff scans; None applies every installed plugin
+    #[serde(skip)]
+    pub secret_plugins: Option<Vec<String>>,
+    /// Dotenv files are skipped, or packed as variable names only
+    #[serde(default)]
+    pub dotenv_policy: DotenvPolicy,
+    /// Header snippets from matching plugins, rendered against the project metadata
+    #[serde(default)]
+    pub header_templates: Vec<String>,
+    /// Per-file notes from plugin pre-pack commands, keyed by relative path
+    #[serde(skip)]
+    pub annotations: HashMap<String, String>,
+    /// Metadata already extracted for this project; the packer parses manifests itself when unset
+    #[serde(skip)]
+    pub metadata: Option<ProjectMetadata>,
+}
+
 // CodePack: pack_files 返回结构，包含统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackResult {
@@ -58,6 +540,64 @@ pub struct PackResult {
     pub estimated_tokens: f64,
     #[serde(default)]
     pub skipped_files: Vec<SkippedFile>,
+    #[serde(default)]
+    pub budget_warning: Option<BudgetWarning>,
+    #[serde(default)]
+    pub estimated_cost: Option<CostEstimate>,
+    /// Files that still contain merge conflict markers
+    #[serde(default)]
+    pub conflicts: Vec<ConflictedFile>,
+    /// Files whose secrets were masked, when `mask_secrets` is on
+    #[serde(default)]
+    pub masked_secrets: Vec<MaskedFile>,
+    /// Secrets found in the git diff sections, which may cover files that weren't selected
+    #[serde(default)]
+    pub diff_secrets: Vec<DiffSecrets>,
+}
+
+// CodePack: diff 中发现的密钥；line_number 为 diff 文本内的行号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffSecrets {
+    pub path: String,
+    /// `range`, `staged` or `unstaged`
+    pub stage: String,
+    pub findings: Vec<SecretMatch>,
+}
+
+// CodePack: 打包时被自动脱敏的文件及脱敏数量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaskedFile {
+    pub path: String,
+    pub count: u32,
+}
+
+// CodePack: 仍含有 <<<<<<< / ======= / >>>>>>> 冲突标记的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictedFile {
+    pub path: String,
+    /// 1-based line of the first `<<<<<<<` marker
+    pub first_line: u32,
+    /// Number of conflict blocks
+    pub blocks: u32,
+}
+
+// CodePack: 按模型输入单价估算的费用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub model: String,
+    pub usd_per_million: f64,
+    pub usd: f64,
+    pub label: String,
+}
+
+// CodePack: 超出目标模型上下文窗口时的警告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetWarning {
+    pub model: String,
+    pub context_window: u64,
+    pub estimated_tokens: u64,
+    pub excess_tokens: u64,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,10 +612,43 @@ pub struct SkippedFile {
 pub struct TokenEstimate {
     pub tokens: f64,
     pub total_bytes: u64,
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+    #[serde(default)]
+    pub approximate: bool,
+    #[serde(default)]
+    pub estimated_cost: Option<CostEstimate>,
 }
 
-// CodePack: 项目元数据，用于导出时附加丰富上下文
+// CodePack: 单个文件的 Token 估算
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTokenEstimate {
+    pub path: String,
+    pub bytes: u64,
+    pub tokens: f64,
+}
+
+// CodePack: estimate_tokens_detailed 返回结构，逐文件明细 + 汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailedTokenEstimate {
+    pub files: Vec<FileTokenEstimate>,
+    pub tokens: f64,
+    pub total_bytes: u64,
+    pub tokenizer: Tokenizer,
+    pub approximate: bool,
+}
+
+// CodePack: 按目录汇总的 Token 统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirTokenStat {
+    pub path: String,
+    pub tokens: f64,
+    pub total_bytes: u64,
+    pub file_count: u32,
+}
+
+// CodePack: 项目元数据，用于导出时附加丰富上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProjectMetadata {
     pub name: String,
     pub project_type: String,
@@ -88,6 +661,84 @@ pub struct ProjectMetadata {
     pub runtime: Vec<String>,
     #[serde(default)]
     pub requirements: Vec<String>,
+    /// One entry per detected ecosystem (cargo, npm, ...) for hybrid projects
+    #[serde(default)]
+    pub ecosystems: Vec<EcosystemDeps>,
+    #[serde(default)]
+    pub workspace_members: Vec<CargoMember>,
+    /// SPDX identifier from the manifest or LICENSE file, e.g. `MIT`
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub ci: Vec<CiPipeline>,
+    /// Major frameworks with their major version, e.g. `React 18`, `Tauri 2`
+    #[serde(default)]
+    pub frameworks: Vec<String>,
+    #[serde(default)]
+    pub submodules: Vec<SubmoduleInfo>,
+    /// Source revision the files were read from; None outside a git repository
+    #[serde(default)]
+    pub repository: Option<RepositoryInfo>,
+    /// Top contributors; only filled in when a pack asks for them
+    #[serde(default)]
+    pub contributors: Vec<Contributor>,
+}
+
+// CodePack: 仓库来源信息，让导出内容可追溯到确切的提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryInfo {
+    /// `origin` (or the first remote), with any credentials removed
+    pub remote_url: Option<String>,
+    /// None on a detached HEAD
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    /// Nearest tag reachable from HEAD
+    pub latest_tag: Option<String>,
+    /// Tracked files differ from HEAD (untracked files do not count)
+    pub dirty: bool,
+}
+
+// CodePack: git 子模块及其固定的提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmoduleInfo {
+    pub name: String,
+    /// Relative to the project root, `/`-separated
+    pub path: String,
+    pub url: Option<String>,
+    /// Short SHA recorded in the superproject's HEAD
+    pub commit: Option<String>,
+    /// Short SHA checked out in the submodule; None when it is not initialized
+    pub checked_out: Option<String>,
+}
+
+// CodePack: CI 流水线摘要（GitHub Actions / GitLab CI / Jenkins）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CiPipeline {
+    /// `GitHub Actions`, `GitLab CI` or `Jenkins`
+    pub provider: String,
+    /// Relative to the project root, `/`-separated
+    pub file: String,
+    pub name: Option<String>,
+    pub triggers: Vec<String>,
+    pub jobs: Vec<String>,
+}
+
+// CodePack: Cargo workspace 中的成员 crate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CargoMember {
+    pub name: String,
+    pub version: Option<String>,
+    /// Relative to the workspace root, `/`-separated
+    pub path: String,
+}
+
+// CodePack: 混合项目中单个生态的依赖
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcosystemDeps {
+    pub ecosystem: String,
+    pub project_type: String,
+    pub dependencies: Vec<String>,
+    pub dev_dependencies: Vec<String>,
 }
 
 // CodePack: 敏感信息类型
@@ -108,6 +759,126 @@ pub struct SecretMatch {
     pub description: String,
     pub start_index: usize,
     pub end_index: usize,
+    /// Hash of the rule and the trimmed line, stable when surrounding lines move
+    #[serde(default)]
+    pub fingerprint: String,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+// CodePack: 密钥泄露的严重度，排序时 Critical 在前
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+pub enum Severity {
+    #[serde(rename = "critical")]
+    Critical,
+    #[serde(rename = "high")]
+    High,
+    #[default]
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "low")]
+    Low,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Critical => "critical",
+            Severity::High => "high",
+            Severity::Medium => "medium",
+            Severity::Low => "low",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeverityCounts {
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+impl SeverityCounts {
+    pub fn add(&mut self, severity: Severity) {
+        match severity {
+            Severity::Critical => self.critical += 1,
+            Severity::High => self.high += 1,
+            Severity::Medium => self.medium += 1,
+            Severity::Low => self.low += 1,
+        }
+    }
+}
+
+// CodePack: 单个文件的扫描结果，highest 为其中最高的严重度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileFindings {
+    pub path: String,
+    pub highest: Severity,
+    pub findings: Vec<SecretMatch>,
+}
+
+// CodePack: 项目级安全报告，可导出为 JSON / Markdown 留档
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub project_path: String,
+    pub generated_at: String,
+    pub files_scanned: u32,
+    pub total_findings: u32,
+    pub by_severity: SeverityCounts,
+    pub files: Vec<FileFindings>,
+}
+
+// CodePack: 安全报告导出格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum ReportFormat {
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "markdown")]
+    Markdown,
+}
+
+// CodePack: 已确认可忽略的密钥扫描结果（文件 + 行指纹），按项目保存
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretBaselineEntry {
+    /// Path relative to the project root
+    pub path: String,
+    pub fingerprint: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+// CodePack: 配置文件解析失败时的恢复情况，启动后提示用户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigRecovery {
+    pub path: String,
+    pub error: String,
+    /// The last good config (`.bak`) was loaded and written back
+    pub restored_from_backup: bool,
+    /// Where the unreadable file was kept for inspection
+    pub corrupt_copy: Option<String>,
+}
+
+// CodePack: 设置导入/导出包：应用配置（含各项目的密钥基线与策略）、插件和自定义 Review 预设；不含 API 密钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub version: u32,
+    pub exported_at: String,
+    pub app_config: AppConfig,
+    #[serde(default)]
+    pub plugins: Vec<PluginDef>,
+    /// Custom prompts only; built-ins ship with the app
+    #[serde(default)]
+    pub review_prompts: Vec<ReviewPrompt>,
+}
+
+// CodePack: 预设导入/导出文件，路径相对项目根目录，可在其他项目或机器上导入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetBundle {
+    pub version: u32,
+    pub exported_at: String,
+    pub presets: HashMap<String, Preset>,
 }
 
 // CodePack: Review 角色预设
@@ -120,12 +891,53 @@ pub struct ReviewPrompt {
     pub builtin: bool,
 }
 
+// CodePack: generate_change_summary 的结果：可直接粘贴的提示词，以及配置了模型时生成的草稿
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeSummary {
+    /// Diff-only pack with the prompt's instructions appended
+    pub prompt: String,
+    pub estimated_tokens: f64,
+    /// Commit message / PR description drafted by the configured provider; only when requested
+    pub draft: Option<String>,
+}
+
+// CodePack: 文件树 Token 标注进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenAnnotateProgress {
+    pub files_done: u32,
+    pub files_total: u32,
+}
+
+// CodePack: 项目级密钥扫描进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretScanProgress {
+    pub files_done: u32,
+    pub files_total: u32,
+}
+
 // CodePack: 扫描进度事件
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanProgress {
     pub phase: String,
     pub files_found: u32,
     pub message: String,
+    #[serde(default)]
+    pub dirs_found: u32,
+    /// Directory the walker is currently in (only while scanning)
+    #[serde(default)]
+    pub current_dir: Option<String>,
+}
+
+// CodePack: 增量重新扫描结果，只包含变化的节点
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TreeDiff {
+    /// Newly appeared nodes; a new directory is reported once with its whole subtree
+    pub added: Vec<FileNode>,
+    /// Paths that disappeared; a removed directory is reported once
+    pub removed: Vec<String>,
+    /// Files whose size or modification time changed
+    pub modified: Vec<FileNode>,
+    pub total_files: u32,
 }
 
 // CodePack: AI API 配置
@@ -157,6 +969,15 @@ pub struct LangStat {
     pub file_count: u32,
     pub line_count: u64,
     pub byte_count: u64,
+    /// `line_count` split by kind; comment syntax is guessed from the file extension
+    #[serde(default)]
+    pub code_lines: u64,
+    #[serde(default)]
+    pub comment_lines: u64,
+    #[serde(default)]
+    pub blank_lines: u64,
+    #[serde(default)]
+    pub markers: MarkerCounts,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,5 +985,96 @@ pub struct ProjectStats {
     pub total_files: u32,
     pub total_lines: u64,
     pub total_bytes: u64,
+    #[serde(default)]
+    pub total_code_lines: u64,
+    #[serde(default)]
+    pub total_comment_lines: u64,
+    #[serde(default)]
+    pub total_blank_lines: u64,
+    /// Most code lines first
     pub languages: Vec<LangStat>,
+    #[serde(default)]
+    pub markers: MarkerCounts,
+    /// Files with at least one marker, most markers first
+    #[serde(default)]
+    pub marker_files: Vec<FileMarkers>,
+    #[serde(default)]
+    pub top_files: TopFiles,
+}
+
+// CodePack: 选区中最大的文件，分别按字节、行数和估算 token 排名，方便找出该排除的文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopFiles {
+    pub by_bytes: Vec<FileSize>,
+    pub by_lines: Vec<FileSize>,
+    pub by_tokens: Vec<FileSize>,
+    /// False when token counts used the bytes/4 fallback because the BPE was still loading
+    pub tokens_exact: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSize {
+    /// As passed to compute_project_stats
+    pub path: String,
+    pub bytes: u64,
+    pub lines: u64,
+    pub tokens: u64,
+}
+
+// CodePack: TODO / FIXME / HACK / XXX 标记计数，用于发现技术债集中的位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkerCounts {
+    pub todo: u32,
+    pub fixme: u32,
+    pub hack: u32,
+    pub xxx: u32,
+}
+
+impl MarkerCounts {
+    pub fn total(&self) -> u32 {
+        self.todo + self.fixme + self.hack + self.xxx
+    }
+
+    pub fn add(&mut self, other: &MarkerCounts) {
+        self.todo += other.todo;
+        self.fixme += other.fixme;
+        self.hack += other.hack;
+        self.xxx += other.xxx;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMarkers {
+    /// As passed to compute_project_stats
+    pub path: String,
+    pub markers: MarkerCounts,
+}
+
+// CodePack: 单个文件在统计窗口内的改动热度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChurn {
+    /// Path relative to the project root
+    pub path: String,
+    pub commits: u32,
+    pub authors: u32,
+    /// `YYYY-MM-DD` of the most recent commit touching the file
+    pub last_changed: String,
+}
+
+// CodePack: 最近 N 个月的 git 改动热度报告，files 按提交数降序
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChurnStats {
+    pub months: u32,
+    pub total_commits: u32,
+    pub files: Vec<FileChurn>,
+}
+
+// CodePack: 贡献者统计（按 mailmap 合并同一作者的不同身份）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contributor {
+    pub name: String,
+    pub email: String,
+    pub commits: u32,
+    /// `YYYY-MM-DD` of the author's most recent commit
+    pub last_active: String,
 }
@@ -0,0 +1,421 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+#[cfg(feature = "wasm-plugins")]
+use std::path::{Component, PathBuf};
+
+#[cfg(feature = "wasm-plugins")]
+use crate::paths;
+use crate::plugins::PluginSecretRule;
+use crate::types::ProjectMetadata;
+
+// CodePack: WASM 插件运行时（需启用 `wasm-plugins` feature）。插件是沙箱中的真实代码，
+// 只能通过宿主 API 读取项目根目录下的文件，结果以 JSON 返回。
+//
+// 模块约定：
+// - 导出 `memory` 与 `alloc(len: i32) -> i32`
+// - 可选导出钩子 `detect` / `metadata` / `secret_rules`，签名 `(input_ptr: i32, input_len: i32) -> i64`，
+//   返回 `(ptr << 32) | len` 指向 UTF-8 JSON；返回 0 表示没有结果
+// - 宿主导入 `codepack.read_file(path_ptr: i32, path_len: i32) -> i64`：路径相对根目录，
+//   返回值打包方式相同；文件不存在、越出根目录或过大时为 -1
+
+/// Files handed to a plugin through `read_file` are capped at this size
+#[cfg(feature = "wasm-plugins")]
+const MAX_READ_BYTES: usize = 4 * 1024 * 1024;
+/// Largest JSON result a hook may return
+#[cfg(feature = "wasm-plugins")]
+const MAX_RESULT_BYTES: usize = 1024 * 1024;
+
+pub const HOOK_DETECT: &str = "detect";
+pub const HOOK_METADATA: &str = "metadata";
+pub const HOOK_SECRET_RULES: &str = "secret_rules";
+
+/// Result of the `detect` hook
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WasmDetection {
+    pub matches: bool,
+    /// Files or directories that led to the match, shown as detection evidence
+    #[serde(default)]
+    pub evidence: Vec<String>,
+}
+
+// CodePack: `metadata` 钩子的结果；只补充内置解析器没有得到的字段，列表去重追加
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WasmMetadata {
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub entry_point: Option<String>,
+    #[serde(default)]
+    pub frameworks: Vec<String>,
+    #[serde(default)]
+    pub runtime: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub dev_dependencies: Vec<String>,
+}
+
+impl WasmMetadata {
+    pub fn apply(self, meta: &mut ProjectMetadata) {
+        meta.version = meta.version.take().or(self.version);
+        meta.description = meta.description.take().or(self.description);
+        meta.license = meta.license.take().or(self.license);
+        meta.entry_point = meta.entry_point.take().or(self.entry_point);
+        for (target, extra) in [
+            (&mut meta.frameworks, self.frameworks),
+            (&mut meta.runtime, self.runtime),
+            (&mut meta.dependencies, self.dependencies),
+            (&mut meta.dev_dependencies, self.dev_dependencies),
+        ] {
+            for item in extra {
+                if !target.contains(&item) {
+                    target.push(item);
+                }
+            }
+        }
+    }
+}
+
+/// `relative` inside `root`, following symlinks; None for absolute paths, `..` segments
+/// or anything that resolves outside the root
+#[cfg(feature = "wasm-plugins")]
+fn resolve_under_root(root: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    let root = paths::canonicalize(root).ok()?;
+    let path = paths::canonicalize(&root.join(relative)).ok()?;
+    (path.starts_with(&root) && path.is_file()).then_some(path)
+}
+
+fn hook_input(root: &Path) -> String {
+    let name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    serde_json::json!({ "project_name": name }).to_string()
+}
+
+fn call_json<T: DeserializeOwned>(module: &Path, hook: &str, root: &Path) -> Result<Option<T>, String> {
+    let Some(output) = runtime::call_hook(module, hook, root, &hook_input(root))? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&output)
+        .map(Some)
+        .map_err(|e| format!("Invalid result from '{}' hook: {}", hook, e))
+}
+
+/// None when the module has no `detect` hook, so the plugin's static rules apply instead
+pub fn detect(module: &Path, root: &Path) -> Result<Option<WasmDetection>, String> {
+    call_json(module, HOOK_DETECT, root)
+}
+
+pub fn metadata(module: &Path, root: &Path) -> Result<Option<WasmMetadata>, String> {
+    call_json(module, HOOK_METADATA, root)
+}
+
+/// `root` is the directory the module may read from, e.g. the plugins directory
+pub fn secret_rules(module: &Path, root: &Path) -> Result<Vec<PluginSecretRule>, String> {
+    Ok(call_json(module, HOOK_SECRET_RULES, root)?.unwrap_or_default())
+}
+
+/// Whether the module can be loaded in this build, without running any hook
+pub fn check_module(module: &Path) -> Result<(), String> {
+    runtime::check_module(module)
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod runtime {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, OnceLock};
+    use std::time::SystemTime;
+
+    use wasmtime::{
+        AsContext, AsContextMut, Caller, Config, Engine, Linker, Memory, Module, Store, StoreLimits,
+        StoreLimitsBuilder, TypedFunc,
+    };
+
+    use super::{resolve_under_root, MAX_READ_BYTES, MAX_RESULT_BYTES};
+
+    /// Instructions a single hook call may execute before it is aborted
+    const FUEL: u64 = 500_000_000;
+    const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+    struct HostState {
+        root: PathBuf,
+        limits: StoreLimits,
+    }
+
+    fn engine() -> Result<&'static Engine, String> {
+        static ENGINE: OnceLock<Result<Engine, String>> = OnceLock::new();
+        ENGINE
+            .get_or_init(|| {
+                let mut config = Config::new();
+                config.consume_fuel(true);
+                Engine::new(&config).map_err(|e| format!("Failed to start WASM runtime: {}", e))
+            })
+            .as_ref()
+            .map_err(|e| e.clone())
+    }
+
+    /// Compiled module for `path`, reused until the file's modification time changes
+    fn load_module(engine: &Engine, path: &Path) -> Result<Module, String> {
+        static MODULES: OnceLock<Mutex<HashMap<PathBuf, (SystemTime, Module)>>> = OnceLock::new();
+        let modified = fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+        let modules = MODULES.get_or_init(Default::default);
+        let cached = modules.lock().ok().and_then(|modules| modules.get(path).cloned());
+        if let Some((_, module)) = cached.filter(|(stamp, _)| *stamp == modified) {
+            return Ok(module);
+        }
+        let module = Module::from_file(engine, path)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+        if let Ok(mut modules) = modules.lock() {
+            modules.insert(path.to_path_buf(), (modified, module.clone()));
+        }
+        Ok(module)
+    }
+
+    pub fn check_module(module_path: &Path) -> Result<(), String> {
+        load_module(engine()?, module_path).map(|_| ())
+    }
+
+    fn pack(ptr: i32, len: i32) -> i64 {
+        ((ptr as u32 as i64) << 32) | len as u32 as i64
+    }
+
+    fn write_guest(
+        mut store: impl AsContextMut,
+        memory: &Memory,
+        alloc: &TypedFunc<i32, i32>,
+        bytes: &[u8],
+    ) -> Result<i64, String> {
+        let len = i32::try_from(bytes.len()).map_err(|_| "Buffer too large for plugin".to_string())?;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|e| format!("Plugin alloc failed: {}", e))?;
+        memory
+            .write(&mut store, ptr as u32 as usize, bytes)
+            .map_err(|e| format!("Failed to write plugin memory: {}", e))?;
+        Ok(pack(ptr, len))
+    }
+
+    fn read_guest(
+        store: impl AsContext,
+        memory: &Memory,
+        packed: i64,
+        limit: usize,
+    ) -> Result<Vec<u8>, String> {
+        let ptr = (packed >> 32) as u32 as usize;
+        let len = (packed & 0xffff_ffff) as usize;
+        if len > limit {
+            return Err(format!("Plugin returned {} bytes, more than {}KB", len, limit / 1024));
+        }
+        let mut buffer = vec![0; len];
+        memory
+            .read(&store, ptr, &mut buffer)
+            .map_err(|e| format!("Failed to read plugin memory: {}", e))?;
+        Ok(buffer)
+    }
+
+    // CodePack: 宿主 API：只允许读取根目录下的文件
+    fn host_read_file(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Option<i64> {
+        let memory = caller.get_export("memory")?.into_memory()?;
+        let alloc = caller
+            .get_export("alloc")?
+            .into_func()?
+            .typed::<i32, i32>(&caller)
+            .ok()?;
+        let request = read_guest(&caller, &memory, pack(ptr, len), 4096).ok()?;
+        let relative = String::from_utf8(request).ok()?;
+        let path = resolve_under_root(&caller.data().root, &relative)?;
+        if fs::metadata(&path).ok()?.len() > MAX_READ_BYTES as u64 {
+            return None;
+        }
+        let data = fs::read(&path).ok()?;
+        write_guest(caller, &memory, &alloc, &data).ok()
+    }
+
+    pub fn call_hook(
+        module_path: &Path,
+        hook: &str,
+        root: &Path,
+        input: &str,
+    ) -> Result<Option<String>, String> {
+        let engine = engine()?;
+        let module = load_module(engine, module_path)?;
+        if module.get_export(hook).is_none() {
+            return Ok(None);
+        }
+
+        let state = HostState {
+            root: root.to_path_buf(),
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build(),
+        };
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL).map_err(|e| e.to_string())?;
+
+        let mut linker = Linker::new(engine);
+        linker
+            .func_wrap("codepack", "read_file", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+                host_read_file(&mut caller, ptr, len).unwrap_or(-1)
+            })
+            .map_err(|e| e.to_string())?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| format!("Failed to instantiate plugin: {}", e))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| "Plugin does not export memory".to_string())?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(|e| format!("Plugin does not export alloc: {}", e))?;
+        let func = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, hook)
+            .map_err(|e| format!("Plugin hook '{}' has the wrong signature: {}", hook, e))?;
+
+        let input = write_guest(&mut store, &memory, &alloc, input.as_bytes())?;
+        let packed = func
+            .call(&mut store, ((input >> 32) as i32, input as i32))
+            .map_err(|e| format!("Plugin hook '{}' failed: {}", hook, e))?;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let output = read_guest(&store, &memory, packed, MAX_RESULT_BYTES)?;
+        String::from_utf8(output)
+            .map(Some)
+            .map_err(|_| format!("Plugin hook '{}' returned invalid UTF-8", hook))
+    }
+}
+
+#[cfg(not(feature = "wasm-plugins"))]
+mod runtime {
+    use std::path::Path;
+
+    pub fn call_hook(
+        module_path: &Path,
+        _hook: &str,
+        _root: &Path,
+        _input: &str,
+    ) -> Result<Option<String>, String> {
+        check_module(module_path).map(|_| None)
+    }
+
+    pub fn check_module(module_path: &Path) -> Result<(), String> {
+        Err(format!(
+            "{}: WASM plugins are not supported in this build (enable the wasm-plugins feature); only the static rules apply",
+            module_path.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "wasm-plugins")]
+    use std::fs;
+    #[cfg(feature = "wasm-plugins")]
+    use tempfile::TempDir;
+
+    #[cfg(feature = "wasm-plugins")]
+    #[test]
+    fn test_resolve_under_root() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/manifest.json"), "{}").unwrap();
+        assert!(resolve_under_root(dir.path(), "src/manifest.json").is_some());
+        assert!(resolve_under_root(dir.path(), "./src/manifest.json").is_some());
+        assert!(resolve_under_root(dir.path(), "src").is_none());
+        assert!(resolve_under_root(dir.path(), "missing.json").is_none());
+        assert!(resolve_under_root(dir.path(), "../etc/passwd").is_none());
+        assert!(resolve_under_root(dir.path(), "/etc/passwd").is_none());
+    }
+
+    #[test]
+    fn test_wasm_metadata_fills_gaps() {
+        let mut meta: ProjectMetadata = serde_json::from_str(
+            "{\"name\": \"hacs\", \"project_type\": \"Python\", \"version\": \"1.0.0\", \"dependencies\": [], \"dev_dependencies\": [], \"frameworks\": [\"Home Assistant\"]}",
+        )
+        .unwrap();
+        let extra: WasmMetadata = serde_json::from_str(
+            "{\"version\": \"2.0.0\", \"license\": \"MIT\", \"frameworks\": [\"Home Assistant\", \"aiohttp\"]}",
+        )
+        .unwrap();
+        extra.apply(&mut meta);
+        assert_eq!(meta.version.as_deref(), Some("1.0.0"));
+        assert_eq!(meta.license.as_deref(), Some("MIT"));
+        assert_eq!(meta.frameworks, vec!["Home Assistant", "aiohttp"]);
+    }
+
+    #[test]
+    fn test_secret_rule_defaults() {
+        let rule: PluginSecretRule =
+            serde_json::from_str("{\"description\": \"Acme token\", \"pattern\": \"acme_[a-z0-9]{32}\"}").unwrap();
+        assert_eq!(rule.severity, crate::types::Severity::Medium);
+    }
+
+    /// `detect` matches when `marker.txt` can be read, `metadata` returns `meta.json` as read
+    /// through the host, and `secret_rules` spins until its fuel runs out
+    #[cfg(feature = "wasm-plugins")]
+    const PROBE_WAT: &str = r##"
+(module
+  (import "codepack" "read_file" (func $read_file (param i32 i32) (result i64)))
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (data (i32.const 0) "marker.txt")
+  (data (i32.const 16) "meta.json")
+  (data (i32.const 32) "{\"matches\":true,\"evidence\":[\"marker.txt\"]}")
+  (data (i32.const 128) "{\"matches\":false}")
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+  (func (export "detect") (param i32 i32) (result i64)
+    (if (result i64) (i64.eq (call $read_file (i32.const 0) (i32.const 10)) (i64.const -1))
+      (then (i64.or (i64.shl (i64.const 128) (i64.const 32)) (i64.const 17)))
+      (else (i64.or (i64.shl (i64.const 32) (i64.const 32)) (i64.const 42)))))
+  (func (export "metadata") (param i32 i32) (result i64)
+    (call $read_file (i32.const 16) (i32.const 9)))
+  (func (export "secret_rules") (param i32 i32) (result i64)
+    (loop $spin (br $spin))
+    (i64.const 0)))
+"##;
+
+    #[cfg(feature = "wasm-plugins")]
+    #[test]
+    fn test_wasm_hooks() {
+        let plugins = TempDir::new().unwrap();
+        let module = plugins.path().join("probe.wasm");
+        fs::write(&module, PROBE_WAT).unwrap();
+        assert!(check_module(&module).is_ok());
+
+        let project = TempDir::new().unwrap();
+        assert!(!detect(&module, project.path()).unwrap().unwrap().matches);
+        fs::write(project.path().join("marker.txt"), "").unwrap();
+        let detection = detect(&module, project.path()).unwrap().unwrap();
+        assert!(detection.matches);
+        assert_eq!(detection.evidence, vec!["marker.txt"]);
+
+        fs::write(project.path().join("meta.json"), "{\"version\": \"9.9.9\", \"frameworks\": [\"probe\"]}").unwrap();
+        let meta = metadata(&module, project.path()).unwrap().unwrap();
+        assert_eq!(meta.version.as_deref(), Some("9.9.9"));
+        assert_eq!(meta.frameworks, vec!["probe"]);
+
+        let err = secret_rules(&module, project.path()).unwrap_err();
+        assert!(err.contains("failed"), "{}", err);
+    }
+}
@@ -5,6 +5,8 @@ use std::time::Duration;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher, EventKind};
 use tauri::{AppHandle, Emitter, Manager};
 
+use crate::security::SecretCacheState;
+
 // ─── State ─────────────────────────────────────────────────────
 
 pub struct WatcherState {
@@ -33,6 +35,8 @@ pub fn start_watching(app: &AppHandle, project_path: &str) -> Result<(), String>
 
     // Stop existing watcher if any
     *guard = None;
+    // Changes made while nothing was watching were never reported
+    app.state::<SecretCacheState>().clear();
 
     let app_handle = app.clone();
     let path = project_path.to_string();
@@ -40,6 +44,7 @@ pub fn start_watching(app: &AppHandle, project_path: &str) -> Result<(), String>
     let mut watcher = RecommendedWatcher::new(
         move |res: Result<notify::Event, notify::Error>| {
             if let Ok(event) = res {
+                app_handle.state::<SecretCacheState>().invalidate(&event.paths);
                 match event.kind {
                     EventKind::Create(_)
                     | EventKind::Remove(_)
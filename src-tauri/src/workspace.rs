@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::scanner::build_file_tree_limited;
+use crate::types::{FileNode, ScanOptions, WorkspaceRoot};
+
+// ─── Roots ─────────────────────────────────────────────────────
+
+/// Labels each root with its folder name, suffixing `-2`, `-3`... when two roots share a name
+pub fn root_labels(roots: &[String]) -> Vec<WorkspaceRoot> {
+    let mut used: HashMap<String, u32> = HashMap::new();
+    roots
+        .iter()
+        .map(|root| {
+            let base = Path::new(root)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| root.clone());
+            let count = used.entry(base.clone()).or_insert(0);
+            *count += 1;
+            let label = if *count == 1 { base } else { format!("{}-{}", base, count) };
+            WorkspaceRoot {
+                label,
+                path: root.clone(),
+            }
+        })
+        .collect()
+}
+
+pub fn validate_roots(roots: &[String]) -> Result<(), String> {
+    if roots.is_empty() {
+        return Err("Workspace needs at least one root folder".to_string());
+    }
+    for root in roots {
+        if !Path::new(root).is_dir() {
+            return Err(format!("Workspace root is not a directory: {}", root));
+        }
+    }
+    Ok(())
+}
+
+// ─── Virtual Tree ──────────────────────────────────────────────
+
+/// Scans every root and hangs them under one virtual node named after the workspace.
+/// Each root keeps its real path but is displayed under its label, and is scanned with the
+/// options `options_for` gives it (so per-project rules apply to each member)
+pub fn build_workspace_tree(
+    name: &str,
+    roots: &[WorkspaceRoot],
+    options_for: impl Fn(&Path) -> ScanOptions,
+) -> (FileNode, bool) {
+    let mut truncated = false;
+    let mut children = Vec::new();
+    for root in roots {
+        let root_path = Path::new(&root.path);
+        let (mut tree, root_truncated) = build_file_tree_limited(root_path, &options_for(root_path));
+        truncated |= root_truncated;
+        tree.name = root.label.clone();
+        children.push(tree);
+    }
+
+    let node = FileNode {
+        name: name.to_string(),
+        path: format!("workspace:{}", name),
+        is_dir: true,
+        checked: true,
+        size_bytes: children.iter().map(|c| c.size_bytes).sum(),
+        modified_at: children.iter().filter_map(|c| c.modified_at).max(),
+        children,
+        ..Default::default()
+    };
+    (node, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::count_files;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_root_labels_dedupe() {
+        let roots = vec![
+            "/work/api".to_string(),
+            "/other/api".to_string(),
+            "/work/web".to_string(),
+        ];
+        let labels: Vec<String> = root_labels(&roots).into_iter().map(|r| r.label).collect();
+        assert_eq!(labels, vec!["api", "api-2", "web"]);
+    }
+
+    #[test]
+    fn test_validate_roots() {
+        let dir = TempDir::new().unwrap();
+        assert!(validate_roots(&[]).is_err());
+        assert!(validate_roots(&[dir.path().to_string_lossy().to_string()]).is_ok());
+        assert!(validate_roots(&[dir.path().join("missing").to_string_lossy().to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_build_workspace_tree() {
+        let api = TempDir::new().unwrap();
+        let web = TempDir::new().unwrap();
+        fs::write(api.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::write(web.path().join("index.ts"), "export {}").unwrap();
+        fs::write(web.path().join("app.vue"), "<template />").unwrap();
+
+        let roots = root_labels(&[
+            api.path().to_string_lossy().to_string(),
+            web.path().to_string_lossy().to_string(),
+        ]);
+        let (tree, truncated) = build_workspace_tree("full-stack", &roots, |_| ScanOptions::default());
+        assert!(!truncated);
+        assert_eq!(tree.name, "full-stack");
+        assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.children[0].name, roots[0].label);
+        assert_eq!(count_files(&tree), 3);
+    }
+}